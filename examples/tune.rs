@@ -0,0 +1,309 @@
+//! Sweeps match thresholds and scale ranges over labelled avatar/screenshot pairs,
+//! printing precision and recall for each so `DetectionParams` can be chosen without
+//! recompiling the library
+//!
+//! ```text
+//! cargo run --example tune -- [FIXTURES_DIR] [--thresholds FROM:TO:STEP] [--scales MIN:MAX:STEPS]...
+//! ```
+//!
+//! `FIXTURES_DIR` (default `tests/fixtures/tuning`) holds a `positive` directory of pairs
+//! where the avatar is in the screenshot and a `negative` one of pairs where it isn't.
+//! Each pair is `<name>.needle.png` and `<name>.haystack.png`. Needles are matched through
+//! a circular mask, as the bot does with avatars. No Discord token is needed.
+
+use anyhow::{Context, Result, bail};
+use opencv::core::Mat;
+use std::path::{Path, PathBuf};
+use wordle_timer_bot::detection::{DetectionParams, detect_needle_in_haystack};
+use wordle_timer_bot::read_image;
+
+const DEFAULT_FIXTURES: &str = "tests/fixtures/tuning";
+const DEFAULT_THRESHOLDS: Thresholds = Thresholds {
+    from: 0.5,
+    to: 1.0,
+    step: 0.05,
+};
+const DEFAULT_SCALES: [Scales; 3] = [
+    Scales {
+        min: 0.6,
+        max: 1.4,
+        steps: 100,
+    },
+    Scales {
+        min: 0.8,
+        max: 1.2,
+        steps: 40,
+    },
+    Scales {
+        min: 0.9,
+        max: 1.1,
+        steps: 20,
+    },
+];
+
+/// The thresholds tried, from `from` up to `to` inclusive
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
+    from: f64,
+    to: f64,
+    step: f64,
+}
+
+impl Thresholds {
+    fn values(&self) -> Vec<f64> {
+        let count = ((self.to - self.from) / self.step + 1e-9).floor() as usize;
+        (0..=count)
+            .map(|i| self.from + i as f64 * self.step)
+            .collect()
+    }
+}
+
+/// A scale range searched, as in [`DetectionParams`]
+#[derive(Debug, Clone, Copy)]
+struct Scales {
+    min: f64,
+    max: f64,
+    steps: usize,
+}
+
+impl std::fmt::Display for Scales {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2}-{:.2}/{}", self.min, self.max, self.steps)
+    }
+}
+
+/// A labelled avatar and screenshot
+struct Pair {
+    name: String,
+    needle: Mat,
+    haystack: Mat,
+}
+
+/// How a threshold fared over every pair
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    true_positives: usize,
+    false_positives: usize,
+    false_negatives: usize,
+}
+
+impl Tally {
+    fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    fn f1(&self) -> f64 {
+        ratio(
+            2 * self.true_positives,
+            2 * self.true_positives + self.false_positives + self.false_negatives,
+        )
+    }
+}
+
+/// `part / whole`, or 1.0 when there is nothing to get wrong
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        1.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn main() -> Result<()> {
+    let mut fixtures = PathBuf::from(DEFAULT_FIXTURES);
+    let mut thresholds = DEFAULT_THRESHOLDS;
+    let mut scales = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--thresholds" => {
+                let value = args.next().context("--thresholds needs FROM:TO:STEP")?;
+                thresholds = parse_thresholds(&value)?;
+            }
+            "--scales" => {
+                let value = args.next().context("--scales needs MIN:MAX:STEPS")?;
+                scales.push(parse_scales(&value)?);
+            }
+            "-h" | "--help" => {
+                println!(
+                    "Usage: tune [FIXTURES_DIR] [--thresholds FROM:TO:STEP] [--scales MIN:MAX:STEPS]..."
+                );
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => bail!("Unknown option {arg}"),
+            _ => fixtures = PathBuf::from(arg),
+        }
+    }
+    if scales.is_empty() {
+        scales = DEFAULT_SCALES.to_vec();
+    }
+
+    let positives = load_pairs(&fixtures.join("positive"))?;
+    let negatives = load_pairs(&fixtures.join("negative"))?;
+    if positives.is_empty() {
+        bail!("No positive pairs in {}", fixtures.display());
+    }
+    println!(
+        "{} positive and {} negative pair(s) from {}",
+        positives.len(),
+        negatives.len(),
+        fixtures.display()
+    );
+
+    let mut best: Option<(Scales, f64, Tally)> = None;
+    for scales in scales {
+        // The best score of each pair decides every threshold, so search once per range
+        let positive_scores = best_scores(&positives, scales)?;
+        let negative_scores = best_scores(&negatives, scales)?;
+        for (pair, score) in positives.iter().zip(&positive_scores) {
+            println!("  {scales} positive {}: {score:.4}", pair.name);
+        }
+        for (pair, score) in negatives.iter().zip(&negative_scores) {
+            println!("  {scales} negative {}: {score:.4}", pair.name);
+        }
+
+        println!();
+        println!(
+            "{:<16} {:>9} {:>4} {:>4} {:>4} {:>9} {:>7} {:>6}",
+            "scales", "threshold", "tp", "fp", "fn", "precision", "recall", "f1"
+        );
+        for threshold in thresholds.values() {
+            let tally = tally(&positive_scores, &negative_scores, threshold);
+            println!(
+                "{:<16} {:>9.3} {:>4} {:>4} {:>4} {:>9.3} {:>7.3} {:>6.3}",
+                scales.to_string(),
+                threshold,
+                tally.true_positives,
+                tally.false_positives,
+                tally.false_negatives,
+                tally.precision(),
+                tally.recall(),
+                tally.f1()
+            );
+            // Ties keep the earlier, lower threshold, favouring recall
+            if best.is_none_or(|(_, _, best)| tally.f1() > best.f1()) {
+                best = Some((scales, threshold, tally));
+            }
+        }
+        println!();
+    }
+
+    if let Some((scales, threshold, tally)) = best {
+        println!(
+            "Best: scales {scales}, threshold {threshold:.3} (precision {:.3}, recall {:.3}, f1 {:.3})",
+            tally.precision(),
+            tally.recall(),
+            tally.f1()
+        );
+    }
+
+    Ok(())
+}
+
+/// Parses `FROM:TO:STEP`
+fn parse_thresholds(value: &str) -> Result<Thresholds> {
+    let parts = split::<3>(value)?;
+    let thresholds = Thresholds {
+        from: parts[0].parse()?,
+        to: parts[1].parse()?,
+        step: parts[2].parse()?,
+    };
+    if thresholds.step <= 0.0 || thresholds.to < thresholds.from {
+        bail!("Thresholds {value} don't go anywhere");
+    }
+    Ok(thresholds)
+}
+
+/// Parses `MIN:MAX:STEPS`
+fn parse_scales(value: &str) -> Result<Scales> {
+    let parts = split::<3>(value)?;
+    let scales = Scales {
+        min: parts[0].parse()?,
+        max: parts[1].parse()?,
+        steps: parts[2].parse()?,
+    };
+    if scales.min <= 0.0 || scales.max < scales.min {
+        bail!("Scales {value} aren't a range of positive scales");
+    }
+    Ok(scales)
+}
+
+fn split<const N: usize>(value: &str) -> Result<[&str; N]> {
+    let parts: Vec<&str> = value.split(':').collect();
+    parts
+        .try_into()
+        .ok()
+        .with_context(|| format!("Expected {N} values separated by ':' in {value}"))
+}
+
+/// Every `<name>.needle.png` in `dir` with its `<name>.haystack.png`, sorted by name
+fn load_pairs(dir: &Path) -> Result<Vec<Pair>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut pairs = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Reading {}", dir.display()))? {
+        let path = entry?.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".needle.png"))
+        else {
+            continue;
+        };
+
+        let haystack_path = dir.join(format!("{name}.haystack.png"));
+        let needle = read_image(&path.display().to_string())?;
+        let haystack = read_image(&haystack_path.display().to_string())
+            .with_context(|| format!("{name} has no readable haystack"))?;
+        pairs.push(Pair {
+            name: name.to_string(),
+            needle,
+            haystack,
+        });
+    }
+
+    pairs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(pairs)
+}
+
+/// The confidence of each pair's best match over `scales`, or -1.0 if nothing matched
+fn best_scores(pairs: &[Pair], scales: Scales) -> Result<Vec<f64>> {
+    let params = DetectionParams {
+        min_scale: scales.min,
+        max_scale: scales.max,
+        scale_steps: scales.steps,
+        threshold: -1.0, // The lowest TM_CCOEFF_NORMED score, so every best match is kept
+        circular: true,
+        ..Default::default()
+    };
+
+    pairs
+        .iter()
+        .map(|pair| {
+            let matches = detect_needle_in_haystack(&pair.needle, &pair.haystack, &params)?;
+            Ok(matches.first().map_or(-1.0, |(_, confidence)| *confidence))
+        })
+        .collect()
+}
+
+fn tally(positive_scores: &[f64], negative_scores: &[f64], threshold: f64) -> Tally {
+    let true_positives = positive_scores.iter().filter(|s| **s >= threshold).count();
+    Tally {
+        true_positives,
+        false_positives: negative_scores.iter().filter(|s| **s >= threshold).count(),
+        false_negatives: positive_scores.len() - true_positives,
+    }
+}
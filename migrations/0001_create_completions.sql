@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS completions (
+    user_id INTEGER NOT NULL,
+    guild_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    active_time_ms INTEGER NOT NULL,
+    guess_count INTEGER,
+    outcome TEXT NOT NULL,
+    PRIMARY KEY (user_id, guild_id, date)
+);
+
+CREATE INDEX IF NOT EXISTS completions_guild_date ON completions (guild_id, date);
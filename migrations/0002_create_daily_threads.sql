@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS daily_threads (
+    channel_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    thread_id INTEGER NOT NULL,
+    PRIMARY KEY (channel_id, date)
+);
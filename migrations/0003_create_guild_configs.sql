@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS guild_configs (
+    guild_id INTEGER PRIMARY KEY NOT NULL,
+    channel_id INTEGER,
+    channel_name TEXT,
+    timezone TEXT,
+    embed_title TEXT,
+    embed_footer TEXT,
+    embed_colour INTEGER
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS opt_outs (
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
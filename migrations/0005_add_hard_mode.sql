@@ -0,0 +1 @@
+ALTER TABLE completions ADD COLUMN hard_mode INTEGER NOT NULL DEFAULT 0;
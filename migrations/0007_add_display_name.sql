@@ -0,0 +1 @@
+ALTER TABLE completions ADD COLUMN display_name TEXT;
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use crate::format_duration;
+
+/// The day's fastest solvers out of its leaderboard, as `(user_id, active_time)`: every
+/// player tied on the quickest time, in leaderboard order
+pub fn fastest_solvers(leaderboard: &[(u64, Duration)]) -> Vec<(u64, Duration)> {
+    let Some(fastest) = leaderboard.iter().map(|(_, time)| *time).min() else {
+        return Vec::new();
+    };
+    leaderboard
+        .iter()
+        .filter(|(_, time)| *time == fastest)
+        .copied()
+        .collect()
+}
+
+/// The text of a "fastest today" announcement, naming every tied solver
+pub fn fastest_description(fastest: &[(u64, Duration)]) -> String {
+    match fastest {
+        [] => "Nobody solved today's Wordle.".to_string(),
+        [(user_id, time)] => format!(
+            "<@{user_id}> solved today's Wordle fastest, in {}!",
+            format_duration(*time)
+        ),
+        [(_, time), ..] => {
+            let mentions: Vec<String> = fastest
+                .iter()
+                .map(|(user_id, _)| format!("<@{user_id}>"))
+                .collect();
+            format!(
+                "Tied for today's fastest solve, in {}: {}",
+                format_duration(*time),
+                mentions.join(", ")
+            )
+        }
+    }
+}
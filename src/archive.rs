@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::error::{Result, WordleError};
+
+/// What detection made of an archived screenshot, saved beside it as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub source_url: String,
+    pub archived_at: String,         // RFC 3339
+    pub players: Vec<PlayerOutcome>, // Every player searched for, found or not
+}
+
+/// Whether a single player's avatar was found in the screenshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerOutcome {
+    pub uid: usize,
+    pub found: bool,
+}
+
+/// Keeps copies of processed screenshots for re-analysis when tuning detection
+///
+/// Downloads are named after the last URL segment and get overwritten, so each
+/// screenshot is copied into `archive/YYYY-MM-DD/` under the data dir instead.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+impl Archive {
+    /// An archive in the `archive` directory of `data_dir`
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join("archive"),
+        }
+    }
+
+    /// Copies the image at `image_path` into the day's directory with the players'
+    /// outcomes beside it, returning where the copy went
+    pub async fn save(
+        &self,
+        image_path: &str,
+        players: Vec<PlayerOutcome>,
+        source_url: &str,
+        at: DateTime<Utc>,
+    ) -> Result<PathBuf> {
+        let day_dir = self.dir.join(at.format("%Y-%m-%d").to_string());
+        let archive_error = |path: &Path, source: std::io::Error| WordleError::Archive {
+            path: path.display().to_string(),
+            source,
+        };
+
+        // Screenshots are often all called image.png, so prefix the time to keep them apart
+        let file_name = Path::new(image_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('?').next())
+            .unwrap_or("screenshot");
+        let copy_path = day_dir.join(format!("{}-{}", at.format("%H%M%S%3f"), file_name));
+        let sidecar_path = copy_path.with_file_name(format!(
+            "{}.json",
+            copy_path.file_name().unwrap_or_default().to_string_lossy()
+        ));
+
+        fs::create_dir_all(&day_dir)
+            .await
+            .map_err(|why| archive_error(&day_dir, why))?;
+        fs::copy(image_path, &copy_path)
+            .await
+            .map_err(|why| archive_error(&copy_path, why))?;
+
+        let record = ArchiveRecord {
+            source_url: source_url.to_string(),
+            archived_at: at.to_rfc3339(),
+            players,
+        };
+        let json = serde_json::to_vec_pretty(&record)
+            .map_err(|why| archive_error(&sidecar_path, why.into()))?;
+        fs::write(&sidecar_path, json)
+            .await
+            .map_err(|why| archive_error(&sidecar_path, why))?;
+
+        Ok(copy_path)
+    }
+
+    /// Writes a screenshot annotated with its detection into the day's directory,
+    /// returning where it went
+    pub async fn save_annotated(
+        &self,
+        png: &[u8],
+        source_url: &str,
+        at: DateTime<Utc>,
+    ) -> Result<PathBuf> {
+        let day_dir = self.dir.join(at.format("%Y-%m-%d").to_string());
+        let archive_error = |path: &Path, source: std::io::Error| WordleError::Archive {
+            path: path.display().to_string(),
+            source,
+        };
+
+        let file_name = source_url
+            .split('?')
+            .next()
+            .and_then(|url| url.rsplit('/').next())
+            .and_then(|name| Path::new(name).file_stem())
+            .and_then(|stem| stem.to_str())
+            .filter(|stem| !stem.is_empty())
+            .unwrap_or("screenshot");
+        let path = day_dir.join(format!(
+            "{}-{}-annotated.png",
+            at.format("%H%M%S%3f"),
+            file_name
+        ));
+
+        fs::create_dir_all(&day_dir)
+            .await
+            .map_err(|why| archive_error(&day_dir, why))?;
+        fs::write(&path, png)
+            .await
+            .map_err(|why| archive_error(&path, why))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,1614 @@
+use log::{error, info};
+use serenity::all::{
+    ButtonStyle, Colour, CommandInteraction, CommandOptionType, ComponentInteraction,
+    CreateActionRow, CreateAttachment, CreateButton, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponse, CreateInteractionResponseMessage,
+    EditInteractionResponse, GuildId, Permissions, ResolvedValue, User, UserId,
+};
+use serenity::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+use wordle_timer_bot::config::{GuildConfig, parse_hex_colour, parse_timezone};
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::game::{
+    GameState, StartedAt, WordlePuzzles, is_players_game, set_guild_timezone, summarize_games,
+};
+use wordle_timer_bot::history::{
+    Completion, History, ImportReport, MIN_PERCENTILE_SAMPLES, completions_to_csv,
+    leaderboard_page, resolve_display_name,
+};
+use wordle_timer_bot::locale::fill;
+use wordle_timer_bot::overrides::AvatarOverrides;
+use wordle_timer_bot::reminders::ReminderTime;
+use wordle_timer_bot::{DownloadLimiter, MarkerTemplate, TrackedChannel};
+use wordle_timer_bot::{
+    completion_description_in, completion_embed, detection_summary, format_duration,
+    format_duration_in, is_image_attachment, parse_duration, simulate_detection,
+    start_description_in,
+};
+
+/// A slash command's name, description and who may use it, as registered and listed
+/// by /help
+struct CommandInfo {
+    name: &'static str,
+    description: &'static str,
+    permissions: Option<Permissions>, // Members need these to see and use the command
+}
+
+/// Every slash command the bot has, the one place they are described
+const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "progress",
+        description: "Show who has finished today's Wordle and who is still playing",
+        permissions: None,
+    },
+    CommandInfo {
+        name: "leaderboard",
+        description: "Show the server's Wordle rankings",
+        permissions: None,
+    },
+    CommandInfo {
+        name: "when",
+        description: "Show when someone started today's Wordle",
+        permissions: None,
+    },
+    CommandInfo {
+        name: "reset",
+        description: "Clear a user's tracked Wordle game",
+        permissions: Some(Permissions::MANAGE_MESSAGES),
+    },
+    CommandInfo {
+        name: "record",
+        description: "Record a solve time for someone the bot couldn't track",
+        permissions: Some(Permissions::MANAGE_MESSAGES),
+    },
+    CommandInfo {
+        name: "simulate",
+        description: "Run completion detection on a screenshot and show what was found",
+        permissions: Some(Permissions::MANAGE_MESSAGES),
+    },
+    CommandInfo {
+        name: "set-avatar",
+        description: "Set the image a user's avatar is looked for as in screenshots",
+        permissions: Some(Permissions::MANAGE_MESSAGES),
+    },
+    CommandInfo {
+        name: "status",
+        description: "Show how the bot's tracking is doing",
+        permissions: Some(Permissions::MANAGE_MESSAGES),
+    },
+    CommandInfo {
+        name: "export",
+        description: "Download a Wordle history as CSV",
+        permissions: None,
+    },
+    CommandInfo {
+        name: "wordle",
+        description: "Your Wordle stats, and whether the bot tracks your games",
+        permissions: None,
+    },
+    CommandInfo {
+        name: "timezone",
+        description: "Set the timezone the server's puzzle day follows",
+        permissions: Some(Permissions::MANAGE_GUILD),
+    },
+    CommandInfo {
+        name: "import",
+        description: "Add past games to the server's Wordle history from a CSV",
+        permissions: Some(Permissions::MANAGE_GUILD),
+    },
+    CommandInfo {
+        name: "config",
+        description: "Show or change how the bot works in this server",
+        permissions: Some(Permissions::MANAGE_GUILD),
+    },
+    CommandInfo {
+        name: "help",
+        description: "List the bot's commands",
+        permissions: None,
+    },
+];
+
+/// Builds the definitions of every slash command the bot registers
+pub fn register() -> Vec<CreateCommand> {
+    COMMANDS
+        .iter()
+        .map(|info| {
+            let command = CreateCommand::new(info.name).description(info.description);
+            let command = match info.permissions {
+                Some(permissions) => command.default_member_permissions(permissions),
+                None => command,
+            };
+            add_options(info.name, command)
+        })
+        .collect()
+}
+
+/// Adds the options of the command called `name`
+fn add_options(name: &str, command: CreateCommand) -> CreateCommand {
+    match name {
+        "leaderboard" => command.add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "alltime",
+            "Everyone's fastest ever solve",
+        )),
+        "when" => command.add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Whose game to show, defaults to you",
+        )),
+        "reset" => command
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "The user to reset")
+                    .required(true),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "delete_message",
+                "Also delete the user's completion message",
+            )),
+        "record" => command
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "Who solved it")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "time",
+                    "How long they took, e.g. 4m32s or 4:32",
+                )
+                .required(true),
+            ),
+        "simulate" => command
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Attachment,
+                    "screenshot",
+                    "The screenshot to check",
+                )
+                .required(true),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "Whose avatar to look for, defaults to you",
+            )),
+        "set-avatar" => command
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "Whose avatar to set")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Attachment,
+                    "image",
+                    "The avatar as it appears in screenshots",
+                )
+                .required(true),
+            ),
+        "export" => command.add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Whose history to export (moderators only, defaults to you)",
+        )),
+        "wordle" => command
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "optin",
+                "Let the bot track your games again",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "optout",
+                "Stop the bot tracking your games and forget today's",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remind",
+                    "Get a DM reminder on days you haven't finished the Wordle yet",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "enabled",
+                        "Whether to send you reminders",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "stats",
+                    "Solve-time stats from recorded games",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Whose stats to show, defaults to you",
+                )),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "pb",
+                    "Fastest recorded solve",
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Whose best to show, defaults to you",
+                )),
+            ),
+        "timezone" => command.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "name",
+                "IANA timezone name, e.g. Australia/Sydney",
+            )
+            .required(true),
+        ),
+        "import" => command.add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Attachment,
+                "file",
+                "CSV with user_id,date,active_time_ms,guess_count,outcome rows",
+            )
+            .required(true),
+        ),
+        "config" => command
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Channel,
+                "channel",
+                "The channel the Wordle app posts in",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "timezone",
+                "Timezone the puzzle day follows, e.g. Australia/Sydney",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "embed_title",
+                "Title of completion messages",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "embed_footer",
+                "Footer of completion messages",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "embed_colour",
+                "Colour of completion messages as hex, e.g. #57F287",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "reset",
+                "Go back to the default settings before applying any others",
+            )),
+        _ => command,
+    }
+}
+
+/// Dispatches a slash command to its handler and sends the response
+pub async fn run(
+    ctx: &Context,
+    command: &CommandInteraction,
+    history: &History,
+    defaults: &GuildConfig,
+    downloads: &DownloadLimiter,
+    marker_templates: &[MarkerTemplate],
+    overrides: &AvatarOverrides,
+) {
+    // Detection can outlast the time allowed for a response, so it answers on its own
+    if command.data.name == "simulate" {
+        simulate(ctx, command, downloads, marker_templates).await;
+        return;
+    }
+    // As can fetching the uploaded image
+    if command.data.name == "set-avatar" {
+        set_avatar(ctx, command, overrides).await;
+        return;
+    }
+    if command.data.name == "import" {
+        import(ctx, command, history).await;
+        return;
+    }
+
+    let config = guild_config(command.guild_id, history, defaults).await;
+
+    let response = match command.data.name.as_str() {
+        "progress" => progress(ctx, command.guild_id, &config).await,
+        "leaderboard" => all_time_leaderboard(command.guild_id, history, &config, 0).await,
+        "when" => when(ctx, command, &config).await,
+        "reset" => reset(ctx, command).await,
+        "record" => record(ctx, command, history, &config).await,
+        "status" => status(ctx, command, &config).await,
+        "export" => export(command, history).await,
+        "timezone" => set_timezone(ctx, command, history).await,
+        "config" => configure(command, history, defaults).await,
+        "wordle" => match command.data.options().first().map(|option| option.name) {
+            Some("stats") => stats(command, history).await,
+            Some("pb") => personal_best(command, history).await,
+            Some("remind") => remind(ctx, command, history).await,
+            _ => tracking(ctx, command, history).await,
+        },
+        "help" => help(&config),
+        other => {
+            info!("Ignoring unknown command: {}", other);
+            return;
+        }
+    };
+
+    if let Err(why) = command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await
+    {
+        error!("Error responding to /{}: {:?}", command.data.name, why);
+    }
+}
+
+/// Handles a button press on one of the bot's messages
+pub async fn run_component(
+    ctx: &Context,
+    component: &ComponentInteraction,
+    history: &History,
+    defaults: &GuildConfig,
+) {
+    // The page to show is carried in the button itself, so no state is kept between presses
+    let Some(page) = component
+        .data
+        .custom_id
+        .strip_prefix(ALL_TIME_PAGE_PREFIX)
+        .and_then(|page| page.parse().ok())
+    else {
+        info!("Ignoring unknown component: {}", component.data.custom_id);
+        return;
+    };
+
+    let config = guild_config(component.guild_id, history, defaults).await;
+    let response = all_time_leaderboard(component.guild_id, history, &config, page).await;
+
+    if let Err(why) = component
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::UpdateMessage(response),
+        )
+        .await
+    {
+        error!("Error turning leaderboard page: {:?}", why);
+    }
+}
+
+/// Lists every command, noting those only moderators or admins can use
+fn help(config: &GuildConfig) -> CreateInteractionResponseMessage {
+    let strings = config.locale.strings();
+    let lines: Vec<String> = COMMANDS
+        .iter()
+        .map(|info| {
+            let restriction = match info.permissions {
+                Some(permissions) if permissions.manage_guild() => Some(strings.admins_only),
+                Some(_) => Some(strings.moderators_only),
+                None => None,
+            };
+            match restriction {
+                Some(restriction) => {
+                    format!("**/{}** — {} {}", info.name, info.description, restriction)
+                }
+                None => format!("**/{}** — {}", info.name, info.description),
+            }
+        })
+        .collect();
+
+    let (r, g, b) = config.embed.colour;
+    let tracked = fill(
+        strings.help_tracked,
+        &[
+            ("channel", &describe_channel(&config.channel)),
+            ("timezone", &config.timezone.to_string()),
+        ],
+    );
+    let embed = CreateEmbed::new()
+        .title(strings.help_title)
+        .description(format!("{}\n\n{}", lines.join("\n"), tracked))
+        .colour(Colour::from_rgb(r, g, b));
+
+    CreateInteractionResponseMessage::new()
+        .embed(embed)
+        .ephemeral(true)
+}
+
+/// The tracked channel as a mention, or how it's matched by name
+fn describe_channel(channel: &TrackedChannel) -> String {
+    match channel {
+        TrackedChannel::Id(id) => format!("<#{}>", id),
+        TrackedChannel::Name(name) => format!("channels named like #{}", name),
+    }
+}
+
+/// The config for a guild, or the defaults outside guilds or if it can't be loaded
+async fn guild_config(
+    guild_id: Option<GuildId>,
+    history: &History,
+    defaults: &GuildConfig,
+) -> GuildConfig {
+    match guild_id {
+        Some(guild_id) => history
+            .guild_config(guild_id.get(), defaults)
+            .await
+            .unwrap_or_else(|why| {
+                error!("Error loading config for guild {}: {:?}", guild_id, why);
+                defaults.clone()
+            }),
+        None => defaults.clone(),
+    }
+}
+
+/// Custom ID prefix of the all-time leaderboard's page buttons, followed by the page
+const ALL_TIME_PAGE_PREFIX: &str = "leaderboard_alltime:";
+
+/// Shows a page of everyone's best solve times, with buttons to turn the page
+async fn all_time_leaderboard(
+    guild_id: Option<GuildId>,
+    history: &History,
+    config: &GuildConfig,
+    page: usize,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new();
+    let strings = config.locale.strings();
+
+    let Some(guild_id) = guild_id else {
+        return response
+            .content(strings.leaderboard_guild_only)
+            .ephemeral(true);
+    };
+    let entries = match history.all_time_leaderboard(guild_id.get()).await {
+        Ok(entries) => entries,
+        Err(why) => {
+            error!(
+                "Error loading leaderboard for guild {}: {:?}",
+                guild_id, why
+            );
+            return response
+                .content(strings.leaderboard_unavailable)
+                .ephemeral(true);
+        }
+    };
+
+    let page = leaderboard_page(&entries, page);
+    let embed = CreateEmbed::new()
+        .title(strings.leaderboard_title)
+        .description(page.text)
+        .footer(CreateEmbedFooter::new(fill(
+            strings.leaderboard_page,
+            &[
+                ("page", &(page.page + 1).to_string()),
+                ("pages", &page.pages.to_string()),
+            ],
+        )))
+        .colour(Colour::from_rgb(
+            config.embed.colour.0,
+            config.embed.colour.1,
+            config.embed.colour.2,
+        ));
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!(
+            "{}{}",
+            ALL_TIME_PAGE_PREFIX,
+            page.page.saturating_sub(1)
+        ))
+        .label(strings.previous)
+        .style(ButtonStyle::Secondary)
+        .disabled(page.page == 0),
+        CreateButton::new(format!("{}{}", ALL_TIME_PAGE_PREFIX, page.page + 1))
+            .label(strings.next)
+            .style(ButtonStyle::Secondary)
+            .disabled(page.page + 1 >= page.pages),
+    ]);
+
+    response.embed(embed).components(vec![buttons])
+}
+
+/// Lists today's finished players and the players still solving
+async fn progress(
+    ctx: &Context,
+    guild_id: Option<GuildId>,
+    config: &GuildConfig,
+) -> CreateInteractionResponseMessage {
+    let data_read = ctx.data.read().await;
+    let puzzle_map = data_read
+        .get::<WordlePuzzles>()
+        .expect("Expected WordlePuzzles in TypeMap")
+        .lock()
+        .await;
+
+    // A user can have several games if the Wordle app posted more than one message,
+    // so collapse them: any completed game counts, otherwise keep the longest one
+    let mut players: HashMap<UserId, (&str, (bool, Duration))> = HashMap::new();
+    for ((guild, _, user_id), game_state) in puzzle_map.iter() {
+        if Some(*guild) != guild_id || !game_state.is_current() {
+            continue;
+        }
+
+        let elapsed = game_state.elapsed();
+        let (name, player) = players
+            .entry(*user_id)
+            .or_insert((&game_state.player_name, (game_state.completed, elapsed)));
+        if game_state.completed && !player.0 {
+            *name = &game_state.player_name;
+            *player = (true, elapsed);
+        } else if game_state.completed == player.0 && elapsed > player.1 {
+            player.1 = elapsed;
+        }
+    }
+
+    let (mut finished, mut in_progress): (Vec<_>, Vec<_>) = players
+        .into_values()
+        .partition(|(_, (completed, _))| *completed);
+    finished.sort_by_key(|(_, (_, elapsed))| *elapsed);
+    in_progress.sort_by_key(|(username, _)| *username);
+
+    // Players whose activity stopped without finishing stay in progress, with the
+    // time they had accumulated so far
+    let strings = config.locale.strings();
+    let format_list = |players: &[(&str, (bool, Duration))], template: &str| {
+        if players.is_empty() {
+            return strings.nobody_yet.to_string();
+        }
+        players
+            .iter()
+            .map(|(username, (_, elapsed))| {
+                fill(
+                    template,
+                    &[
+                        ("name", username),
+                        ("time", &format_duration_in(strings, *elapsed)),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = CreateEmbed::new()
+        .title(strings.progress_title)
+        .field(
+            strings.progress_finished,
+            format_list(&finished, strings.progress_finished_in),
+            false,
+        )
+        .field(
+            strings.progress_playing,
+            format_list(&in_progress, strings.progress_playing_for),
+            false,
+        )
+        .colour(Colour::from_rgb(
+            config.embed.colour.0,
+            config.embed.colour.1,
+            config.embed.colour.2,
+        ));
+
+    CreateInteractionResponseMessage::new().embed(embed)
+}
+
+/// Says when a user started today's game, and how long they took or have taken so far
+async fn when(
+    ctx: &Context,
+    command: &CommandInteraction,
+    config: &GuildConfig,
+) -> CreateInteractionResponseMessage {
+    let strings = config.locale.strings();
+    let user = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match (option.name, option.value) {
+            ("user", ResolvedValue::User(user, _)) => Some(user),
+            _ => None,
+        })
+        .unwrap_or(&command.user);
+
+    let data_read = ctx.data.read().await;
+    let puzzle_map = data_read
+        .get::<WordlePuzzles>()
+        .expect("Expected WordlePuzzles in TypeMap")
+        .lock()
+        .await;
+
+    // As in /progress, a completed game wins over ones the Wordle app posted again
+    let games: Vec<&GameState> = puzzle_map
+        .iter()
+        .filter(|(key, game)| is_players_game(key, command.guild_id, user.id) && game.is_current())
+        .map(|(_, game)| game)
+        .collect();
+    let Some(first) = games.iter().min_by_key(|game| game.created_at) else {
+        return CreateInteractionResponseMessage::new()
+            .content(fill(strings.not_started, &[("name", &user.name)]));
+    };
+    let latest = games
+        .iter()
+        .find(|game| game.completed)
+        .or_else(|| games.iter().max_by_key(|game| game.elapsed()))
+        .unwrap_or(first);
+
+    CreateInteractionResponseMessage::new().content(start_description_in(
+        strings,
+        &user.name,
+        first.created_at,
+        latest.elapsed(),
+        latest.completed,
+        first.timezone,
+    ))
+}
+
+/// Whether the invoking member may use moderator commands
+fn is_moderator(command: &CommandInteraction) -> bool {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_messages())
+}
+
+/// Takes every game tracked for `user_id` in a guild out of the game map
+async fn remove_games(ctx: &Context, guild_id: Option<GuildId>, user_id: UserId) -> Vec<GameState> {
+    let data_read = ctx.data.read().await;
+    let mut puzzle_map = data_read
+        .get::<WordlePuzzles>()
+        .expect("Expected WordlePuzzles in TypeMap")
+        .lock()
+        .await;
+
+    let keys: Vec<_> = puzzle_map
+        .keys()
+        .filter(|key| is_players_game(key, guild_id, user_id))
+        .copied()
+        .collect();
+    keys.into_iter()
+        .filter_map(|key| puzzle_map.remove(&key))
+        .collect()
+}
+
+/// Shows the server's tracked games at a glance, and how long the bot has been up
+async fn status(
+    ctx: &Context,
+    command: &CommandInteraction,
+    config: &GuildConfig,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    if !is_moderator(command) {
+        return response.content("You need the Manage Messages permission to see the status.");
+    }
+
+    let data_read = ctx.data.read().await;
+    let uptime = data_read
+        .get::<StartedAt>()
+        .map(|started_at| started_at.elapsed());
+    let now = chrono::Utc::now();
+    let summary = summarize_games(
+        data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await
+            .iter()
+            .filter(|((guild, _, _), _)| Some(*guild) == command.guild_id)
+            .map(|(_, game_state)| game_state),
+        now,
+    );
+
+    let last_completion = match summary.last_completed_at {
+        Some(at) => format!(
+            "{} ago",
+            format_duration((now - at).to_std().unwrap_or_default())
+        ),
+        None => "None yet".to_string(),
+    };
+    let embed = CreateEmbed::new()
+        .title("📊 Tracking Status")
+        .field("⏳ Active games", summary.active.to_string(), true)
+        .field("✅ Completed today", summary.completed.to_string(), true)
+        .field("🗑️ From earlier days", summary.stale.to_string(), true)
+        .field("🕒 Last completion", last_completion, false)
+        .field(
+            "⏱️ Uptime",
+            uptime.map_or_else(|| "Unknown".to_string(), format_duration),
+            false,
+        )
+        .colour(Colour::from_rgb(
+            config.embed.colour.0,
+            config.embed.colour.1,
+            config.embed.colour.2,
+        ));
+
+    response.embed(embed)
+}
+
+/// Longest solve time /record accepts, past which the time is surely a typo
+const MAX_RECORDED_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Records today's solve time for a user whose game couldn't be tracked, finishing any
+/// game of theirs that is, and posts its completion embed
+async fn record(
+    ctx: &Context,
+    command: &CommandInteraction,
+    history: &History,
+    config: &GuildConfig,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new();
+
+    if !is_moderator(command) {
+        return response
+            .content("You need the Manage Messages permission to record solves.")
+            .ephemeral(true);
+    }
+    let Some(guild_id) = command.guild_id else {
+        return response
+            .content("Solves can only be recorded in servers.")
+            .ephemeral(true);
+    };
+
+    let mut user = None;
+    let mut time = None;
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("user", ResolvedValue::User(u, member)) => user = Some((u, member)),
+            ("time", ResolvedValue::String(t)) => time = Some(t),
+            _ => {}
+        }
+    }
+    let (Some((user, member)), Some(time)) = (user, time) else {
+        return response
+            .content("Please choose a user and give their time.")
+            .ephemeral(true);
+    };
+    let time = match parse_duration(time) {
+        Ok(time) if !time.is_zero() && time <= MAX_RECORDED_TIME => time,
+        Ok(_) => {
+            return response
+                .content("The time must be more than zero and at most 24 hours.")
+                .ephemeral(true);
+        }
+        Err(why) => {
+            return response
+                .content(format!("{why}. Try a time like 4m32s or 4:32."))
+                .ephemeral(true);
+        }
+    };
+
+    let now = chrono::Utc::now();
+    {
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
+        for (_, game_state) in puzzle_map.iter_mut().filter(|(key, game_state)| {
+            is_players_game(key, Some(guild_id), user.id) && game_state.is_current()
+        }) {
+            game_state.mark_completed(time, now);
+        }
+    }
+
+    let current = member.map(|member| member.nick.as_deref().unwrap_or(user.display_name()));
+    let name = display_name(history, guild_id, user, current).await;
+    let mut game_state = GameState::in_timezone_at(config.timezone, now);
+    game_state.mark_completed(time, now);
+    let completion = Completion::from_game(
+        user.id.get(),
+        guild_id.get(),
+        &game_state,
+        current.map(String::from),
+    );
+    if let Err(why) = history.record_completion(&completion).await {
+        error!("Error recording {}'s solve: {:?}", user.name, why);
+        return response
+            .content("Unable to record the solve right now.")
+            .ephemeral(true);
+    }
+    info!(
+        "Recorded a {:?} solve for {} in guild {}",
+        time, user.name, guild_id
+    );
+
+    let description = completion_description_in(
+        config.locale.strings(),
+        &name,
+        time,
+        Duration::ZERO,
+        false,
+        false,
+    );
+    response.embed(completion_embed(config, description, now))
+}
+
+/// Removes every game tracked for a user, optionally deleting their completion messages
+async fn reset(ctx: &Context, command: &CommandInteraction) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    if !is_moderator(command) {
+        return response.content("You need the Manage Messages permission to reset games.");
+    }
+
+    let mut user = None;
+    let mut delete_message = false;
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("user", ResolvedValue::User(u, _)) => user = Some(u),
+            ("delete_message", ResolvedValue::Boolean(delete)) => delete_message = delete,
+            _ => {}
+        }
+    }
+    let Some(user) = user else {
+        return response.content("Please choose a user to reset.");
+    };
+
+    let removed = remove_games(ctx, command.guild_id, user.id).await;
+
+    if removed.is_empty() {
+        return response.content(format!("No tracked games found for {}.", user.name));
+    }
+
+    let mut deleted = 0;
+    if delete_message {
+        for game in &removed {
+            let Some(msg_id) = game.completion_msg_id else {
+                continue;
+            };
+            // The message may live in a daily thread rather than this channel
+            let channel_id = game.completion_channel_id.unwrap_or(command.channel_id);
+            match channel_id.delete_message(&ctx.http, msg_id).await {
+                Ok(()) => deleted += 1,
+                Err(why) => error!("Error deleting completion message {}: {:?}", msg_id, why),
+            }
+        }
+    }
+
+    info!(
+        "Reset {} game(s) for {} and deleted {} completion message(s)",
+        removed.len(),
+        user.name,
+        deleted
+    );
+
+    let mut content = format!(
+        "Cleared {} tracked game(s) for {}",
+        removed.len(),
+        user.name
+    );
+    if delete_message {
+        content.push_str(&format!(" and deleted {} completion message(s)", deleted));
+    }
+    content.push('.');
+
+    response.content(content)
+}
+
+/// Runs detection on an uploaded screenshot, replying with it annotated and a summary
+async fn simulate(
+    ctx: &Context,
+    command: &CommandInteraction,
+    downloads: &DownloadLimiter,
+    marker_templates: &[MarkerTemplate],
+) {
+    if !is_moderator(command) {
+        let response = CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .content("You need the Manage Messages permission to simulate detection.");
+        if let Err(why) = command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await
+        {
+            error!("Error responding to /simulate: {:?}", why);
+        }
+        return;
+    }
+
+    if let Err(why) = command.defer_ephemeral(&ctx.http).await {
+        error!("Error deferring /simulate: {:?}", why);
+        return;
+    }
+    let edit = match simulation(command, downloads, marker_templates).await {
+        Ok(edit) => edit,
+        Err(content) => EditInteractionResponse::new().content(content),
+    };
+
+    if let Err(why) = command.edit_response(&ctx.http, edit).await {
+        error!("Error responding to /simulate: {:?}", why);
+    }
+}
+
+/// The reply to /simulate, or why there isn't one
+async fn simulation(
+    command: &CommandInteraction,
+    downloads: &DownloadLimiter,
+    marker_templates: &[MarkerTemplate],
+) -> Result<EditInteractionResponse, String> {
+    let mut screenshot = None;
+    let mut user = &command.user;
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("screenshot", ResolvedValue::Attachment(attachment)) => screenshot = Some(attachment),
+            ("user", ResolvedValue::User(u, _)) => user = u,
+            _ => {}
+        }
+    }
+    let Some(screenshot) = screenshot else {
+        return Err("Please attach a screenshot to check.".to_string());
+    };
+    if !is_image_attachment(screenshot) {
+        return Err(format!("{} isn't an image.", screenshot.filename));
+    }
+
+    let avatar_urls: Vec<String> = user.static_avatar_url().into_iter().collect();
+    let avatar_urls: Vec<&str> = avatar_urls.iter().map(String::as_str).collect();
+    let (trace, annotated) = match simulate_detection(
+        downloads,
+        marker_templates,
+        &screenshot.url,
+        &avatar_urls,
+    )
+    .await
+    {
+        Ok(simulation) => simulation,
+        Err(WordleError::Decode { .. }) => {
+            return Err(format!(
+                "{} couldn't be read as an image.",
+                screenshot.filename
+            ));
+        }
+        Err(why) => {
+            error!("Error simulating detection on {}: {}", screenshot.url, why);
+            return Err("Unable to run detection right now.".to_string());
+        }
+    };
+
+    info!(
+        "Simulated detection on {} for {}: {:?}",
+        screenshot.filename, user.name, trace
+    );
+
+    // Only the chosen user's avatar is looked for, if they have one
+    let mut summary = detection_summary(&trace, &[&user.name]);
+    if trace.avatars.is_empty() {
+        summary.push_str(&format!("\n{} has no avatar to look for.", user.name));
+    }
+
+    Ok(EditInteractionResponse::new()
+        .content(summary)
+        .new_attachment(CreateAttachment::bytes(annotated, "simulated.png")))
+}
+
+/// Stores an uploaded image as the avatar a user is looked for by
+async fn set_avatar(ctx: &Context, command: &CommandInteraction, overrides: &AvatarOverrides) {
+    if !is_moderator(command) {
+        let response = CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .content("You need the Manage Messages permission to set avatars.");
+        if let Err(why) = command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await
+        {
+            error!("Error responding to /set-avatar: {:?}", why);
+        }
+        return;
+    }
+
+    if let Err(why) = command.defer_ephemeral(&ctx.http).await {
+        error!("Error deferring /set-avatar: {:?}", why);
+        return;
+    }
+    let content = match avatar_override(command, overrides).await {
+        Ok(content) | Err(content) => content,
+    };
+
+    if let Err(why) = command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await
+    {
+        error!("Error responding to /set-avatar: {:?}", why);
+    }
+}
+
+/// The reply to /set-avatar once the image is saved, or why it wasn't
+async fn avatar_override(
+    command: &CommandInteraction,
+    overrides: &AvatarOverrides,
+) -> Result<String, String> {
+    let Some(guild_id) = command.guild_id else {
+        return Err("Avatars can only be set in a server.".to_string());
+    };
+
+    let mut user = None;
+    let mut image = None;
+    for option in command.data.options() {
+        match (option.name, option.value) {
+            ("user", ResolvedValue::User(u, _)) => user = Some(u),
+            ("image", ResolvedValue::Attachment(attachment)) => image = Some(attachment),
+            _ => {}
+        }
+    }
+    let (Some(user), Some(image)) = (user, image) else {
+        return Err("Please choose a user and attach their avatar.".to_string());
+    };
+    if !is_image_attachment(image) {
+        return Err(format!("{} isn't an image.", image.filename));
+    }
+
+    let bytes = image.download().await.map_err(|why| {
+        error!("Error downloading avatar {}: {:?}", image.url, why);
+        "Unable to fetch the image right now.".to_string()
+    })?;
+    match overrides.save(guild_id.get(), user.id.get(), &bytes).await {
+        Ok(path) => {
+            info!(
+                "Saved avatar override for {} to {}",
+                user.name,
+                path.display()
+            );
+            Ok(format!(
+                "{} will now be looked for by the uploaded avatar.",
+                user.name
+            ))
+        }
+        Err(WordleError::Decode { .. }) => {
+            Err(format!("{} couldn't be read as an image.", image.filename))
+        }
+        Err(why) => {
+            error!("Error saving avatar override for {}: {}", user.name, why);
+            Err("Unable to save the avatar right now.".to_string())
+        }
+    }
+}
+
+/// Attaches a user's recorded games as a CSV file
+async fn export(
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("History can only be exported from a server.");
+    };
+
+    let mut user = &command.user;
+    for option in command.data.options() {
+        if let ("user", ResolvedValue::User(u, _)) = (option.name, option.value) {
+            user = u;
+        }
+    }
+    if user.id != command.user.id && !is_moderator(command) {
+        return response.content("You need the Manage Messages permission to export other users.");
+    }
+
+    let completions = match history
+        .completions_for_user(guild_id.get(), user.id.get())
+        .await
+    {
+        Ok(completions) => completions,
+        Err(why) => {
+            error!("Error loading history for {}: {:?}", user.name, why);
+            return response.content("Unable to load the history right now.");
+        }
+    };
+
+    info!("Exporting {} game(s) for {}", completions.len(), user.name);
+
+    let csv = completions_to_csv(&user.name, &completions);
+    response
+        .content(format!(
+            "{} recorded game(s) for {}.",
+            completions.len(),
+            user.name
+        ))
+        .add_file(CreateAttachment::bytes(
+            csv,
+            format!("wordle-history-{}.csv", user.name),
+        ))
+}
+
+/// Largest history CSV /import accepts
+const MAX_IMPORT_BYTES: u32 = 1024 * 1024;
+
+/// Rejected rows listed in the reply to /import before the rest are only counted
+const MAX_REJECTED_SHOWN: usize = 10;
+
+/// Adds the games in an uploaded CSV to the server's history
+async fn import(ctx: &Context, command: &CommandInteraction, history: &History) {
+    if !is_admin(command) {
+        let response = CreateInteractionResponseMessage::new()
+            .ephemeral(true)
+            .content("You need the Manage Server permission to import history.");
+        if let Err(why) = command
+            .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+            .await
+        {
+            error!("Error responding to /import: {:?}", why);
+        }
+        return;
+    }
+
+    if let Err(why) = command.defer_ephemeral(&ctx.http).await {
+        error!("Error deferring /import: {:?}", why);
+        return;
+    }
+    let content = match import_history(command, history).await {
+        Ok(content) | Err(content) => content,
+    };
+
+    if let Err(why) = command
+        .edit_response(&ctx.http, EditInteractionResponse::new().content(content))
+        .await
+    {
+        error!("Error responding to /import: {:?}", why);
+    }
+}
+
+/// The reply to /import once the file is imported, or why it wasn't
+async fn import_history(command: &CommandInteraction, history: &History) -> Result<String, String> {
+    let Some(guild_id) = command.guild_id else {
+        return Err("History can only be imported into a server.".to_string());
+    };
+
+    let file = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match (option.name, option.value) {
+            ("file", ResolvedValue::Attachment(attachment)) => Some(attachment),
+            _ => None,
+        })
+        .ok_or_else(|| "Please attach a CSV file.".to_string())?;
+    if file.size > MAX_IMPORT_BYTES {
+        return Err(format!(
+            "{} is too large, files up to {} KiB can be imported.",
+            file.filename,
+            MAX_IMPORT_BYTES / 1024
+        ));
+    }
+
+    let bytes = file.download().await.map_err(|why| {
+        error!("Error downloading history {}: {:?}", file.url, why);
+        "Unable to fetch the file right now.".to_string()
+    })?;
+    let csv =
+        String::from_utf8(bytes).map_err(|_| format!("{} isn't a text file.", file.filename))?;
+
+    let report = history
+        .import_csv(guild_id.get(), &csv)
+        .await
+        .map_err(|why| {
+            error!("Error importing history into guild {}: {:?}", guild_id, why);
+            "Unable to save the history right now.".to_string()
+        })?;
+    info!(
+        "Imported {} game(s) into guild {} from {}, skipped {} row(s)",
+        report.imported,
+        guild_id,
+        file.filename,
+        report.rejected.len()
+    );
+
+    Ok(import_summary(&report))
+}
+
+/// Describes what an import added and which rows it skipped
+fn import_summary(report: &ImportReport) -> String {
+    let mut summary = format!("Imported {} game(s).", report.imported);
+    if report.rejected.is_empty() {
+        return summary;
+    }
+
+    summary.push_str(&format!(
+        "\nSkipped {} row(s) that couldn't be read:",
+        report.rejected.len()
+    ));
+    for row in report.rejected.iter().take(MAX_REJECTED_SHOWN) {
+        summary.push_str(&format!("\n- Line {}: {}", row.line, row.reason));
+    }
+    if report.rejected.len() > MAX_REJECTED_SHOWN {
+        summary.push_str(&format!(
+            "\nand {} more.",
+            report.rejected.len() - MAX_REJECTED_SHOWN
+        ));
+    }
+    summary
+}
+
+/// Whether the invoking member may change the server's settings
+fn is_admin(command: &CommandInteraction) -> bool {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+}
+
+/// Updates the server's settings with any options given, then shows the result
+async fn configure(
+    command: &CommandInteraction,
+    history: &History,
+    defaults: &GuildConfig,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("Settings can only be changed in a server.");
+    };
+    if !is_admin(command) {
+        return response.content("You need the Manage Server permission to change settings.");
+    }
+
+    let mut overrides = match history.guild_overrides(guild_id.get()).await {
+        Ok(overrides) => overrides,
+        Err(why) => {
+            error!("Error loading config for guild {}: {:?}", guild_id, why);
+            return response.content("Unable to load the settings right now.");
+        }
+    };
+
+    let options = command.data.options();
+    if options.iter().any(|option| {
+        matches!(
+            (option.name, &option.value),
+            ("reset", ResolvedValue::Boolean(true))
+        )
+    }) {
+        overrides = Default::default();
+    }
+
+    for option in options {
+        match (option.name, option.value) {
+            ("channel", ResolvedValue::Channel(channel)) => {
+                overrides.channel = Some(TrackedChannel::Id(channel.id.get()));
+            }
+            ("timezone", ResolvedValue::String(timezone)) => match parse_timezone(timezone) {
+                Some(timezone) => overrides.timezone = Some(timezone),
+                None => return response.content(unknown_timezone(timezone)),
+            },
+            ("embed_title", ResolvedValue::String(title)) => {
+                overrides.embed_title = Some(title.to_string());
+            }
+            ("embed_footer", ResolvedValue::String(footer)) => {
+                overrides.embed_footer = Some(footer.to_string());
+            }
+            ("embed_colour", ResolvedValue::String(colour)) => match parse_hex_colour(colour) {
+                Some(colour) => overrides.embed_colour = Some(colour),
+                None => {
+                    return response.content(format!(
+                        "Invalid colour '{}', use hex like #57F287.",
+                        colour
+                    ));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    if let Err(why) = history
+        .set_guild_overrides(guild_id.get(), &overrides)
+        .await
+    {
+        error!("Error saving config for guild {}: {:?}", guild_id, why);
+        return response.content("Unable to save the settings right now.");
+    }
+
+    let config = overrides.apply(defaults);
+    info!("Config for guild {} is now {:?}", guild_id, config);
+
+    let channel = describe_channel(&config.channel);
+    let (r, g, b) = config.embed.colour;
+    response.content(format!(
+        "**Channel:** {}\n**Timezone:** {}\n**Embed title:** {}\n**Embed footer:** {}\n**Embed colour:** #{:02X}{:02X}{:02X}",
+        channel, config.timezone, config.embed.title, config.embed.footer, r, g, b
+    ))
+}
+
+/// Sets the timezone a guild's puzzle day follows, moving the games being tracked to it
+async fn set_timezone(
+    ctx: &Context,
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("The timezone can only be set in a server.");
+    };
+    if !is_admin(command) {
+        return response.content("You need the Manage Server permission to set the timezone.");
+    }
+    let name = command
+        .data
+        .options()
+        .into_iter()
+        .find_map(|option| match (option.name, option.value) {
+            ("name", ResolvedValue::String(name)) => Some(name),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let Some(timezone) = parse_timezone(name) else {
+        return response.content(unknown_timezone(name));
+    };
+
+    let mut overrides = match history.guild_overrides(guild_id.get()).await {
+        Ok(overrides) => overrides,
+        Err(why) => {
+            error!("Error loading config for guild {}: {:?}", guild_id, why);
+            return response.content("Unable to load the settings right now.");
+        }
+    };
+    overrides.timezone = Some(timezone);
+    if let Err(why) = history
+        .set_guild_overrides(guild_id.get(), &overrides)
+        .await
+    {
+        error!("Error saving config for guild {}: {:?}", guild_id, why);
+        return response.content("Unable to save the settings right now.");
+    }
+
+    let moved = {
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
+        set_guild_timezone(&mut puzzle_map, guild_id, timezone)
+    };
+    info!(
+        "Timezone for guild {} is now {}, moved {} game(s)",
+        guild_id, timezone, moved
+    );
+
+    response.content(format!(
+        "The puzzle day now follows **{}**, where it's {}.",
+        timezone,
+        chrono::Utc::now()
+            .with_timezone(&timezone)
+            .format("%H:%M on %a %-d %b")
+    ))
+}
+
+/// Why a timezone name was rejected, with an example of one that works
+fn unknown_timezone(name: &str) -> String {
+    format!(
+        "Unknown timezone '{name}'. Use an IANA name such as Australia/Sydney or America/New_York."
+    )
+}
+
+/// Opts the invoking user in to or out of tracking
+async fn tracking(
+    ctx: &Context,
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("Tracking can only be changed in a server.");
+    };
+    let user = &command.user;
+    let Some(subcommand) = command.data.options().into_iter().next() else {
+        return response.content("Please choose optin or optout.");
+    };
+
+    match subcommand.name {
+        "optin" => {
+            if let Err(why) = history.opt_in(guild_id.get(), user.id.get()).await {
+                error!("Error opting in {}: {:?}", user.name, why);
+                return response.content("Unable to update your tracking right now.");
+            }
+            info!("{} opted in to tracking", user.name);
+            response.content("Your Wordle games will be tracked again.")
+        }
+        "optout" => {
+            if let Err(why) = history.opt_out(guild_id.get(), user.id.get()).await {
+                error!("Error opting out {}: {:?}", user.name, why);
+                return response.content("Unable to update your tracking right now.");
+            }
+
+            let removed = remove_games(ctx, Some(guild_id), user.id).await;
+            info!(
+                "{} opted out of tracking, cleared {} game(s)",
+                user.name,
+                removed.len()
+            );
+            response.content("Your Wordle games won't be tracked any more.")
+        }
+        _ => response.content("Please choose optin or optout."),
+    }
+}
+
+/// Turns a user's daily reminders on or off
+async fn remind(
+    ctx: &Context,
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("Reminders can only be set up in a server.");
+    };
+    let Some(reminder_time) = ctx.data.read().await.get::<ReminderTime>().copied() else {
+        return response.content("Reminders aren't set up for this bot.");
+    };
+    let user = &command.user;
+    let mut enabled = false;
+    if let Some(ResolvedValue::SubCommand(options)) =
+        command.data.options().first().map(|option| &option.value)
+    {
+        for option in options {
+            if let ("enabled", ResolvedValue::Boolean(value)) = (option.name, &option.value) {
+                enabled = *value;
+            }
+        }
+    }
+
+    let updated = if enabled {
+        history.remind(guild_id.get(), user.id.get()).await
+    } else {
+        history.stop_reminding(guild_id.get(), user.id.get()).await
+    };
+    if let Err(why) = updated {
+        error!("Error updating reminders for {}: {:?}", user.name, why);
+        return response.content("Unable to update your reminders right now.");
+    }
+
+    if enabled {
+        info!("{} asked for reminders", user.name);
+        response.content(format!(
+            "You'll get a DM at {} on days you haven't finished the Wordle by then. \
+             Make sure DMs from server members are allowed.",
+            reminder_time.format("%H:%M")
+        ))
+    } else {
+        info!("{} stopped reminders", user.name);
+        response.content("You won't get Wordle reminders any more.")
+    }
+}
+
+/// The user a subcommand's `user` option chose, defaulting to whoever ran it, with the
+/// name they go by in the server now if they're still a member
+fn chosen_user(command: &CommandInteraction) -> (&User, Option<&str>) {
+    if let Some(ResolvedValue::SubCommand(options)) =
+        command.data.options().first().map(|option| &option.value)
+    {
+        for option in options {
+            if let ("user", ResolvedValue::User(user, member)) = (option.name, &option.value) {
+                let current =
+                    member.map(|member| member.nick.as_deref().unwrap_or(user.display_name()));
+                return (user, current);
+            }
+        }
+    }
+
+    let user = &command.user;
+    let current = command
+        .member
+        .as_ref()
+        .map(|member| member.nick.as_deref().unwrap_or(user.display_name()));
+    (user, current)
+}
+
+/// The name to show for a user, see [`resolve_display_name`]
+async fn display_name(
+    history: &History,
+    guild_id: GuildId,
+    user: &User,
+    current: Option<&str>,
+) -> String {
+    let stored = match current {
+        Some(_) => None,
+        None => history
+            .latest_display_name(guild_id.get(), user.id.get())
+            .await
+            .unwrap_or_else(|why| {
+                error!("Error loading the stored name of {}: {:?}", user.name, why);
+                None
+            }),
+    };
+    resolve_display_name(current, stored.as_deref(), &user.name).to_string()
+}
+
+/// Shows a user's fastest solve and when it was
+async fn personal_best(
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("Personal bests are only kept in a server.");
+    };
+
+    let (user, current_name) = chosen_user(command);
+    let name = display_name(history, guild_id, user, current_name).await;
+
+    let best = match history.personal_best(guild_id.get(), user.id.get()).await {
+        Ok(best) => best,
+        Err(why) => {
+            error!("Error loading personal best for {}: {:?}", user.name, why);
+            return response.content("Unable to load personal bests right now.");
+        }
+    };
+    let Some(best) = best else {
+        return response.content(format!("{} hasn't solved any recorded games yet.", name));
+    };
+
+    let mut line = format!(
+        "{}'s personal best is **{}**, on {}",
+        name,
+        format_duration(best.active_time),
+        best.date.format("%-d %B %Y")
+    );
+    match best.guess_count {
+        Some(1) => line.push_str(" in 1 guess"),
+        Some(guess_count) => line.push_str(&format!(" in {guess_count} guesses")),
+        None => {}
+    }
+    if best.hard_mode {
+        line.push_str(", in hard mode");
+    }
+    line.push('.');
+    response.content(line)
+}
+
+/// Shows a user's solve-time stats, with the median and 90th percentile once there are enough
+async fn stats(
+    command: &CommandInteraction,
+    history: &History,
+) -> CreateInteractionResponseMessage {
+    let response = CreateInteractionResponseMessage::new().ephemeral(true);
+
+    let Some(guild_id) = command.guild_id else {
+        return response.content("Stats are only kept in a server.");
+    };
+
+    let (user, current_name) = chosen_user(command);
+    let name = display_name(history, guild_id, user, current_name).await;
+
+    let report = match history
+        .user_time_percentiles(guild_id.get(), user.id.get(), &[50.0, 90.0])
+        .await
+    {
+        Ok(report) => report,
+        Err(why) => {
+            error!("Error loading stats for {}: {:?}", user.name, why);
+            return response.content("Unable to load stats right now.");
+        }
+    };
+
+    let (Some(fastest), Some(mean)) = (report.fastest, report.mean) else {
+        return response.content(format!("{} hasn't solved any recorded games yet.", name));
+    };
+
+    let mut lines = vec![
+        format!("**Solves:** {}", report.samples),
+        format!("**Fastest:** {}", format_duration(fastest)),
+        format!("**Mean:** {}", format_duration(mean)),
+    ];
+    if report.samples >= MIN_PERCENTILE_SAMPLES {
+        for (percentile, time) in &report.percentiles {
+            let label = if *percentile == 50.0 {
+                "Median".to_string()
+            } else {
+                format!("{percentile:.0}th percentile")
+            };
+            lines.push(format!("**{}:** {}", label, format_duration(*time)));
+        }
+    } else {
+        lines.push(format!(
+            "*Median and 90th percentile show after {MIN_PERCENTILE_SAMPLES} solves.*"
+        ));
+    }
+
+    response.content(format!("Stats for {}\n{}", name, lines.join("\n")))
+}
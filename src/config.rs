@@ -0,0 +1,570 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{Result, WordleError};
+use crate::locale::Locale;
+use crate::{
+    DEFAULT_ACTIVITY_NAMES, DEFAULT_CONFIDENCE_VOTES, DEFAULT_DOWNLOAD_TIMEOUT,
+    DEFAULT_MARKER_THRESHOLD, DEFAULT_MAX_DOWNLOADS, RetryPolicy, SOLVED_TEMPLATE, SearchRegion,
+    TrackedChannel,
+};
+
+/// Where the config file is read from unless `WORDLE_CONFIG` says otherwise
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// How completion embeds look
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EmbedStyle {
+    pub title: String,
+    pub footer: String, // `{puzzle}` and `{date}` are filled in, see [`crate::render_footer`]
+    #[serde(deserialize_with = "deserialize_colour")]
+    pub colour: (u8, u8, u8), // Written as hex in the config file
+}
+
+impl Default for EmbedStyle {
+    fn default() -> Self {
+        Self {
+            title: Locale::English.strings().embed_title.to_string(),
+            footer: Locale::English.strings().embed_footer.to_string(),
+            colour: (87, 242, 135), // A nice green color
+        }
+    }
+}
+
+impl EmbedStyle {
+    /// The style with a title and footer left as the English defaults written in
+    /// `locale`'s language instead
+    pub fn localized(&self, locale: Locale) -> Self {
+        let english = Self::default();
+        let strings = locale.strings();
+        Self {
+            title: if self.title == english.title {
+                strings.embed_title.to_string()
+            } else {
+                self.title.clone()
+            },
+            footer: if self.footer == english.footer {
+                strings.embed_footer.to_string()
+            } else {
+                self.footer.clone()
+            },
+            colour: self.colour,
+        }
+    }
+}
+
+/// The colours of a result grid's squares, used to count the rows filled with guesses
+///
+/// The defaults are those of NYT's light theme; each is written as hex in the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct GridColours {
+    #[serde(deserialize_with = "deserialize_colour")]
+    pub green: (u8, u8, u8), // WORDLE_GRID_GREEN, a letter in the right spot
+    #[serde(deserialize_with = "deserialize_colour")]
+    pub yellow: (u8, u8, u8), // WORDLE_GRID_YELLOW, a letter in the wrong spot
+    #[serde(deserialize_with = "deserialize_colour")]
+    pub grey: (u8, u8, u8), // WORDLE_GRID_GREY, a letter not in the word
+    pub tolerance: u8, // WORDLE_GRID_TOLERANCE, how far each channel may be off, for compression
+}
+
+impl Default for GridColours {
+    fn default() -> Self {
+        Self {
+            green: (106, 170, 100), // #6AAA64
+            yellow: (201, 180, 88), // #C9B458
+            grey: (120, 124, 126),  // #787C7E
+            tolerance: 24,
+        }
+    }
+}
+
+/// A webhook to post completion messages through instead of the bot user
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub url: Option<String>,  // WORDLE_WEBHOOK_URL, posting as the bot when unset
+    pub name: Option<String>, // WORDLE_WEBHOOK_NAME, the webhook's own name when unset
+    pub avatar_url: Option<String>, // WORDLE_WEBHOOK_AVATAR_URL
+}
+
+/// Bot-wide settings, read from a TOML file with environment variables taking precedence
+///
+/// Every setting is optional in the file. The environment variable overriding each one
+/// is given beside it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub token: Option<String>,              // DISCORD_TOKEN
+    pub channel_name: String,               // DAILY_PUZZLES_CHANNEL_NAME
+    pub channel_id: Option<u64>,            // DAILY_PUZZLES_CHANNEL_ID, preferred over the name
+    pub debug_channel_name: Option<String>, // DEBUG_CHANNEL_NAME, answers any image with detection details
+    #[serde(deserialize_with = "deserialize_timezone")]
+    pub timezone: Tz, // WORDLE_TIMEZONE
+    pub embed: EmbedStyle,                  // WORDLE_EMBED_TITLE, _FOOTER and _COLOUR
+    #[serde(deserialize_with = "deserialize_locale")]
+    pub locale: Locale, // WORDLE_LOCALE, e.g. fr, the language of embeds and command responses
+    pub webhook: WebhookConfig,
+    pub grid_colours: GridColours,
+    pub dry_run: bool,                         // WORDLE_DRY_RUN
+    pub min_active_secs: f64,                  // WORDLE_MIN_ACTIVE_SECS
+    pub midnight_grace_secs: f64,              // WORDLE_MIDNIGHT_GRACE_SECS
+    pub post_cooldown_secs: f64,               // WORDLE_POST_COOLDOWN_SECS
+    pub completion_reaction: Option<String>,   // WORDLE_COMPLETION_REACTION, e.g. ✅
+    pub confidence_votes: usize,               // WORDLE_CONFIDENCE_VOTES
+    pub marker_band_reach: Option<f64>, // WORDLE_MARKER_BAND_REACH, in marker heights; the whole screenshot is searched if unset
+    pub data_dir: PathBuf,              // WORDLE_DATA_DIR
+    pub archive_screenshots: bool,      // WORDLE_ARCHIVE_SCREENSHOTS, off to bound disk use
+    pub database_url: Option<String>,   // WORDLE_DATABASE_URL, defaults to a file in data_dir
+    pub completion_threads: bool,       // WORDLE_COMPLETION_THREADS
+    pub combine_completions: bool,      // WORDLE_COMBINE_COMPLETIONS, one message per screenshot
+    pub annotate_completions: bool,     // WORDLE_ANNOTATE_COMPLETIONS, attach the detection boxed
+    pub max_downloads: usize,           // WORDLE_MAX_DOWNLOADS
+    pub avatar_size: Option<u32>, // WORDLE_AVATAR_SIZE, avatars are used as downloaded if unset
+    pub download_retries: u32,    // WORDLE_DOWNLOAD_RETRIES
+    pub max_backoff_secs: f64,    // WORDLE_MAX_BACKOFF_SECS
+    pub download_timeout_secs: f64, // WORDLE_DOWNLOAD_TIMEOUT_SECS
+    pub download_retention_hours: Option<f64>, // WORDLE_DOWNLOAD_RETENTION_HOURS, kept forever if unset
+    pub catch_up_hours: Option<f64>, // WORDLE_CATCH_UP_HOURS, completions missed while down are lost if unset
+    #[serde(deserialize_with = "deserialize_time")]
+    pub reminder_time: Option<NaiveTime>, // WORDLE_REMINDER_TIME, e.g. 20:00 in each guild's timezone
+    #[serde(deserialize_with = "deserialize_time")]
+    pub fastest_time: Option<NaiveTime>, // WORDLE_FASTEST_TIME, when the day's fastest solver is announced
+    pub http_port: Option<u16>,                  // WORDLE_HTTP_PORT
+    pub proxy_url: Option<String>,               // WORDLE_PROXY_URL, or HTTPS_PROXY
+    pub marker_templates: Vec<String>, // WORDLE_MARKER_TEMPLATES, comma-separated, e.g. one per theme
+    pub marker_thresholds: HashMap<String, f64>, // WORDLE_MARKER_THRESHOLDS, as path=threshold,...
+    pub activity_names: Vec<String>, // WORDLE_ACTIVITY_NAMES, comma-separated, matched ignoring case
+    pub track_presence: bool, // WORDLE_TRACK_PRESENCE, time games by players' Wordle activity
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            token: None,
+            channel_name: "daily-puzzles".to_string(),
+            channel_id: None,
+            debug_channel_name: None, // Only the tracked channel is watched
+            timezone: chrono_tz::Australia::Sydney,
+            embed: EmbedStyle::default(),
+            locale: Locale::English,
+            webhook: WebhookConfig::default(),
+            grid_colours: GridColours::default(),
+            dry_run: false,
+            min_active_secs: 0.0, // Show every time
+            midnight_grace_secs: 15.0 * 60.0,
+            post_cooldown_secs: 30.0,
+            completion_reaction: None, // Only screenshots and shares finish games
+            confidence_votes: DEFAULT_CONFIDENCE_VOTES,
+            marker_band_reach: None,
+            data_dir: PathBuf::from("./data"),
+            archive_screenshots: false,
+            database_url: None,
+            completion_threads: false,
+            combine_completions: false,
+            annotate_completions: false,
+            max_downloads: DEFAULT_MAX_DOWNLOADS,
+            avatar_size: None,
+            download_retries: RetryPolicy::default().max_retries,
+            max_backoff_secs: RetryPolicy::default().max_backoff.as_secs_f64(),
+            download_timeout_secs: DEFAULT_DOWNLOAD_TIMEOUT.as_secs_f64(),
+            download_retention_hours: None,
+            catch_up_hours: None,
+            reminder_time: None,
+            fastest_time: None,
+            http_port: None,
+            proxy_url: None,
+            marker_templates: vec![SOLVED_TEMPLATE.to_string()],
+            marker_thresholds: HashMap::new(),
+            activity_names: DEFAULT_ACTIVITY_NAMES.map(String::from).to_vec(),
+            track_presence: false,
+        }
+    }
+}
+
+impl Config {
+    /// Parses a config file's contents, leaving out anything the environment sets
+    pub fn from_toml(contents: &str) -> Result<Config> {
+        parse_toml(contents, DEFAULT_CONFIG_PATH)
+    }
+
+    /// Reads the config file at `path`, if there is one, then applies the environment
+    pub fn load(path: &Path) -> Result<Config> {
+        let mut config = match std::fs::read_to_string(path) {
+            Ok(contents) => parse_toml(&contents, &path.display().to_string())?,
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(why) => {
+                return Err(WordleError::Config {
+                    path: path.display().to_string(),
+                    reason: why.to_string(),
+                });
+            }
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+        Ok(config)
+    }
+
+    /// Overrides settings with the environment variables `var` returns a value for
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        fn parse<T: std::str::FromStr>(key: &str, value: String) -> Result<T> {
+            value.parse().map_err(|_| WordleError::InvalidSetting {
+                key: key.to_string(),
+                value,
+            })
+        }
+        // Durations and reaches, which must be a finite amount that isn't negative
+        fn amount(key: &str, value: String) -> Result<f64> {
+            match value.parse() {
+                Ok(amount) if is_valid_amount(amount) => Ok(amount),
+                _ => Err(WordleError::InvalidSetting {
+                    key: key.to_string(),
+                    value,
+                }),
+            }
+        }
+        fn flag(value: String) -> bool {
+            matches!(value.to_lowercase().as_str(), "1" | "true" | "yes")
+        }
+
+        if let Some(value) = var("DISCORD_TOKEN") {
+            self.token = Some(value);
+        }
+        if let Some(value) = var("DAILY_PUZZLES_CHANNEL_NAME") {
+            self.channel_name = value;
+        }
+        if let Some(value) = var("DAILY_PUZZLES_CHANNEL_ID") {
+            self.channel_id = Some(parse("DAILY_PUZZLES_CHANNEL_ID", value)?);
+        }
+        if let Some(value) = var("DEBUG_CHANNEL_NAME") {
+            self.debug_channel_name = Some(value);
+        }
+        if let Some(value) = var("WORDLE_TIMEZONE") {
+            self.timezone = parse("WORDLE_TIMEZONE", value)?;
+        }
+        for (key, colour) in [
+            ("WORDLE_GRID_GREEN", &mut self.grid_colours.green),
+            ("WORDLE_GRID_YELLOW", &mut self.grid_colours.yellow),
+            ("WORDLE_GRID_GREY", &mut self.grid_colours.grey),
+        ] {
+            if let Some(value) = var(key) {
+                *colour = parse_hex_colour(&value).ok_or_else(|| WordleError::InvalidSetting {
+                    key: key.to_string(),
+                    value,
+                })?;
+            }
+        }
+        if let Some(value) = var("WORDLE_GRID_TOLERANCE") {
+            self.grid_colours.tolerance = parse("WORDLE_GRID_TOLERANCE", value)?;
+        }
+        if let Some(value) = var("WORDLE_LOCALE") {
+            self.locale = parse("WORDLE_LOCALE", value)?;
+        }
+        if let Some(value) = var("WORDLE_EMBED_TITLE") {
+            self.embed.title = value;
+        }
+        if let Some(value) = var("WORDLE_EMBED_FOOTER") {
+            self.embed.footer = value;
+        }
+        if let Some(value) = var("WORDLE_EMBED_COLOUR") {
+            self.embed.colour =
+                parse_hex_colour(&value).ok_or_else(|| WordleError::InvalidSetting {
+                    key: "WORDLE_EMBED_COLOUR".to_string(),
+                    value,
+                })?;
+        }
+        if let Some(value) = var("WORDLE_WEBHOOK_URL") {
+            self.webhook.url = Some(value);
+        }
+        if let Some(value) = var("WORDLE_WEBHOOK_NAME") {
+            self.webhook.name = Some(value);
+        }
+        if let Some(value) = var("WORDLE_WEBHOOK_AVATAR_URL") {
+            self.webhook.avatar_url = Some(value);
+        }
+        if let Some(value) = var("WORDLE_DRY_RUN") {
+            self.dry_run = flag(value);
+        }
+        if let Some(value) = var("WORDLE_MIN_ACTIVE_SECS") {
+            self.min_active_secs = amount("WORDLE_MIN_ACTIVE_SECS", value)?;
+        }
+        if let Some(value) = var("WORDLE_MIDNIGHT_GRACE_SECS") {
+            self.midnight_grace_secs = amount("WORDLE_MIDNIGHT_GRACE_SECS", value)?;
+        }
+        if let Some(value) = var("WORDLE_POST_COOLDOWN_SECS") {
+            self.post_cooldown_secs = amount("WORDLE_POST_COOLDOWN_SECS", value)?;
+        }
+        if let Some(value) = var("WORDLE_COMPLETION_REACTION") {
+            self.completion_reaction = Some(value);
+        }
+        if let Some(value) = var("WORDLE_CONFIDENCE_VOTES") {
+            self.confidence_votes = parse("WORDLE_CONFIDENCE_VOTES", value)?;
+        }
+        if let Some(value) = var("WORDLE_MARKER_BAND_REACH") {
+            self.marker_band_reach = Some(amount("WORDLE_MARKER_BAND_REACH", value)?);
+        }
+        if let Some(value) = var("WORDLE_DATA_DIR") {
+            self.data_dir = PathBuf::from(value);
+        }
+        if let Some(value) = var("WORDLE_ARCHIVE_SCREENSHOTS") {
+            self.archive_screenshots = flag(value);
+        }
+        if let Some(value) = var("WORDLE_DATABASE_URL") {
+            self.database_url = Some(value);
+        }
+        if let Some(value) = var("WORDLE_COMPLETION_THREADS") {
+            self.completion_threads = flag(value);
+        }
+        if let Some(value) = var("WORDLE_COMBINE_COMPLETIONS") {
+            self.combine_completions = flag(value);
+        }
+        if let Some(value) = var("WORDLE_ANNOTATE_COMPLETIONS") {
+            self.annotate_completions = flag(value);
+        }
+        if let Some(value) = var("WORDLE_MAX_DOWNLOADS") {
+            self.max_downloads = parse("WORDLE_MAX_DOWNLOADS", value)?;
+        }
+        if let Some(value) = var("WORDLE_AVATAR_SIZE") {
+            self.avatar_size = Some(parse("WORDLE_AVATAR_SIZE", value)?);
+        }
+        if let Some(value) = var("WORDLE_DOWNLOAD_RETRIES") {
+            self.download_retries = parse("WORDLE_DOWNLOAD_RETRIES", value)?;
+        }
+        if let Some(value) = var("WORDLE_MAX_BACKOFF_SECS") {
+            self.max_backoff_secs = amount("WORDLE_MAX_BACKOFF_SECS", value)?;
+        }
+        if let Some(value) = var("WORDLE_DOWNLOAD_TIMEOUT_SECS") {
+            self.download_timeout_secs = amount("WORDLE_DOWNLOAD_TIMEOUT_SECS", value)?;
+        }
+        if let Some(value) = var("WORDLE_DOWNLOAD_RETENTION_HOURS") {
+            self.download_retention_hours = Some(amount("WORDLE_DOWNLOAD_RETENTION_HOURS", value)?);
+        }
+        if let Some(value) = var("WORDLE_CATCH_UP_HOURS") {
+            self.catch_up_hours = Some(amount("WORDLE_CATCH_UP_HOURS", value)?);
+        }
+        if let Some(value) = var("WORDLE_REMINDER_TIME") {
+            self.reminder_time = Some(parse("WORDLE_REMINDER_TIME", value)?);
+        }
+        if let Some(value) = var("WORDLE_FASTEST_TIME") {
+            self.fastest_time = Some(parse("WORDLE_FASTEST_TIME", value)?);
+        }
+        if let Some(value) = var("WORDLE_HTTP_PORT") {
+            self.http_port = Some(parse("WORDLE_HTTP_PORT", value)?);
+        }
+        if let Some(value) = var("WORDLE_PROXY_URL").or_else(|| var("HTTPS_PROXY")) {
+            self.proxy_url = Some(value);
+        }
+        if let Some(value) = var("WORDLE_MARKER_TEMPLATES") {
+            self.marker_templates = value
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(value) = var("WORDLE_TRACK_PRESENCE") {
+            self.track_presence = flag(value);
+        }
+        if let Some(value) = var("WORDLE_ACTIVITY_NAMES") {
+            self.activity_names = value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(value) = var("WORDLE_MARKER_THRESHOLDS") {
+            self.marker_thresholds = value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| match entry.rsplit_once('=') {
+                    Some((path, threshold)) => Ok((
+                        path.trim().to_string(),
+                        parse("WORDLE_MARKER_THRESHOLDS", threshold.trim().to_string())?,
+                    )),
+                    None => Err(WordleError::InvalidSetting {
+                        key: "WORDLE_MARKER_THRESHOLDS".to_string(),
+                        value: entry.to_string(),
+                    }),
+                })
+                .collect::<Result<_>>()?;
+        }
+
+        Ok(())
+    }
+
+    /// The channel to track, by ID if one was given since that survives renames
+    pub fn tracked_channel(&self) -> TrackedChannel {
+        match self.channel_id {
+            Some(id) => TrackedChannel::Id(id),
+            None => TrackedChannel::Name(self.channel_name.clone()),
+        }
+    }
+
+    /// Where completions are stored
+    pub fn database_url(&self) -> String {
+        self.database_url
+            .clone()
+            .unwrap_or_else(|| format!("sqlite://{}", self.data_dir.join("wordle.db").display()))
+    }
+
+    /// How failed downloads are retried
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.download_retries,
+            max_backoff: std::time::Duration::from_secs_f64(self.max_backoff_secs),
+        }
+    }
+
+    /// Where avatars are looked for in a screenshot: only around its completion markers
+    /// if given a reach, so avatars elsewhere (e.g. a member list) can't be credited
+    pub fn search_region(&self) -> SearchRegion {
+        match self.marker_band_reach {
+            Some(reach) => SearchRegion::AroundMarkers { reach },
+            None => SearchRegion::Whole,
+        }
+    }
+
+    /// The confidence the marker template at `path` must be matched with
+    pub fn marker_threshold(&self, path: &str) -> f64 {
+        self.marker_thresholds
+            .get(path)
+            .copied()
+            .unwrap_or(DEFAULT_MARKER_THRESHOLD)
+    }
+
+    /// The config for guilds that haven't changed it with /config
+    pub fn guild_defaults(&self) -> GuildConfig {
+        GuildConfig {
+            channel: self.tracked_channel(),
+            timezone: self.timezone,
+            embed: self.embed.localized(self.locale),
+            locale: self.locale,
+        }
+    }
+}
+
+fn parse_toml(contents: &str, path: &str) -> Result<Config> {
+    let config: Config = toml::from_str(contents).map_err(|why| WordleError::Config {
+        path: path.to_string(),
+        reason: why.to_string(),
+    })?;
+
+    // Durations would panic on anything else once converted
+    for (key, amount) in [
+        ("min_active_secs", Some(config.min_active_secs)),
+        ("midnight_grace_secs", Some(config.midnight_grace_secs)),
+        ("post_cooldown_secs", Some(config.post_cooldown_secs)),
+        ("marker_band_reach", config.marker_band_reach),
+        ("max_backoff_secs", Some(config.max_backoff_secs)),
+        ("download_timeout_secs", Some(config.download_timeout_secs)),
+        ("download_retention_hours", config.download_retention_hours),
+        ("catch_up_hours", config.catch_up_hours),
+    ] {
+        if let Some(amount) = amount
+            && !is_valid_amount(amount)
+        {
+            return Err(WordleError::InvalidSetting {
+                key: key.to_string(),
+                value: amount.to_string(),
+            });
+        }
+    }
+    Ok(config)
+}
+
+fn is_valid_amount(amount: f64) -> bool {
+    amount.is_finite() && amount >= 0.0
+}
+
+fn deserialize_timezone<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Tz, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map_err(serde::de::Error::custom)
+}
+
+fn deserialize_locale<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Locale, D::Error> {
+    let code = String::deserialize(deserializer)?;
+    code.parse().map_err(serde::de::Error::custom)
+}
+
+fn deserialize_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveTime>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|time| time.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+fn deserialize_colour<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(u8, u8, u8), D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    parse_hex_colour(&hex)
+        .ok_or_else(|| serde::de::Error::custom(format!("{hex:?} is not a hex colour")))
+}
+
+/// Everything the bot needs to know about a guild to track its games
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildConfig {
+    pub channel: TrackedChannel, // Where the Wordle app posts
+    pub timezone: Tz,            // Decides which puzzle day a game belongs to
+    pub embed: EmbedStyle,
+    pub locale: Locale, // The language of its embeds and command responses
+}
+
+/// Settings a guild has changed from the defaults; `None` means use the default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuildOverrides {
+    pub channel: Option<TrackedChannel>,
+    pub timezone: Option<Tz>,
+    pub embed_title: Option<String>,
+    pub embed_footer: Option<String>,
+    pub embed_colour: Option<(u8, u8, u8)>,
+}
+
+impl GuildOverrides {
+    /// The guild's effective config, falling back to `defaults` for anything not set
+    pub fn apply(&self, defaults: &GuildConfig) -> GuildConfig {
+        GuildConfig {
+            channel: self
+                .channel
+                .clone()
+                .unwrap_or_else(|| defaults.channel.clone()),
+            timezone: self.timezone.unwrap_or(defaults.timezone),
+            embed: EmbedStyle {
+                title: self
+                    .embed_title
+                    .clone()
+                    .unwrap_or_else(|| defaults.embed.title.clone()),
+                footer: self
+                    .embed_footer
+                    .clone()
+                    .unwrap_or_else(|| defaults.embed.footer.clone()),
+                colour: self.embed_colour.unwrap_or(defaults.embed.colour),
+            },
+            locale: defaults.locale,
+        }
+    }
+}
+
+/// Parses an IANA timezone name, e.g. "Australia/Sydney"
+pub fn parse_timezone(value: &str) -> Option<Tz> {
+    value.trim().parse().ok()
+}
+
+/// Parses a colour written as hex, e.g. "#57F287" or "57f287"
+pub fn parse_hex_colour(value: &str) -> Option<(u8, u8, u8)> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
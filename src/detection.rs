@@ -3,114 +3,1291 @@ use opencv::prelude::*;
 
 use opencv::{
     Result,
-    core::{self, Mat, Point, Size},
+    core::{self, Mat, Point, Point2f, Size},
 };
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
 
-type BoundingBox = (Point, Point);
-type MatchResult = (BoundingBox, f64); // (bounding box, confidence score)
+use crate::config::GridColours;
+use crate::metrics::metrics;
 
-/// Detect multiple instances of a template in an image, handling different scales
+pub type BoundingBox = (Point, Point); // (top left, bottom right)
+pub type MatchResult = (BoundingBox, f64); // (bounding box, confidence score)
+
+/// How the needle and haystack are converted before matching
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Preprocess {
+    /// Match the images as they are
+    #[default]
+    None,
+    /// Match on luminance only, ignoring colour noise from recompression
+    Grayscale,
+    /// Match on Canny edges, comparing shapes rather than colours
+    Edges,
+}
+
+/// How the scales tried between `min_scale` and `max_scale` are spaced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScaleSpacing {
+    /// Equal differences between steps
+    #[default]
+    Linear,
+    /// Equal ratios between steps, giving each step the same relative change in size so
+    /// fewer are spent on the extremes of the range
+    Log,
+}
+
+/// How candidate matches are scored
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Scoring {
+    /// Normalised correlation from template matching alone
+    #[default]
+    Correlation,
+    /// Template matching down to `coarse_threshold` to find candidates, which are then
+    /// rescored and ranked by [`ssim`] against the needle
+    ///
+    /// Correlation ignores brightness and contrast, and the anti-aliased edges of round
+    /// avatars drag it down, so a true match can rank just below a merely
+    /// similar-textured patch. [`DetectionParams::threshold`] applies to the SSIM score.
+    Ssim { coarse_threshold: f64 },
+}
+
+/// A rectangle given as fractions of an image's width and height, from its top left
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RectFraction {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl RectFraction {
+    /// The rectangle in pixels of a frame of the given size, clamped to it
+    pub fn to_rect(&self, frame: Size) -> core::Rect {
+        let along = |fraction: f64, length: i32| {
+            ((fraction.clamp(0.0, 1.0) * length as f64).round() as i32).clamp(0, length)
+        };
+        let (left, top) = (along(self.x, frame.width), along(self.y, frame.height));
+        let right = along(self.x + self.width, frame.width);
+        let bottom = along(self.y + self.height, frame.height);
+        core::Rect::new(left, top, (right - left).max(0), (bottom - top).max(0))
+    }
+}
+
+/// Candidates rescored by [`Scoring::Ssim`] for every match wanted
+const SSIM_CANDIDATES_PER_MATCH: usize = 5;
+
+/// Parameters controlling how a template is searched for in an image
 ///
-/// # Arguments
-/// * `needle` - Template image to search for
-/// * `haystack` - Image to search in
-/// * `num_players` - Number of players/matches to find
+/// # Fields
+/// * `num_matches` - Number of players/matches to find
 /// * `min_scale` - Minimum scale factor to try (e.g., 0.8)
 /// * `max_scale` - Maximum scale factor to try (e.g., 1.2)
 /// * `scale_steps` - Number of scale steps to try between min and max
+/// * `scale_spacing` - How those steps are spaced, see [`ScaleSpacing`]
 /// * `threshold` - Minimum confidence score to consider a match valid (0.0 to 1.0)
+/// * `rotation_steps` - Number of rotation steps to try either side of 0° (0 disables rotation)
+/// * `max_rotation_deg` - Largest rotation to try in each direction, in degrees (e.g., 5.0)
+/// * `short_circuit_threshold` - Stop trying further scales once `num_matches` matches at
+///   least this confident have been found (`None` always searches every scale)
+/// * `preprocess` - Conversion applied to both images before matching, see [`Preprocess`]
+/// * `scoring` - How matches are scored and ranked, see [`Scoring`]
+/// * `box_padding` - Fraction of a match's width and height to grow its box by on every
+///   side, clamped to the haystack, e.g. to take in the result grid beside an avatar
+///   (0.0 keeps the tight box)
+/// * `inner_crop_fraction` - Fraction of the needle's width and height to match with,
+///   around its centre, so a badge or status ring over the avatar's edge doesn't spoil
+///   the match (1.0 matches the whole needle). Boxes still cover the whole needle.
+/// * `roi` - The part of the haystack to search, leaving out e.g. UI chrome with
+///   avatar-like elements (`None` searches all of it). Boxes are still in the
+///   haystack's coordinates.
+/// * `circular` - Match only the largest circle centred in the needle, as avatars are
+///   drawn, through a mask, so its corners needn't agree with whatever is behind the
+///   circle (false matches the whole needle)
+/// * `needle_fingerprint` - Identifies the needle among the needles already resized, see
+///   [`needle_fingerprint`], computed once when it is loaded (`None` resizes it again on
+///   every search)
+#[derive(Debug, Clone, Copy)]
+pub struct DetectionParams {
+    pub num_matches: usize,
+    pub min_scale: f64,
+    pub max_scale: f64,
+    pub scale_steps: usize,
+    pub scale_spacing: ScaleSpacing,
+    pub threshold: f64,
+    pub rotation_steps: usize,
+    pub max_rotation_deg: f64,
+    pub short_circuit_threshold: Option<f64>,
+    pub preprocess: Preprocess,
+    pub scoring: Scoring,
+    pub box_padding: f64,
+    pub inner_crop_fraction: f64,
+    pub roi: Option<RectFraction>,
+    pub circular: bool,
+    pub needle_fingerprint: Option<u64>,
+}
+
+impl Default for DetectionParams {
+    fn default() -> Self {
+        Self {
+            num_matches: 1,
+            min_scale: 0.6,
+            max_scale: 1.4,
+            scale_steps: 100,
+            scale_spacing: ScaleSpacing::Linear,
+            threshold: 0.95,
+            rotation_steps: 0,
+            max_rotation_deg: 0.0,
+            short_circuit_threshold: Some(0.99),
+            preprocess: Preprocess::None,
+            scoring: Scoring::Correlation,
+            box_padding: 0.0,
+            inner_crop_fraction: 1.0,
+            roi: None,
+            circular: false,
+            needle_fingerprint: None,
+        }
+    }
+}
+
+/// Counters describing how much work a search did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetectionStats {
+    pub scales_searched: usize, // Scale steps actually tried before stopping
+}
+
+/// Tight `(min_scale, max_scale)` window for a needle expected to appear `target_px`
+/// across in the haystack
+///
+/// `tolerance` is the allowed relative error either side of the exact scale, e.g. 0.1
+/// for ±10%. Use it for [`DetectionParams::min_scale`] and [`DetectionParams::max_scale`]
+/// when the screenshot layout is known, so far fewer scale steps are needed.
+pub fn scale_range_for_target(needle: &Mat, target_px: i32, tolerance: f64) -> (f64, f64) {
+    let native_px = needle.cols().max(needle.rows()).max(1);
+    let scale = target_px as f64 / native_px as f64;
+
+    (
+        scale * (1.0 - tolerance).max(0.0),
+        scale * (1.0 + tolerance),
+    )
+}
+
+/// Convert an (RGB, as loaded by this crate) image as described by `preprocess`
+///
+/// The output keeps the input's size, so coordinates found on it apply to the original.
+pub fn preprocess_image(image: &Mat, preprocess: Preprocess) -> Result<Mat> {
+    if preprocess == Preprocess::None {
+        return Ok(image.clone());
+    }
+
+    let grey = match image.channels() {
+        1 => image.clone(),
+        channels => {
+            let code = if channels == 4 {
+                imgproc::COLOR_RGBA2GRAY
+            } else {
+                imgproc::COLOR_RGB2GRAY
+            };
+            let mut grey = Mat::default();
+            imgproc::cvt_color_def(image, &mut grey, code)?;
+            grey
+        }
+    };
+    if preprocess == Preprocess::Grayscale {
+        return Ok(grey);
+    }
+
+    let mut edges = Mat::default();
+    imgproc::canny_def(&grey, &mut edges, 50.0, 150.0)?;
+    Ok(edges)
+}
+
+/// A mask of a circle `diameter` across, centred in a frame of `size`
+///
+/// Avatars are rendered as circles in screenshots, so the corners of a square avatar
+/// never agree with the haystack and would drag the match confidence down, whatever
+/// they were painted.
+fn circle_mask(size: Size, diameter: i32) -> Result<Mat> {
+    let mut mask = Mat::zeros(size.height, size.width, core::CV_8UC1)?.to_mat()?;
+    imgproc::circle(
+        &mut mask,
+        Point::new(size.width / 2, size.height / 2),
+        diameter / 2,
+        core::Scalar::all(255.0),
+        -1, // Fill the circle
+        imgproc::LINE_8,
+        0,
+    )?;
+    Ok(mask)
+}
+
+/// Levels each colour channel is split into by [`colour_histogram`]
+const COLOUR_LEVELS: i32 = 8;
+
+/// Pixels of a colour a haystack needs before it counts as showing it, so that a few
+/// stray pixels, e.g. of JPEG ringing, can't stand in for part of an avatar
+const MIN_COLOUR_PIXELS: f32 = 16.0;
+
+/// Coarse colour histogram of a whole 3-channel image, for [`colour_coverage`]
+///
+/// Worth computing once per screenshot when checking several avatars against it.
+pub fn colour_histogram(image: &Mat) -> Result<Mat> {
+    histogram_masked(image, &core::no_array())
+}
+
+fn histogram_masked(image: &Mat, mask: &impl core::ToInputArray) -> Result<Mat> {
+    if image.channels() != 3 {
+        return Err(opencv::Error::new(
+            core::StsUnmatchedFormats,
+            format!(
+                "Image has {} channel(s) but colours are compared in 3",
+                image.channels()
+            ),
+        ));
+    }
+
+    let mut histogram = Mat::default();
+    imgproc::calc_hist(
+        image,
+        &core::Vector::from_slice(&[0, 1, 2]),
+        mask,
+        &mut histogram,
+        &core::Vector::from_slice(&[COLOUR_LEVELS; 3]),
+        &core::Vector::from_slice(&[0.0, 256.0, 0.0, 256.0, 0.0, 256.0]),
+        false,
+    )?;
+    Ok(histogram)
+}
+
+/// Share of the needle's pixels, within its centred circle, whose colour the haystack
+/// shows somewhere, from the haystack's [`colour_histogram`]
+///
+/// A needle can only be in the haystack if its colours are, so a low coverage rules it
+/// out far more cheaply than searching for it. Colours are compared coarsely, and a
+/// neighbouring level counts as the same colour, so rescaling and recompression don't
+/// shift one out of coverage.
+pub fn colour_coverage(needle: &Mat, haystack_histogram: &Mat) -> Result<f64> {
+    let needle_histogram = histogram_masked(
+        needle,
+        &circle_mask(needle.size()?, needle.cols().min(needle.rows()))?,
+    )?;
+    let near = |level: i32| (level - 1).max(0)..=(level + 1).min(COLOUR_LEVELS - 1);
+
+    let (mut covered, mut total) = (0.0, 0.0);
+    for r in 0..COLOUR_LEVELS {
+        for g in 0..COLOUR_LEVELS {
+            for b in 0..COLOUR_LEVELS {
+                let pixels = *needle_histogram.at_3d::<f32>(r, g, b)? as f64;
+                if pixels == 0.0 {
+                    continue;
+                }
+
+                let mut shown = 0.0;
+                for hr in near(r) {
+                    for hg in near(g) {
+                        for hb in near(b) {
+                            shown += *haystack_histogram.at_3d::<f32>(hr, hg, hb)?;
+                        }
+                    }
+                }
+                total += pixels;
+                if shown >= MIN_COLOUR_PIXELS {
+                    covered += pixels;
+                }
+            }
+        }
+    }
+
+    // An empty needle has no colours to be missing
+    Ok(if total == 0.0 { 1.0 } else { covered / total })
+}
+
+/// Rows in a Wordle result grid, one per guess
+pub const GRID_ROWS: i32 = 6;
+
+/// Share of a row's pixels that must be square colours for the row to hold a guess
+///
+/// A filled row is mostly squares, less the gaps between them and their letters. An
+/// empty row has only outlines, in none of the square colours.
+const FILLED_ROW_SHARE: f64 = 0.25;
+
+/// Counts the rows of a result grid holding a guess, going by the colours of their
+/// squares, as an estimate of the guesses taken
+///
+/// `grid` is the grid alone, cropped from a screenshot loaded as RGB. It's split into
+/// [`GRID_ROWS`] equal bands, each filled when enough of it is green, yellow or grey,
+/// give or take the colours' tolerance.
+pub fn count_filled_rows(grid: &Mat, colours: &GridColours) -> Result<u32> {
+    if grid.channels() != 3 {
+        return Err(opencv::Error::new(
+            core::StsUnmatchedFormats,
+            format!(
+                "Grid has {} channel(s) but square colours are RGB; load it as RGB",
+                grid.channels()
+            ),
+        ));
+    }
+
+    let tolerance = colours.tolerance as f64;
+    let bounds = |(r, g, b): (u8, u8, u8)| {
+        let channel = |value: u8| {
+            (
+                (value as f64 - tolerance).max(0.0),
+                (value as f64 + tolerance).min(255.0),
+            )
+        };
+        let (r, g, b) = (channel(r), channel(g), channel(b));
+        (
+            core::Scalar::new(r.0, g.0, b.0, 0.0),
+            core::Scalar::new(r.1, g.1, b.1, 0.0),
+        )
+    };
+    let targets = [colours.green, colours.yellow, colours.grey].map(bounds);
+
+    let row_height = grid.rows() / GRID_ROWS;
+    if row_height == 0 || grid.cols() == 0 {
+        return Ok(0);
+    }
+
+    let mut filled = 0;
+    for row in 0..GRID_ROWS {
+        let band = grid.roi(core::Rect::new(
+            0,
+            row * row_height,
+            grid.cols(),
+            row_height,
+        ))?;
+        // The colours' ranges can overlap, so a pixel is counted once whichever it's in
+        let mut coloured = Mat::zeros(row_height, grid.cols(), core::CV_8UC1)?.to_mat()?;
+        for (lower, upper) in &targets {
+            let mut mask = Mat::default();
+            core::in_range(&band, lower, upper, &mut mask)?;
+            let mut either = Mat::default();
+            core::bitwise_or_def(&coloured, &mask, &mut either)?;
+            coloured = either;
+        }
+
+        let share = core::count_non_zero(&coloured)? as f64 / (row_height * grid.cols()) as f64;
+        if share >= FILLED_ROW_SHARE {
+            filled += 1;
+        }
+    }
+
+    Ok(filled)
+}
+
+/// The centre of an image, `fraction` of its width and height
+fn crop_to_centre(image: &Mat, fraction: f64) -> Result<Mat> {
+    let width = ((image.cols() as f64 * fraction).round() as i32).max(1);
+    let height = ((image.rows() as f64 * fraction).round() as i32).max(1);
+    let centre = core::Rect::new(
+        (image.cols() - width) / 2,
+        (image.rows() - height) / 2,
+        width,
+        height,
+    );
+
+    image.roi(centre)?.try_clone()
+}
+
+/// Rotate an image about its centre, growing the canvas so that no corners are clipped
+pub fn rotate_image(image: &Mat, angle_deg: f64) -> Result<Mat> {
+    let width = image.cols() as f64;
+    let height = image.rows() as f64;
+    let (sin, cos) = angle_deg.to_radians().sin_cos();
+    let rotated_size = Size::new(
+        (width * cos.abs() + height * sin.abs()).round() as i32,
+        (width * sin.abs() + height * cos.abs()).round() as i32,
+    );
+
+    let center = Point2f::new(width as f32 / 2.0, height as f32 / 2.0);
+    let mut rotation = imgproc::get_rotation_matrix_2d(center, angle_deg, 1.0)?;
+
+    // Shift the result so the rotated image sits in the middle of the enlarged canvas
+    *rotation.at_2d_mut::<f64>(0, 2)? += (rotated_size.width as f64 - width) / 2.0;
+    *rotation.at_2d_mut::<f64>(1, 2)? += (rotated_size.height as f64 - height) / 2.0;
+
+    let mut rotated = Mat::default();
+    imgproc::warp_affine(
+        image,
+        &mut rotated,
+        &rotation,
+        rotated_size,
+        imgproc::INTER_LINEAR,
+        core::BORDER_CONSTANT,
+        core::Scalar::all(0.0),
+    )?;
+
+    Ok(rotated)
+}
+
+/// Detect multiple instances of a template in an image, handling different scales
+/// and, optionally, small rotations
+///
+/// # Arguments
+/// * `needle` - Template image to search for
+/// * `haystack` - Image to search in
+/// * `params` - Search parameters, see [`DetectionParams`]
+///
+/// Rotation is opt-in as every rotation step multiplies the cost of the search. Matches
+/// found with a rotated needle are reported as the axis-aligned box of the rotated needle.
 pub fn detect_needle_in_haystack(
     needle: &Mat,
     haystack: &Mat,
-    num_players: usize,
-    min_scale: f64,
-    max_scale: f64,
-    scale_steps: usize,
-    threshold: f64,
+    params: &DetectionParams,
 ) -> Result<Vec<MatchResult>> {
+    detect_needle_in_haystack_with_stats(needle, haystack, params).map(|(matches, _)| matches)
+}
+
+/// Same as [`detect_needle_in_haystack`], also reporting how much of the search was run
+pub fn detect_needle_in_haystack_with_stats(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+) -> Result<(Vec<MatchResult>, DetectionStats)> {
+    search(needle, haystack, params, Some(params.num_matches))
+}
+
+/// Every match above the threshold, best first, however many there are
+///
+/// Unlike [`detect_needle_in_haystack`], `params.num_matches` is ignored, for when it
+/// isn't known how many instances to expect (e.g. how many players finished). Every
+/// scale is searched, and matches of the same instance at different scales are merged
+/// by [`suppress_overlaps`].
+pub fn detect_all_above_threshold(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+) -> Result<Vec<MatchResult>> {
+    search(needle, haystack, params, None).map(|(matches, _)| matches)
+}
+
+/// Every scale a search tries, from `min_scale` to `max_scale` in `scale_steps` steps
+///
+/// Logarithmic spacing needs a positive `min_scale`, falling back to linear otherwise.
+pub fn scale_values(params: &DetectionParams) -> Vec<f64> {
+    let (min, max) = (params.min_scale, params.max_scale);
+    if params.scale_steps == 0 {
+        return vec![min];
+    }
+
+    let steps = params.scale_steps as f64;
+    let scale_step = (max - min) / steps;
+    (0..=params.scale_steps)
+        .map(|step| match params.scale_spacing {
+            ScaleSpacing::Log if min > 0.0 => min * (max / min).powf(step as f64 / steps),
+            _ => min + (step as f64 * scale_step),
+        })
+        .collect()
+}
+
+/// Resized needles kept for reuse before the oldest is dropped
+pub const RESIZE_CACHE_CAPACITY: usize = 1024;
+
+/// Which needle was resized: its fingerprint, and how it was preprocessed and cropped
+/// before resizing
+type NeedleKey = (u64, Preprocess, u64);
+
+/// A resized needle's key: the needle and the size it was resized to
+type ResizeKey = (NeedleKey, (i32, i32));
+
+/// Needles already resized to each scale searched
+///
+/// The same templates and avatars are searched for at the same scales in every
+/// screenshot, whatever its size, so most resizes repeat. Bounded to
+/// [`RESIZE_CACHE_CAPACITY`] needles, dropping the oldest first.
+#[derive(Default)]
+struct ResizeCache {
+    needles: HashMap<ResizeKey, Arc<Mat>>,
+    order: VecDeque<ResizeKey>, // Oldest first
+}
+
+fn resize_cache() -> &'static Mutex<ResizeCache> {
+    static CACHE: OnceLock<Mutex<ResizeCache>> = OnceLock::new();
+    CACHE.get_or_init(Mutex::default)
+}
+
+/// A hash of a needle's size, type and pixels, telling needles apart among those
+/// already resized, see [`DetectionParams::needle_fingerprint`]
+pub fn needle_fingerprint(needle: &Mat) -> Result<u64> {
+    let continuous;
+    let needle = if needle.is_continuous() {
+        needle
+    } else {
+        continuous = needle.try_clone()?;
+        &continuous
+    };
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (needle.rows(), needle.cols(), needle.typ()).hash(&mut hasher);
+    needle.data_bytes()?.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+/// The needle resized to `scaled_size`, from the cache if the needle identified by
+/// `needle_key` was resized to this size before
+fn resized_needle(
+    needle: &Mat,
+    needle_key: Option<NeedleKey>,
+    scaled_size: Size,
+) -> Result<Arc<Mat>> {
+    let resize = || -> Result<Arc<Mat>> {
+        let mut scaled_needle = Mat::default();
+        imgproc::resize(
+            needle,
+            &mut scaled_needle,
+            scaled_size,
+            0.0,
+            0.0,
+            imgproc::INTER_LINEAR,
+        )?;
+        Ok(Arc::new(scaled_needle))
+    };
+    let Some(needle_key) = needle_key else {
+        return resize();
+    };
+
+    let key = (needle_key, (scaled_size.width, scaled_size.height));
+    let lock = || {
+        resize_cache()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    };
+    if let Some(cached) = lock().needles.get(&key) {
+        metrics().resize_cache_hits.inc();
+        return Ok(cached.clone());
+    }
+    metrics().resize_cache_misses.inc();
+    let scaled_needle = resize()?;
+
+    let mut cache = lock();
+    if cache.needles.insert(key, scaled_needle.clone()).is_none() {
+        cache.order.push_back(key);
+    }
+    while cache.order.len() > RESIZE_CACHE_CAPACITY {
+        if let Some(oldest) = cache.order.pop_front() {
+            cache.needles.remove(&oldest);
+        }
+    }
+    Ok(scaled_needle)
+}
+
+/// Searches every scale and rotation, keeping up to `limit` of the best matches, or all
+/// of them if `None`
+fn search(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+    limit: Option<usize>,
+) -> Result<(Vec<MatchResult>, DetectionStats)> {
+    // A needle loaded with a different colour mode than the haystack can never match, and
+    // match_template's own assertion failure doesn't say why
+    if needle.typ() != haystack.typ() {
+        return Err(opencv::Error::new(
+            core::StsUnmatchedFormats,
+            format!(
+                "Needle is {} ({} channel(s)) but haystack is {} ({} channel(s)); \
+                 load both with the same colour mode",
+                core::type_to_string(needle.typ())?,
+                needle.channels(),
+                core::type_to_string(haystack.typ())?,
+                haystack.channels(),
+            ),
+        ));
+    }
+
+    let (matches, stats) = match params.roi {
+        Some(roi) => search_roi(needle, haystack, roi, params, limit)?,
+        None => search_unpadded(needle, haystack, params, limit)?,
+    };
+    // Padded in the haystack's frame, so boxes found in a region of interest can grow
+    // past its edges
+    let matches = pad_matches(matches, params, haystack.size()?);
+    log::debug!(
+        "Best matches after {} scale(s): {:?}",
+        stats.scales_searched,
+        matches
+    );
+
+    Ok((matches, stats))
+}
+
+/// Same as [`search`] over the whole haystack, leaving the boxes as matched
+fn search_unpadded(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+    limit: Option<usize>,
+) -> Result<(Vec<MatchResult>, DetectionStats)> {
+    let needle = &preprocess_image(needle, params.preprocess)?;
+    let haystack = &preprocess_image(haystack, params.preprocess)?;
+    let cropped = params.inner_crop_fraction > 0.0 && params.inner_crop_fraction < 1.0;
+    let needle = &if cropped {
+        crop_to_centre(needle, params.inner_crop_fraction)?
+    } else {
+        needle.clone()
+    };
+
+    // Rescoring needs more candidates than matches wanted, found below the final threshold
+    let (matching, candidate_limit) = match params.scoring {
+        Scoring::Correlation => (*params, limit),
+        Scoring::Ssim { coarse_threshold } => (
+            DetectionParams {
+                threshold: coarse_threshold,
+                ..*params
+            },
+            limit.map(|limit| limit * SSIM_CANDIDATES_PER_MATCH),
+        ),
+    };
+
     let mut matches: Vec<MatchResult> = Vec::new();
-    let scale_step = (max_scale - min_scale) / (scale_steps as f64);
+    let mut stats = DetectionStats::default();
+    let rotation_steps = params.rotation_steps as i32;
+    let needle_key = params.needle_fingerprint.map(|fingerprint| {
+        (
+            fingerprint,
+            params.preprocess,
+            params.inner_crop_fraction.to_bits(),
+        )
+    });
 
     // Try different scales
-    for step in 0..=scale_steps {
-        let scale = min_scale + (step as f64 * scale_step);
+    for scale in scale_values(params) {
         let scaled_size = Size::new(
             (needle.cols() as f64 * scale) as i32,
             (needle.rows() as f64 * scale) as i32,
         );
+        if scaled_size.width < 1 || scaled_size.height < 1 {
+            log::debug!("Skipping scale {scale}, the needle shrinks to nothing");
+            continue;
+        }
 
         // Resize template to current scale
-        let mut scaled_needle = Mat::default();
+        let scaled_needle = resized_needle(needle, needle_key, scaled_size)?;
+        let scaled_needle = &*scaled_needle;
+        // Rotating a circle leaves it where it was, only the canvas around it grows
+        let mask = |size: Size| {
+            let diameter = scaled_size.width.min(scaled_size.height);
+            params
+                .circular
+                .then(|| circle_mask(size, diameter))
+                .transpose()
+        };
+
+        // Try different rotations of the scaled template
+        for rotation_step in -rotation_steps..=rotation_steps {
+            if rotation_step == 0 {
+                find_matches(
+                    scaled_needle,
+                    mask(scaled_size)?.as_ref(),
+                    haystack,
+                    &matching,
+                    candidate_limit,
+                    &mut matches,
+                )?;
+            } else {
+                let angle = params.max_rotation_deg * rotation_step as f64 / rotation_steps as f64;
+                let rotated_needle = rotate_image(scaled_needle, angle)?;
+                find_matches(
+                    &rotated_needle,
+                    mask(rotated_needle.size()?)?.as_ref(),
+                    haystack,
+                    &matching,
+                    candidate_limit,
+                    &mut matches,
+                )?;
+            }
+        }
+        stats.scales_searched += 1;
+        log::trace!(
+            "Searched scale {:.3} ({}x{}), {} match(es) so far",
+            scale,
+            scaled_size.width,
+            scaled_size.height,
+            matches.len()
+        );
+
+        // Other scales are unlikely to beat matches this confident, so stop early. Only
+        // distinct instances count, as neighbouring scales find the same one again and
+        // would otherwise fill the quota before larger instances are searched for.
+        if let Some(short_circuit) = params.short_circuit_threshold
+            && let Some(limit) = limit
+        {
+            let confident: Vec<MatchResult> = matches
+                .iter()
+                .filter(|(_, confidence)| *confidence >= short_circuit)
+                .copied()
+                .collect();
+            if suppress_overlaps(&confident).len() >= limit {
+                break;
+            }
+        }
+    }
+
+    rank_matches(&mut matches);
+
+    // The same instance is found again at neighbouring scales
+    let mut matches = suppress_overlaps(&matches);
+    if let Scoring::Ssim { .. } = params.scoring {
+        if let Some(candidate_limit) = candidate_limit {
+            matches.truncate(candidate_limit);
+        }
+        matches = rescore_with_ssim(needle, haystack, &matches, params.threshold)?;
+    }
+    if let Some(limit) = limit {
+        matches.truncate(limit);
+    }
+    Ok((matches, stats))
+}
+
+/// Grows the boxes matched to a needle's centre back to the whole needle, then by
+/// `box_padding`, within `frame`
+fn pad_matches(
+    mut matches: Vec<MatchResult>,
+    params: &DetectionParams,
+    frame: Size,
+) -> Vec<MatchResult> {
+    if params.inner_crop_fraction > 0.0 && params.inner_crop_fraction < 1.0 {
+        // Grow each box from the centre that was matched to the whole needle
+        let padding = (1.0 / params.inner_crop_fraction - 1.0) / 2.0;
+        matches = matches
+            .into_iter()
+            .filter_map(|(bounding_box, confidence)| {
+                pad_box(&bounding_box, padding, frame).map(|padded| (padded, confidence))
+            })
+            .collect();
+    }
+    if params.box_padding > 0.0 {
+        matches = matches
+            .into_iter()
+            .filter_map(|(bounding_box, confidence)| {
+                pad_box(&bounding_box, params.box_padding, frame).map(|padded| (padded, confidence))
+            })
+            .collect();
+    }
+    matches
+}
+
+/// Searches only the `roi` of the haystack, moving the unpadded matches back to its
+/// coordinates
+fn search_roi(
+    needle: &Mat,
+    haystack: &Mat,
+    roi: RectFraction,
+    params: &DetectionParams,
+    limit: Option<usize>,
+) -> Result<(Vec<MatchResult>, DetectionStats)> {
+    let rect = roi.to_rect(haystack.size()?);
+    if rect.width < 1 || rect.height < 1 {
+        log::debug!("Region of interest {roi:?} is empty, nothing to search");
+        return Ok((Vec::new(), DetectionStats::default()));
+    }
+
+    let cropped = haystack.roi(rect)?.try_clone()?;
+    let (matches, stats) = search_unpadded(needle, &cropped, params, limit)?;
+
+    let offset = |point: Point| Point::new(point.x + rect.x, point.y + rect.y);
+    let matches = matches
+        .into_iter()
+        .map(|((top_left, bottom_right), confidence)| {
+            ((offset(top_left), offset(bottom_right)), confidence)
+        })
+        .collect();
+    Ok((matches, stats))
+}
+
+/// Replaces each match's confidence with the [`ssim`] of the needle, resized to fit, and
+/// the haystack under it, keeping those at least `threshold`, best first
+fn rescore_with_ssim(
+    needle: &Mat,
+    haystack: &Mat,
+    matches: &[MatchResult],
+    threshold: f64,
+) -> Result<Vec<MatchResult>> {
+    let mut rescored = Vec::new();
+    for ((top_left, bottom_right), correlation) in matches {
+        let region = core::Rect::new(
+            top_left.x,
+            top_left.y,
+            bottom_right.x - top_left.x,
+            bottom_right.y - top_left.y,
+        );
+        let crop = haystack.roi(region)?.try_clone()?;
+        let mut resized = Mat::default();
         imgproc::resize(
             needle,
-            &mut scaled_needle,
-            scaled_size,
+            &mut resized,
+            region.size(),
             0.0,
             0.0,
             imgproc::INTER_LINEAR,
         )?;
 
-        // Perform template matching
-        let mut result = Mat::default();
-        opencv::imgproc::match_template(
+        let score = ssim(&resized, &crop)?;
+        log::trace!("Rescored match at {top_left:?} from {correlation:.3} to SSIM {score:.3}");
+        if score >= threshold {
+            rescored.push(((*top_left, *bottom_right), score));
+        }
+    }
+
+    rescored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Ok(rescored)
+}
+
+/// Mean structural similarity of two images of the same size and type, from -1.0 to 1.0
+///
+/// Compares local luminance, contrast and structure over 11×11 Gaussian windows, as in
+/// Wang et al. (2004), and averages over every channel.
+pub fn ssim(a: &Mat, b: &Mat) -> Result<f64> {
+    const C1: f64 = 6.5025; // (0.01 × 255)²
+    const C2: f64 = 58.5225; // (0.03 × 255)²
+
+    let to_float = |image: &Mat| -> Result<Mat> {
+        let mut float = Mat::default();
+        image.convert_to(&mut float, core::CV_32F, 1.0, 0.0)?;
+        Ok(float)
+    };
+    let blur = |image: &Mat| -> Result<Mat> {
+        let mut blurred = Mat::default();
+        imgproc::gaussian_blur_def(image, &mut blurred, Size::new(11, 11), 1.5)?;
+        Ok(blurred)
+    };
+    let multiply = |x: &Mat, y: &Mat| -> Result<Mat> {
+        let mut product = Mat::default();
+        core::multiply_def(x, y, &mut product)?;
+        Ok(product)
+    };
+    let add_weighted = |x: &Mat, alpha: f64, y: &Mat, beta: f64, gamma: f64| -> Result<Mat> {
+        let mut sum = Mat::default();
+        core::add_weighted_def(x, alpha, y, beta, gamma, &mut sum)?;
+        Ok(sum)
+    };
+
+    let (a, b) = (to_float(a)?, to_float(b)?);
+    let (mean_a, mean_b) = (blur(&a)?, blur(&b)?);
+    let mean_ab = multiply(&mean_a, &mean_b)?;
+
+    // (2μaμb + C1)(2σab + C2) / ((μa² + μb² + C1)(σa² + σb² + C2))
+    let mut luminance_num = Mat::default();
+    mean_ab.convert_to(&mut luminance_num, -1, 2.0, C1)?;
+    let structure_num = add_weighted(&blur(&multiply(&a, &b)?)?, 2.0, &mean_ab, -2.0, C2)?;
+    let luminance_den = add_weighted(
+        &multiply(&mean_a, &mean_a)?,
+        1.0,
+        &multiply(&mean_b, &mean_b)?,
+        1.0,
+        C1,
+    )?;
+    let variances = add_weighted(
+        &blur(&multiply(&a, &a)?)?,
+        1.0,
+        &blur(&multiply(&b, &b)?)?,
+        1.0,
+        C1 + C2,
+    )?;
+    let structure_den = add_weighted(&variances, 1.0, &luminance_den, -1.0, 0.0)?;
+
+    let mut map = Mat::default();
+    core::divide2_def(
+        &multiply(&luminance_num, &structure_num)?,
+        &multiply(&luminance_den, &structure_den)?,
+        &mut map,
+    )?;
+
+    let mean = core::mean_def(&map)?;
+    let channels = a.channels().clamp(1, 4) as usize;
+    Ok(mean.0[..channels].iter().sum::<f64>() / channels as f64)
+}
+
+/// Ranks matches from any scale fairly, best first, see [`scale_normalized_confidence`]
+pub fn rank_matches(matches: &mut [MatchResult]) {
+    matches.sort_by(|a, b| {
+        scale_normalized_confidence(b)
+            .partial_cmp(&scale_normalized_confidence(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Non-maximum suppression: drops every match overlapping a better one
+///
+/// `matches` must be ranked best first, which the kept matches stay in.
+pub fn suppress_overlaps(matches: &[MatchResult]) -> Vec<MatchResult> {
+    let mut kept: Vec<MatchResult> = Vec::new();
+    for found in matches {
+        if !kept.iter().any(|kept| overlaps(&kept.0, &found.0)) {
+            kept.push(*found);
+        }
+    }
+    kept
+}
+
+/// Whether two boxes share any area
+fn overlaps((a_tl, a_br): &BoundingBox, (b_tl, b_br): &BoundingBox) -> bool {
+    a_tl.x < b_br.x && b_tl.x < a_br.x && a_tl.y < b_br.y && b_tl.y < a_br.y
+}
+
+/// A match's confidence adjusted for the size of the template that found it, for ranking
+/// matches found at different scales against each other
+///
+/// The normalised correlation of two unrelated patches of `n` pixels has a standard
+/// deviation of roughly `1 / √n`, so a smaller (down-scaled) template scores spuriously
+/// high more easily. Subtracting that noise floor puts every scale on equal terms:
+///
+/// `normalised = confidence - 1 / √(width × height)`
+///
+/// Only the ranking uses this. Thresholds and the returned matches keep the raw
+/// confidence.
+pub fn scale_normalized_confidence(((top_left, bottom_right), confidence): &MatchResult) -> f64 {
+    let pixels = ((bottom_right.x - top_left.x) * (bottom_right.y - top_left.y)).max(1);
+    confidence - 1.0 / (pixels as f64).sqrt()
+}
+
+/// Match a single (already scaled/rotated) template against the haystack, appending
+/// every match above the threshold to `matches`
+///
+/// A template larger than the haystack simply can't be there, so it is skipped rather
+/// than treated as an error.
+fn find_matches(
+    templ: &Mat,
+    mask: Option<&Mat>,
+    haystack: &Mat,
+    params: &DetectionParams,
+    limit: Option<usize>,
+    matches: &mut Vec<MatchResult>,
+) -> Result<()> {
+    if templ.cols() > haystack.cols() || templ.rows() > haystack.rows() {
+        log::debug!(
+            "Skipping {}x{} needle, larger than the {}x{} haystack",
+            templ.cols(),
+            templ.rows(),
+            haystack.cols(),
+            haystack.rows()
+        );
+        return Ok(());
+    }
+
+    // Perform template matching
+    let mut result = Mat::default();
+    match mask {
+        Some(mask) => {
+            opencv::imgproc::match_template(haystack, templ, &mut result, TM_CCOEFF_NORMED, mask)?;
+            // Where the haystack is flat under the mask the score divides by zero, which
+            // would otherwise outrank every real match
+            core::patch_na_ns(&mut result, 0.0)?;
+            let mut finite = Mat::default();
+            imgproc::threshold(
+                &result,
+                &mut finite,
+                1.0 + 1e-6,
+                0.0,
+                imgproc::THRESH_TOZERO_INV,
+            )?;
+            result = finite;
+        }
+        None => opencv::imgproc::match_template(
             haystack,
-            &scaled_needle,
+            templ,
             &mut result,
             TM_CCOEFF_NORMED,
             &core::no_array(),
+        )?,
+    }
+
+    // Find matches above threshold, bounded by the positions there are when uncapped
+    for _ in 0..limit.unwrap_or(result.total()) {
+        let mut min_val = 0.0;
+        let mut max_val = 0.0;
+        let mut min_loc = Point::default();
+        let mut max_loc = Point::default();
+
+        core::min_max_loc(
+            &result,
+            Some(&mut min_val),
+            Some(&mut max_val),
+            Some(&mut min_loc),
+            Some(&mut max_loc),
+            &core::no_array(),
         )?;
 
-        // Find matches above threshold
-        for _ in 0..num_players {
-            let mut min_val = 0.0;
-            let mut max_val = 0.0;
-            let mut min_loc = Point::default();
-            let mut max_loc = Point::default();
+        // Stop once the best match left isn't good enough
+        if max_val < params.threshold {
+            break;
+        }
+        let top_left = max_loc;
+        let bottom_right = Point::new(top_left.x + templ.cols(), top_left.y + templ.rows());
+        matches.push(((top_left, bottom_right), max_val));
 
-            core::min_max_loc(
-                &result,
-                Some(&mut min_val),
-                Some(&mut max_val),
-                Some(&mut min_loc),
-                Some(&mut max_loc),
-                &core::no_array(),
+        // Zero out the region around the match to prevent duplicate detections
+        let x1 = (max_loc.x - templ.cols() / 4).max(0);
+        let y1 = (max_loc.y - templ.rows() / 4).max(0);
+        let x2 = (x1 + templ.cols() + templ.cols() / 2).min(result.cols());
+        let y2 = (y1 + templ.rows() + templ.rows() / 2).min(result.rows());
+
+        if x2 > x1 && y2 > y1 {
+            let rect = core::Rect::new(x1, y1, x2 - x1, y2 - y1);
+            imgproc::rectangle(
+                &mut result,
+                rect,
+                core::Scalar::all(0.0),
+                -1, // Fill the rectangle
+                imgproc::LINE_8,
+                0,
             )?;
+        }
+    }
 
-            // If match is good enough, add it to results
-            if max_val >= threshold {
-                let top_left = max_loc;
-                let bottom_right = Point::new(
-                    top_left.x + scaled_needle.cols(),
-                    top_left.y + scaled_needle.rows(),
-                );
-                matches.push(((top_left, bottom_right), max_val));
-
-                // Zero out the region around the match to prevent duplicate detections
-                let x1 = (max_loc.x - scaled_needle.cols() / 4).max(0);
-                let y1 = (max_loc.y - scaled_needle.rows() / 4).max(0);
-                let x2 = (x1 + scaled_needle.cols() + scaled_needle.cols() / 2).min(result.cols());
-                let y2 = (y1 + scaled_needle.rows() + scaled_needle.rows() / 2).min(result.rows());
-
-                if x2 > x1 && y2 > y1 {
-                    let rect = core::Rect::new(x1, y1, x2 - x1, y2 - y1);
-                    imgproc::rectangle(
-                        &mut result,
-                        rect,
-                        core::Scalar::all(0.0),
-                        -1, // Fill the rectangle
-                        imgproc::LINE_8,
-                        0,
-                    )?;
-                }
+    Ok(())
+}
+
+/// Picks the match threshold that best separates labelled examples
+///
+/// Each `(needle, haystack)` pair is scored by its best match under the default search.
+/// Every score is tried as a threshold, keeping the one with the highest F1 (the lowest
+/// on ties, favouring recall). The result is then moved halfway to the next lower score
+/// so it doesn't sit exactly on a known positive. The returned value is meant for
+/// [`DetectionParams::threshold`].
+pub fn calibrate_threshold(positives: &[(Mat, Mat)], negatives: &[(Mat, Mat)]) -> Result<f64> {
+    let params = DetectionParams {
+        threshold: -1.0, // The lowest TM_CCOEFF_NORMED score, so every best match is kept
+        ..Default::default()
+    };
+    let best_score = |(needle, haystack): &(Mat, Mat)| -> Result<f64> {
+        let matches = detect_needle_in_haystack(needle, haystack, &params)?;
+        Ok(matches.first().map_or(-1.0, |(_, confidence)| *confidence))
+    };
+
+    let positive_scores = positives
+        .iter()
+        .map(best_score)
+        .collect::<Result<Vec<_>>>()?;
+    let negative_scores = negatives
+        .iter()
+        .map(best_score)
+        .collect::<Result<Vec<_>>>()?;
+    if positive_scores.is_empty() {
+        return Ok(DetectionParams::default().threshold);
+    }
+
+    let f1 = |threshold: f64| {
+        let true_positives = positive_scores.iter().filter(|s| **s >= threshold).count();
+        let false_positives = negative_scores.iter().filter(|s| **s >= threshold).count();
+        let false_negatives = positive_scores.len() - true_positives;
+        2.0 * true_positives as f64
+            / (2 * true_positives + false_positives + false_negatives) as f64
+    };
+
+    let mut candidates: Vec<f64> = positive_scores
+        .iter()
+        .chain(&negative_scores)
+        .copied()
+        .filter(|score| score.is_finite())
+        .collect();
+    candidates.sort_by(|a, b| a.total_cmp(b));
+
+    let mut best = (f64::MIN, DetectionParams::default().threshold);
+    for threshold in &candidates {
+        let score = f1(*threshold);
+        if score > best.0 {
+            best = (score, *threshold);
+        }
+    }
+    let threshold = best.1;
+
+    Ok(
+        match candidates.iter().rev().find(|score| **score < threshold) {
+            Some(lower) => (threshold + lower) / 2.0,
+            None => threshold,
+        },
+    )
+}
+
+/// Whether the needle is found in a majority of `votes` searches, each over a slightly
+/// shifted set of scales
+///
+/// A single search only tries the scales on its grid, so a needle whose true scale
+/// falls between two of them can land either side of the threshold. Shifting the grid
+/// by up to half a step either way and taking the majority settles those borderline
+/// cases. The threshold itself isn't varied, as the majority of passes around it would
+/// just reproduce the unshifted decision. One vote is the same as a single search.
+pub fn vote_on_detection(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+    votes: usize,
+) -> Result<bool> {
+    vote_on_best_match(needle, haystack, params, votes).map(|(found, _)| found)
+}
+
+/// Same as [`vote_on_detection`], also returning the best match of any vote, ranked
+/// as in [`detect_needle_in_haystack`]
+pub fn vote_on_best_match(
+    needle: &Mat,
+    haystack: &Mat,
+    params: &DetectionParams,
+    votes: usize,
+) -> Result<(bool, Option<MatchResult>)> {
+    let votes = votes.max(1);
+    // A single scale has no step to shift between, as in scale_values
+    let scale_step = if params.scale_steps == 0 {
+        0.0
+    } else {
+        (params.max_scale - params.min_scale) / (params.scale_steps as f64)
+    };
+
+    let mut found = 0;
+    let mut best: Option<MatchResult> = None;
+    for vote in 0..votes {
+        let offset = if votes == 1 {
+            0.0
+        } else {
+            (vote as f64 / (votes - 1) as f64 - 0.5) * scale_step
+        };
+        let shifted = DetectionParams {
+            min_scale: params.min_scale + offset,
+            max_scale: params.max_scale + offset,
+            ..*params
+        };
+
+        let matches = detect_needle_in_haystack(needle, haystack, &shifted)?;
+        log::debug!(
+            "Vote {} (scales shifted by {:.4}): {} match(es)",
+            vote,
+            offset,
+            matches.len()
+        );
+        if let Some(first) = matches.first() {
+            found += 1;
+            if best.is_none_or(|best| {
+                scale_normalized_confidence(first) > scale_normalized_confidence(&best)
+            }) {
+                best = Some(*first);
             }
         }
     }
 
-    // Sort matches by confidence score in descending order
-    matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok((found * 2 > votes, best))
+}
+
+/// Centre point of a bounding box
+pub fn center((top_left, bottom_right): &BoundingBox) -> Point {
+    Point::new(
+        (top_left.x + bottom_right.x) / 2,
+        (top_left.y + bottom_right.y) / 2,
+    )
+}
+
+/// Clamps a bounding box to a frame of the given size, or returns `None` if nothing
+/// of it is left
+///
+/// Matches from scaled needles can extend past the haystack's edges, which would put
+/// their centre somewhere it can't be.
+pub fn clamp_to_frame((top_left, bottom_right): &BoundingBox, frame: Size) -> Option<BoundingBox> {
+    let clamp = |point: &Point| {
+        Point::new(
+            point.x.clamp(0, frame.width),
+            point.y.clamp(0, frame.height),
+        )
+    };
+    let (top_left, bottom_right) = (clamp(top_left), clamp(bottom_right));
 
-    // Take top num_players matches
-    matches.truncate(num_players);
+    (bottom_right.x > top_left.x && bottom_right.y > top_left.y).then_some((top_left, bottom_right))
+}
+
+/// The full-width band of a frame reaching `reach` marker heights above and below a
+/// completion marker, or `None` if it falls outside the frame
+pub fn marker_band(marker: &BoundingBox, reach: f64, frame: Size) -> Option<BoundingBox> {
+    let (top_left, bottom_right) = marker;
+    let margin = ((bottom_right.y - top_left.y) as f64 * reach).round() as i32;
+
+    clamp_to_frame(
+        &(
+            Point::new(0, top_left.y - margin),
+            Point::new(frame.width, bottom_right.y + margin),
+        ),
+        frame,
+    )
+}
+
+/// Grows a bounding box by `padding` times its width and height on every side, clamped
+/// to a frame of the given size
+pub fn pad_box(bounding_box: &BoundingBox, padding: f64, frame: Size) -> Option<BoundingBox> {
+    let (top_left, bottom_right) = bounding_box;
+    let dx = ((bottom_right.x - top_left.x) as f64 * padding).round() as i32;
+    let dy = ((bottom_right.y - top_left.y) as f64 * padding).round() as i32;
+
+    clamp_to_frame(
+        &(
+            Point::new(top_left.x - dx, top_left.y - dy),
+            Point::new(bottom_right.x + dx, bottom_right.y + dy),
+        ),
+        frame,
+    )
+}
+
+/// Whether a point lies within a completion marker's x-range
+pub fn is_under_marker(marker: &BoundingBox, point: Point) -> bool {
+    (marker.0.x..=marker.1.x).contains(&point.x)
+}
+
+/// Pairs completion markers with the avatars they belong to, returning the indices of
+/// the credited avatars
+///
+/// Markers are deduplicated first, keeping the earliest of any that overlap, so callers
+/// should pass them best first. An avatar belongs to a marker when its centre lies
+/// within the marker's x-range, and only an avatar under exactly one marker can be
+/// credited: one under several is ambiguous and logged instead. Each marker credits at
+/// most one avatar and each avatar is credited at most once, so two avatars crowding
+/// one marker can't both be counted: the one whose centre is nearest the marker's
+/// centre wins.
+pub fn credit_completions(markers: &[BoundingBox], avatars: &[Option<BoundingBox>]) -> Vec<usize> {
+    let mut deduped: Vec<BoundingBox> = Vec::new();
+    for marker in markers {
+        if !deduped.iter().any(|kept| overlaps(kept, marker)) {
+            deduped.push(*marker);
+        }
+    }
+
+    let eligible: Vec<Option<Point>> = avatars
+        .iter()
+        .enumerate()
+        .map(|(i, avatar)| {
+            let avatar_center = center(avatar.as_ref()?);
+            let containing = deduped
+                .iter()
+                .filter(|marker| is_under_marker(marker, avatar_center))
+                .count();
+            if containing > 1 {
+                log::warn!(
+                    "Avatar {i} at {avatar_center:?} lies under {containing} completion markers, not crediting it"
+                );
+                return None;
+            }
+            Some(avatar_center)
+        })
+        .collect();
+
+    let mut credited: Vec<usize> = Vec::new();
+
+    for marker in &deduped {
+        let marker_center = center(marker);
+        let nearest = eligible
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !credited.contains(i))
+            .filter_map(|(i, avatar_center)| avatar_center.map(|avatar_center| (i, avatar_center)))
+            .filter(|(_, avatar_center)| is_under_marker(marker, *avatar_center))
+            .min_by_key(|(_, avatar_center)| {
+                let dx = (avatar_center.x - marker_center.x) as i64;
+                let dy = (avatar_center.y - marker_center.y) as i64;
+                dx * dx + dy * dy
+            });
+
+        if let Some((i, _)) = nearest {
+            credited.push(i);
+        }
+    }
 
-    Ok(matches)
+    credited.sort_unstable();
+    credited
 }
@@ -0,0 +1,71 @@
+use thiserror::Error;
+
+/// Errors returned by the library's public functions
+#[derive(Debug, Error)]
+pub enum WordleError {
+    /// Fetching or saving an image failed, which is usually worth retrying
+    #[error("Failed to download {url}: {source}")]
+    Download {
+        url: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A download took longer than allowed, which is usually worth retrying
+    #[error("Timed out downloading {url}")]
+    Timeout { url: String },
+    /// An image file exists but couldn't be read as an image
+    #[error("Unable to decode {path}: {reason}")]
+    Decode { path: String, reason: String },
+    /// A template image needed for detection isn't there
+    #[error("Template {path} does not exist")]
+    TemplateMissing { path: String },
+    /// A screenshot or its detection outcome couldn't be written to the archive
+    #[error("Unable to archive to {path}: {source}")]
+    Archive {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A moderator-set avatar couldn't be stored
+    #[error("Unable to save avatar override to {path}: {source}")]
+    AvatarOverride {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// An expired download couldn't be listed or deleted
+    #[error("Unable to clean up {path}: {source}")]
+    Cleanup {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The config file couldn't be read or isn't valid TOML
+    #[error("Unable to load config {path}: {reason}")]
+    Config { path: String, reason: String },
+    /// A setting from the config file or environment has a value it can't take
+    #[error("Invalid value {value:?} for {key}")]
+    InvalidSetting { key: String, value: String },
+    /// A time couldn't be read as a duration
+    #[error("Invalid duration {value:?}: {reason}")]
+    InvalidDuration { value: String, reason: String },
+    /// Detection was asked for in a build without it, see the `cv` feature
+    #[cfg(not(feature = "cv"))]
+    #[error("Detection isn't available, the bot was built without the cv feature")]
+    DetectionUnavailable,
+    #[cfg(feature = "cv")]
+    #[error(transparent)]
+    OpenCv(#[from] opencv::Error),
+}
+
+impl WordleError {
+    /// Whether trying the same operation again might succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            WordleError::Download { .. } | WordleError::Timeout { .. }
+        )
+    }
+}
+
+pub type Result<T, E = WordleError> = std::result::Result<T, E>;
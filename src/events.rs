@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::history::{Completion, Outcome};
+
+/// Events kept for subscribers that fall behind before the oldest are dropped
+const EVENT_CAPACITY: usize = 256;
+
+/// A finished game, published for anything downstream of detection to react to
+///
+/// A game is published again if it's recorded again, e.g. when a shared result adds
+/// its guess count, so consumers should treat events for the same player and day as
+/// updates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionEvent {
+    pub user_id: u64,
+    pub username: String, // The name the Wordle app showed, lowercased
+    pub guild_id: u64,
+    pub active_time: Duration,
+    pub completed_at: DateTime<Utc>, // When completion was first detected
+    pub guess_count: Option<u32>,
+    pub outcome: Outcome,
+}
+
+impl CompletionEvent {
+    /// The event for a recorded completion
+    pub fn new(completion: &Completion, username: &str, completed_at: DateTime<Utc>) -> Self {
+        Self {
+            user_id: completion.user_id,
+            username: username.to_string(),
+            guild_id: completion.guild_id,
+            active_time: completion.active_time,
+            completed_at,
+            guess_count: completion.guess_count,
+            outcome: completion.outcome,
+        }
+    }
+}
+
+/// Broadcasts completions to every subscriber
+#[derive(Debug, Clone)]
+pub struct CompletionEvents {
+    sender: broadcast::Sender<CompletionEvent>,
+}
+
+impl Default for CompletionEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompletionEvents {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Receives every completion published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<CompletionEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Sends a completion to every current subscriber, returning how many there were
+    pub fn publish(&self, event: CompletionEvent) -> usize {
+        // Sending only fails when nobody is subscribed, which is fine
+        self.sender.send(event).unwrap_or(0)
+    }
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::{Australia::Sydney, Tz};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+use std::time::Instant;
+
+// Struct to store game state and metadata
+pub struct GameState {
+    pub active_since: Option<Instant>, // When the current attempt started, `None` between attempts
+    pub total_active_time: std::time::Duration, // Total time spent actively solving
+    pub completion_msg_id: Option<MessageId>, // ID of the completion message if one exists
+    pub completion_channel_id: Option<ChannelId>, // Channel or thread the completion message is in
+    pub created_at: DateTime<Utc>,     // When this game was first started (stored in UTC)
+    pub puzzle_date: NaiveDate,        // The puzzle's date in `timezone` when the game was started
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>, // When completion was first detected
+    pub timezone: Tz, // The guild's timezone, deciding which day the puzzle belongs to
+    pub guess_count: Option<u32>, // Guesses taken, when known from a text share
+    pub failed: bool, // Whether the player ran out of guesses
+    pub last_posted_at: Option<Instant>, // When the completion message was last sent or edited
+    pub hard_mode: bool, // Whether the end screen showed the hard-mode marker
+    pub combined: bool, // Whether the completion message lists other players too
+    pub player_name: String, // The lowercased name the Wordle app showed the player by
+}
+
+impl GameState {
+    /// Creates a new GameState instance for a Sydney-based puzzle day
+    pub fn new() -> Self {
+        Self::in_timezone(Sydney)
+    }
+
+    /// Creates a new GameState instance whose puzzle day follows `timezone`
+    pub fn in_timezone(timezone: Tz) -> Self {
+        Self::in_timezone_at(timezone, Utc::now())
+    }
+
+    /// Creates a GameState instance for a game started at `created_at`, belonging to
+    /// that day's puzzle in `timezone`
+    pub fn in_timezone_at(timezone: Tz, created_at: DateTime<Utc>) -> Self {
+        Self {
+            active_since: Some(Instant::now()),
+            total_active_time: std::time::Duration::ZERO,
+            completion_msg_id: None,
+            completion_channel_id: None,
+            created_at,
+            puzzle_date: created_at.with_timezone(&timezone).date_naive(),
+            completed: false,
+            completed_at: None,
+            timezone,
+            guess_count: None,
+            failed: false,
+            last_posted_at: None,
+            hard_mode: false,
+            combined: false,
+            player_name: String::new(),
+        }
+    }
+
+    /// Checks if this game is from the current day in the game's timezone
+    pub fn is_current(&self) -> bool {
+        self.is_current_at(Utc::now(), std::time::Duration::ZERO)
+    }
+
+    /// Checks if this game is from the current day as of `now`, allowing a game started
+    /// within `grace` before midnight to carry on until `grace` after it
+    ///
+    /// Players who start just before midnight are still solving that day's puzzle, so
+    /// their game shouldn't be reset under them. Finished games are never carried over.
+    pub fn is_current_at(&self, now: DateTime<Utc>, grace: std::time::Duration) -> bool {
+        let today = now.with_timezone(&self.timezone).date_naive();
+        if self.puzzle_date == today {
+            return true;
+        }
+
+        let Ok(grace) = chrono::Duration::from_std(grace) else {
+            return false;
+        };
+        let date_of = |at: DateTime<Utc>| at.with_timezone(&self.timezone).date_naive();
+        !self.completed
+            && date_of(self.created_at + grace) == today
+            && date_of(now - grace) == self.puzzle_date
+    }
+
+    /// Marks the game finished with its final time, returning when it was finished
+    ///
+    /// Any attempt in progress is ended, its time being part of `total_time`.
+    ///
+    /// Only the first detection sets the completion time, so re-detections that update
+    /// the message keep the original finishing order.
+    pub fn mark_completed(
+        &mut self,
+        total_time: std::time::Duration,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        self.active_since = None;
+        self.total_active_time = total_time;
+        self.completed = true;
+        *self.completed_at.get_or_insert(now)
+    }
+
+    /// Starts a new attempt at `now`, returning `false` if one is already in progress or
+    /// the game is finished
+    ///
+    /// Repeated start events for the same attempt are ignored, so they can't restart or
+    /// double-count its time.
+    pub fn resume(&mut self, now: Instant) -> bool {
+        if self.completed || self.active_since.is_some() {
+            return false;
+        }
+        self.active_since = Some(now);
+        true
+    }
+
+    /// Ends the attempt in progress at `now`, adding its time to the total
+    ///
+    /// Does nothing between attempts. An event older than the attempt's start adds no
+    /// time rather than underflowing.
+    pub fn pause(&mut self, now: Instant) {
+        if let Some(since) = self.active_since.take() {
+            self.total_active_time += now.saturating_duration_since(since);
+        }
+    }
+
+    /// Whether the completion message was sent or edited less than `cooldown` before `now`
+    ///
+    /// Screenshots posted in quick succession each re-detect the completion, and editing
+    /// the message for every one of them only adds noise.
+    pub fn in_post_cooldown(&self, now: Instant, cooldown: std::time::Duration) -> bool {
+        self.last_posted_at
+            .is_some_and(|posted| now.saturating_duration_since(posted) < cooldown)
+    }
+
+    /// Forgets the completion message, for when it was deleted, so a new one is sent
+    pub fn forget_completion_message(&mut self) {
+        self.completion_msg_id = None;
+        self.completion_channel_id = None;
+    }
+
+    /// Records the completion message posted, or updated, at `now` as `(message, channel)`,
+    /// forgetting the old one if posting failed
+    pub fn set_completion_message(&mut self, posted: Option<(MessageId, ChannelId)>, now: Instant) {
+        match posted {
+            Some((msg_id, channel_id)) => {
+                self.completion_msg_id = Some(msg_id);
+                self.completion_channel_id = Some(channel_id);
+                self.last_posted_at = Some(now);
+            }
+            None => self.forget_completion_message(),
+        }
+    }
+
+    /// Total active time, including the attempt in progress
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.elapsed_at(Instant::now())
+    }
+
+    /// Total active time as of `now`, including the attempt in progress
+    pub fn elapsed_at(&self, now: Instant) -> std::time::Duration {
+        self.total_active_time
+            + self
+                .active_since
+                .map_or(std::time::Duration::ZERO, |since| {
+                    now.saturating_duration_since(since)
+                })
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A tracked game: the guild it's played in, the Wordle app message tracking it and the
+/// player
+///
+/// Games are kept apart per guild so that a player in several servers the bot is in,
+/// with the same channel name in each, has separate games in each. They're keyed on
+/// the user rather than the name the Wordle app shows, so a rename doesn't split a
+/// game and members sharing a nickname don't share one.
+pub type GameKey = (GuildId, MessageId, UserId);
+
+/// Replaces a previous day's game with `fresh`, handing back the outgoing game if it
+/// was finished, so that its result can be saved before it's lost
+pub fn roll_over_day(game_state: &mut GameState, fresh: GameState) -> Option<GameState> {
+    let previous = std::mem::replace(game_state, fresh);
+    previous.completed.then_some(previous)
+}
+
+/// Whether a game is one of `user_id`'s in `guild_id`
+pub fn is_players_game(
+    (guild, _, player): &GameKey,
+    guild_id: Option<GuildId>,
+    user_id: UserId,
+) -> bool {
+    Some(*guild) == guild_id && *player == user_id
+}
+
+/// Whether a completion reaction by `reactor` on message `msg_id` finishes a game: the
+/// reactor's own games on that message, or every game on it when a moderator reacted
+pub fn completed_by_reaction(
+    (guild, msg, player): &GameKey,
+    guild_id: GuildId,
+    msg_id: MessageId,
+    reactor: UserId,
+    moderator: bool,
+) -> bool {
+    *guild == guild_id && *msg == msg_id && (moderator || *player == reactor)
+}
+
+// Struct to store active games
+pub struct WordlePuzzles;
+
+impl TypeMapKey for WordlePuzzles {
+    type Value = tokio::sync::Mutex<HashMap<GameKey, GameState>>;
+}
+
+/// When the bot started, for reporting its uptime
+pub struct StartedAt;
+
+impl TypeMapKey for StartedAt {
+    type Value = Instant;
+}
+
+/// Counts of tracked games, for /status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GameSummary {
+    pub active: usize,                            // Today's games still being played
+    pub completed: usize,                         // Today's finished games
+    pub stale: usize, // Games from an earlier day, replaced when their player starts again
+    pub last_completed_at: Option<DateTime<Utc>>, // Most recent completion of any game
+}
+
+/// Moves every game tracked in `guild_id` to `timezone`, returning how many there were
+///
+/// Games keep the puzzle they were started on; the new timezone decides when that
+/// puzzle is over.
+pub fn set_guild_timezone(
+    games: &mut HashMap<GameKey, GameState>,
+    guild_id: GuildId,
+    timezone: Tz,
+) -> usize {
+    let mut moved = 0;
+    for ((guild, _, _), game) in games.iter_mut() {
+        if *guild == guild_id {
+            game.timezone = timezone;
+            moved += 1;
+        }
+    }
+    moved
+}
+
+/// Summarises `games` as of `now`
+pub fn summarize_games<'a>(
+    games: impl IntoIterator<Item = &'a GameState>,
+    now: DateTime<Utc>,
+) -> GameSummary {
+    let mut summary = GameSummary::default();
+    for game_state in games {
+        if !game_state.is_current_at(now, std::time::Duration::ZERO) {
+            summary.stale += 1;
+        } else if game_state.completed {
+            summary.completed += 1;
+        } else {
+            summary.active += 1;
+        }
+        summary.last_completed_at = summary.last_completed_at.max(game_state.completed_at);
+    }
+    summary
+}
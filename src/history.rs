@@ -0,0 +1,809 @@
+use anyhow::{Result, bail};
+use chrono::{Days, NaiveDate};
+use chrono_tz::Tz;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::config::{GuildConfig, GuildOverrides};
+use crate::game::GameState;
+use crate::{TrackedChannel, format_duration};
+
+/// How a recorded game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Solved,
+    Failed,
+}
+
+impl Outcome {
+    /// The value stored in the `outcome` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Solved => "solved",
+            Outcome::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "solved" => Ok(Outcome::Solved),
+            "failed" => Ok(Outcome::Failed),
+            other => bail!("Unknown outcome '{other}'"),
+        }
+    }
+}
+
+/// A single finished game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub user_id: u64,
+    pub guild_id: u64,
+    pub date: NaiveDate, // The puzzle day the game belongs to
+    pub active_time: Duration,
+    pub guess_count: Option<u32>,
+    pub outcome: Outcome,
+    pub hard_mode: bool,
+    pub display_name: Option<String>, // What the player went by when it was recorded, if known
+}
+
+impl Completion {
+    /// The record of a player's finished game
+    pub fn from_game(
+        user_id: u64,
+        guild_id: u64,
+        game_state: &GameState,
+        display_name: Option<String>,
+    ) -> Self {
+        Self {
+            user_id,
+            guild_id,
+            date: game_state.puzzle_date,
+            active_time: game_state.total_active_time,
+            guess_count: game_state.guess_count,
+            outcome: if game_state.failed {
+                Outcome::Failed
+            } else {
+                Outcome::Solved
+            },
+            hard_mode: game_state.hard_mode,
+            display_name,
+        }
+    }
+}
+
+/// SQLite-backed store of finished games, surviving restarts
+#[derive(Clone)]
+pub struct History {
+    pool: SqlitePool,
+}
+
+impl History {
+    /// Opens (creating if needed) the database at `url` and runs any pending migrations
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+        Self::migrate(pool).await
+    }
+
+    /// Opens a fresh in-memory database, mainly for tests
+    ///
+    /// Every SQLite connection to `:memory:` is its own database, so the pool is
+    /// limited to a single connection.
+    pub async fn in_memory() -> Result<Self> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::migrate(pool).await
+    }
+
+    async fn migrate(pool: SqlitePool) -> Result<Self> {
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    /// Records a finished game, replacing any earlier record for the same user and day
+    pub async fn record_completion(&self, completion: &Completion) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO completions
+                (user_id, guild_id, date, active_time_ms, guess_count, outcome, hard_mode,
+                 display_name)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (user_id, guild_id, date) DO UPDATE SET
+                active_time_ms = excluded.active_time_ms,
+                guess_count = excluded.guess_count,
+                outcome = excluded.outcome,
+                hard_mode = excluded.hard_mode,
+                display_name = COALESCE(excluded.display_name, display_name)",
+        )
+        .bind(completion.user_id as i64)
+        .bind(completion.guild_id as i64)
+        .bind(completion.date)
+        .bind(completion.active_time.as_millis() as i64)
+        .bind(completion.guess_count)
+        .bind(completion.outcome.as_str())
+        .bind(completion.hard_mode)
+        .bind(&completion.display_name)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records every valid row of a history CSV (see [`parse_history_csv`]) as a game in
+    /// `guild_id`, replacing any already recorded for the same user and day
+    pub async fn import_csv(&self, guild_id: u64, csv: &str) -> Result<ImportReport> {
+        let (completions, rejected) = parse_history_csv(csv, guild_id);
+        for completion in &completions {
+            self.record_completion(completion).await?;
+        }
+
+        Ok(ImportReport {
+            imported: completions.len(),
+            rejected,
+        })
+    }
+
+    /// Imports the history CSV at `path` into `guild_id`, logging any rows skipped and
+    /// returning how many games were imported
+    pub async fn import_history(&self, guild_id: u64, path: &Path) -> Result<usize> {
+        let csv = tokio::fs::read_to_string(path).await?;
+        let report = self.import_csv(guild_id, &csv).await?;
+        for row in &report.rejected {
+            log::warn!(
+                "Skipped {} line {}: {}",
+                path.display(),
+                row.line,
+                row.reason
+            );
+        }
+
+        Ok(report.imported)
+    }
+
+    /// Whether a user's game for a puzzle day has been recorded
+    pub async fn has_completion(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        date: NaiveDate,
+    ) -> Result<bool> {
+        let found = sqlx::query_scalar::<_, i64>(
+            "SELECT 1 FROM completions WHERE guild_id = ? AND user_id = ? AND date = ?",
+        )
+        .bind(guild_id as i64)
+        .bind(user_id as i64)
+        .bind(date)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(found.is_some())
+    }
+
+    /// Average solve time of a user's solved games, if they have any
+    pub async fn user_average(&self, guild_id: u64, user_id: u64) -> Result<Option<Duration>> {
+        let average: Option<f64> = sqlx::query_scalar::<_, Option<f64>>(
+            "SELECT AVG(active_time_ms) FROM completions
+             WHERE guild_id = ? AND user_id = ? AND outcome = 'solved'",
+        )
+        .bind(guild_id as i64)
+        .bind(user_id as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(average.map(|ms| Duration::from_secs_f64(ms / 1000.0)))
+    }
+
+    /// Solve-time statistics of a user's solved games, including the requested percentiles
+    pub async fn user_time_percentiles(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        percentiles: &[f64],
+    ) -> Result<PercentileReport> {
+        let times = sqlx::query_scalar::<_, i64>(
+            "SELECT active_time_ms FROM completions
+             WHERE guild_id = ? AND user_id = ? AND outcome = 'solved'",
+        )
+        .bind(guild_id as i64)
+        .bind(user_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let times: Vec<Duration> = times
+            .into_iter()
+            .map(|ms| Duration::from_millis(ms as u64))
+            .collect();
+        Ok(percentile_report(&times, percentiles))
+    }
+
+    /// Solved games for a day as `(user_id, active_time)`, fastest first
+    pub async fn leaderboard_for_date(
+        &self,
+        guild_id: u64,
+        date: NaiveDate,
+    ) -> Result<Vec<(u64, Duration)>> {
+        let rows = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT user_id, active_time_ms FROM completions
+             WHERE guild_id = ? AND date = ? AND outcome = 'solved'
+             ORDER BY active_time_ms ASC",
+        )
+        .bind(guild_id as i64)
+        .bind(date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, ms)| (user_id as u64, Duration::from_millis(ms as u64)))
+            .collect())
+    }
+
+    /// Every user's best solve time, fastest first
+    ///
+    /// Users with the same best time are ordered by ID so pages stay stable.
+    pub async fn all_time_leaderboard(&self, guild_id: u64) -> Result<Vec<(u64, Duration)>> {
+        let rows = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT user_id, MIN(active_time_ms) AS best FROM completions
+             WHERE guild_id = ? AND outcome = 'solved'
+             GROUP BY user_id
+             ORDER BY best ASC, user_id ASC",
+        )
+        .bind(guild_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, ms)| (user_id as u64, Duration::from_millis(ms as u64)))
+            .collect())
+    }
+
+    /// Up to `limit` users who have played in a guild, most recently played first
+    pub async fn recent_players(&self, guild_id: u64, limit: usize) -> Result<Vec<u64>> {
+        let rows = sqlx::query_scalar::<_, i64>(
+            "SELECT user_id FROM completions
+             WHERE guild_id = ?
+             GROUP BY user_id
+             ORDER BY MAX(date) DESC, user_id ASC
+             LIMIT ?",
+        )
+        .bind(guild_id as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|user_id| user_id as u64).collect())
+    }
+
+    /// Every recorded game of a user, oldest first
+    pub async fn completions_for_user(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Vec<Completion>> {
+        let rows =
+            sqlx::query_as::<_, (NaiveDate, i64, Option<i64>, String, bool, Option<String>)>(
+                "SELECT date, active_time_ms, guess_count, outcome, hard_mode, display_name
+             FROM completions
+             WHERE guild_id = ? AND user_id = ?
+             ORDER BY date ASC",
+            )
+            .bind(guild_id as i64)
+            .bind(user_id as i64)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.into_iter()
+            .map(
+                |(date, ms, guess_count, outcome, hard_mode, display_name)| {
+                    Ok(Completion {
+                        user_id,
+                        guild_id,
+                        date,
+                        active_time: Duration::from_millis(ms as u64),
+                        guess_count: guess_count.map(|count| count as u32),
+                        outcome: outcome.parse()?,
+                        hard_mode,
+                        display_name,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// The name stored with a user's most recent game that has one
+    pub async fn latest_display_name(&self, guild_id: u64, user_id: u64) -> Result<Option<String>> {
+        let name = sqlx::query_scalar::<_, String>(
+            "SELECT display_name FROM completions
+             WHERE guild_id = ? AND user_id = ? AND display_name IS NOT NULL
+             ORDER BY date DESC
+             LIMIT 1",
+        )
+        .bind(guild_id as i64)
+        .bind(user_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(name)
+    }
+
+    /// A user's fastest solve, see [`personal_best`]
+    pub async fn personal_best(&self, guild_id: u64, user_id: u64) -> Result<Option<Completion>> {
+        let completions = self.completions_for_user(guild_id, user_id).await?;
+        Ok(personal_best(&completions).cloned())
+    }
+
+    /// Number of consecutive days, ending today or yesterday, the user solved the puzzle
+    ///
+    /// Yesterday counts as the end of a streak so it isn't broken before the user has
+    /// had a chance to play today.
+    pub async fn streak_for_user(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        today: NaiveDate,
+    ) -> Result<u32> {
+        let dates = sqlx::query_scalar::<_, NaiveDate>(
+            "SELECT date FROM completions
+             WHERE guild_id = ? AND user_id = ? AND outcome = 'solved'
+             ORDER BY date DESC",
+        )
+        .bind(guild_id as i64)
+        .bind(user_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut streak = 0;
+        let mut expected = today;
+        for date in dates {
+            if streak == 0 && date != today {
+                // Allow the streak to end yesterday
+                expected = today - Days::new(1);
+            }
+            if date != expected {
+                break;
+            }
+            streak += 1;
+            expected = expected - Days::new(1);
+        }
+
+        Ok(streak)
+    }
+
+    /// The thread completions are posted in for a channel's puzzle day, if one was created
+    pub async fn daily_thread(&self, channel_id: u64, date: NaiveDate) -> Result<Option<u64>> {
+        let thread_id = sqlx::query_scalar::<_, i64>(
+            "SELECT thread_id FROM daily_threads WHERE channel_id = ? AND date = ?",
+        )
+        .bind(channel_id as i64)
+        .bind(date)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(thread_id.map(|id| id as u64))
+    }
+
+    /// Remembers the thread for a channel's puzzle day, replacing any earlier one
+    pub async fn set_daily_thread(
+        &self,
+        channel_id: u64,
+        date: NaiveDate,
+        thread_id: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO daily_threads (channel_id, date, thread_id)
+             VALUES (?, ?, ?)
+             ON CONFLICT (channel_id, date) DO UPDATE SET thread_id = excluded.thread_id",
+        )
+        .bind(channel_id as i64)
+        .bind(date)
+        .bind(thread_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The settings a guild has changed, empty if it never ran `/config`
+    pub async fn guild_overrides(&self, guild_id: u64) -> Result<GuildOverrides> {
+        let row = sqlx::query_as::<
+            _,
+            (
+                Option<i64>,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                Option<i64>,
+            ),
+        >(
+            "SELECT channel_id, channel_name, timezone, embed_title, embed_footer, embed_colour
+             FROM guild_configs WHERE guild_id = ?",
+        )
+        .bind(guild_id as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((channel_id, channel_name, timezone, embed_title, embed_footer, embed_colour)) =
+            row
+        else {
+            return Ok(GuildOverrides::default());
+        };
+
+        let channel = match (channel_id, channel_name) {
+            (Some(id), _) => Some(TrackedChannel::Id(id as u64)),
+            (None, Some(name)) => Some(TrackedChannel::Name(name)),
+            (None, None) => None,
+        };
+        let timezone = match timezone {
+            Some(name) => match name.parse::<Tz>() {
+                Ok(timezone) => Some(timezone),
+                Err(_) => bail!("Unknown timezone '{name}' configured for guild {guild_id}"),
+            },
+            None => None,
+        };
+        let embed_colour = embed_colour.map(|rgb| ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+
+        Ok(GuildOverrides {
+            channel,
+            timezone,
+            embed_title,
+            embed_footer,
+            embed_colour,
+        })
+    }
+
+    /// Replaces a guild's settings
+    pub async fn set_guild_overrides(
+        &self,
+        guild_id: u64,
+        overrides: &GuildOverrides,
+    ) -> Result<()> {
+        let (channel_id, channel_name) = match &overrides.channel {
+            Some(TrackedChannel::Id(id)) => (Some(*id as i64), None),
+            Some(TrackedChannel::Name(name)) => (None, Some(name.as_str())),
+            None => (None, None),
+        };
+        let embed_colour = overrides
+            .embed_colour
+            .map(|(r, g, b)| ((r as i64) << 16) | ((g as i64) << 8) | b as i64);
+
+        sqlx::query(
+            "INSERT INTO guild_configs
+                (guild_id, channel_id, channel_name, timezone, embed_title, embed_footer, embed_colour)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT (guild_id) DO UPDATE SET
+                channel_id = excluded.channel_id,
+                channel_name = excluded.channel_name,
+                timezone = excluded.timezone,
+                embed_title = excluded.embed_title,
+                embed_footer = excluded.embed_footer,
+                embed_colour = excluded.embed_colour",
+        )
+        .bind(guild_id as i64)
+        .bind(channel_id)
+        .bind(channel_name)
+        .bind(overrides.timezone.map(|timezone| timezone.name()))
+        .bind(overrides.embed_title.as_deref())
+        .bind(overrides.embed_footer.as_deref())
+        .bind(embed_colour)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A guild's effective config, falling back to `defaults` for anything it hasn't set
+    pub async fn guild_config(&self, guild_id: u64, defaults: &GuildConfig) -> Result<GuildConfig> {
+        Ok(self.guild_overrides(guild_id).await?.apply(defaults))
+    }
+
+    /// Stops a user from being tracked in a guild
+    pub async fn opt_out(&self, guild_id: u64, user_id: u64) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO opt_outs (guild_id, user_id) VALUES (?, ?)")
+            .bind(guild_id as i64)
+            .bind(user_id as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lets an opted-out user be tracked in a guild again
+    pub async fn opt_in(&self, guild_id: u64, user_id: u64) -> Result<()> {
+        sqlx::query("DELETE FROM opt_outs WHERE guild_id = ? AND user_id = ?")
+            .bind(guild_id as i64)
+            .bind(user_id as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Every user who opted out of tracking in a guild
+    pub async fn opted_out_users(&self, guild_id: u64) -> Result<HashSet<u64>> {
+        let user_ids =
+            sqlx::query_scalar::<_, i64>("SELECT user_id FROM opt_outs WHERE guild_id = ?")
+                .bind(guild_id as i64)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(user_ids.into_iter().map(|id| id as u64).collect())
+    }
+
+    /// Has a user sent a reminder on days they haven't finished the Wordle in a guild
+    pub async fn remind(&self, guild_id: u64, user_id: u64) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO reminders (guild_id, user_id) VALUES (?, ?)")
+            .bind(guild_id as i64)
+            .bind(user_id as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Stops reminding a user in a guild
+    pub async fn stop_reminding(&self, guild_id: u64, user_id: u64) -> Result<()> {
+        sqlx::query("DELETE FROM reminders WHERE guild_id = ? AND user_id = ?")
+            .bind(guild_id as i64)
+            .bind(user_id as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Every user who asked for reminders, by guild
+    pub async fn reminded_users(&self) -> Result<HashMap<u64, HashSet<u64>>> {
+        let rows = sqlx::query_as::<_, (i64, i64)>("SELECT guild_id, user_id FROM reminders")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut users: HashMap<u64, HashSet<u64>> = HashMap::new();
+        for (guild_id, user_id) in rows {
+            users
+                .entry(guild_id as u64)
+                .or_default()
+                .insert(user_id as u64);
+        }
+        Ok(users)
+    }
+}
+
+/// Fewest solves for percentiles to be worth showing
+pub const MIN_PERCENTILE_SAMPLES: usize = 5;
+
+/// Summary of a set of solve times
+#[derive(Debug, Clone, PartialEq)]
+pub struct PercentileReport {
+    pub samples: usize,
+    pub fastest: Option<Duration>,
+    pub mean: Option<Duration>,
+    pub percentiles: Vec<(f64, Duration)>, // Each requested percentile and its time, empty without samples
+}
+
+/// Summarises `times`, interpolating each percentile between the two closest ranks
+///
+/// Percentiles are clamped to 0–100, so a single sample is every percentile of itself.
+/// Times are rounded to the millisecond, as they are stored.
+pub fn percentile_report(times: &[Duration], percentiles: &[f64]) -> PercentileReport {
+    let mut sorted = times.to_vec();
+    sorted.sort();
+
+    let Some(&fastest) = sorted.first() else {
+        return PercentileReport {
+            samples: 0,
+            fastest: None,
+            mean: None,
+            percentiles: Vec::new(),
+        };
+    };
+
+    let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+    let percentiles = percentiles
+        .iter()
+        .map(|&percentile| {
+            let rank = percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+            let (below, above) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+            let (below, above) = (below.as_millis() as f64, above.as_millis() as f64);
+            let ms = below + (above - below) * rank.fract();
+            (percentile, Duration::from_millis(ms.round() as u64))
+        })
+        .collect();
+
+    PercentileReport {
+        samples: sorted.len(),
+        fastest: Some(fastest),
+        mean: Some(mean),
+        percentiles,
+    }
+}
+
+/// The fastest solved game, the earliest of them if it was matched later
+pub fn personal_best(completions: &[Completion]) -> Option<&Completion> {
+    completions
+        .iter()
+        .filter(|completion| completion.outcome == Outcome::Solved)
+        .min_by_key(|completion| (completion.active_time, completion.date))
+}
+
+/// Leaderboard entries shown per page
+pub const LEADERBOARD_PAGE_SIZE: usize = 10;
+
+/// One page of a leaderboard, ranked from the whole list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderboardPage {
+    pub page: usize,  // Starting from 0, clamped to the last page
+    pub pages: usize, // At least 1, even with no entries
+    pub text: String,
+}
+
+/// Renders page `page` of `entries`, which must already be ranked, mentioning each user
+pub fn leaderboard_page(entries: &[(u64, Duration)], page: usize) -> LeaderboardPage {
+    let pages = entries.len().div_ceil(LEADERBOARD_PAGE_SIZE).max(1);
+    let page = page.min(pages - 1);
+
+    let text = if entries.is_empty() {
+        "No solves recorded yet.".to_string()
+    } else {
+        entries
+            .iter()
+            .enumerate()
+            .skip(page * LEADERBOARD_PAGE_SIZE)
+            .take(LEADERBOARD_PAGE_SIZE)
+            .map(|(rank, (user_id, time))| {
+                format!(
+                    "**{}.** <@{}> {}",
+                    rank + 1,
+                    user_id,
+                    format_duration(*time)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    LeaderboardPage { page, pages, text }
+}
+
+/// The name to show for a player: the one they go by in the server now when they're
+/// still in it, otherwise the one stored with their games, otherwise their username
+///
+/// Games are keyed by user ID, so a rename never splits a player's stats, but the name
+/// stored at the time goes stale and is only a fallback for players who have left.
+pub fn resolve_display_name<'a>(
+    current: Option<&'a str>,
+    stored: Option<&'a str>,
+    username: &'a str,
+) -> &'a str {
+    current.or(stored).unwrap_or(username)
+}
+
+/// Renders a user's games as CSV, one row per game after a header row
+///
+/// Each row keeps the display name the player had when the game was recorded, left
+/// empty for games recorded without one.
+pub fn completions_to_csv(username: &str, completions: &[Completion]) -> String {
+    let username = csv_field(username);
+    let mut csv = String::from("username,date,active_time_ms,guess_count,outcome,display_name\n");
+    for completion in completions {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            username,
+            completion.date,
+            completion.active_time.as_millis(),
+            completion
+                .guess_count
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            completion.outcome.as_str(),
+            completion
+                .display_name
+                .as_deref()
+                .map(csv_field)
+                .unwrap_or_default(),
+        ));
+    }
+    csv
+}
+
+/// The columns of an imported history, in order
+pub const IMPORT_HEADER: &str = "user_id,date,active_time_ms,guess_count,outcome";
+
+/// A line of an imported history that couldn't be read, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedRow {
+    pub line: usize, // 1-based, counting the header
+    pub reason: String,
+}
+
+/// What came of importing a history CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// Reads a history CSV, as kept in a spreadsheet or exported from another bot, into
+/// games in `guild_id`, alongside the lines that couldn't be read
+///
+/// Each row is [`IMPORT_HEADER`]'s columns, with the date as YYYY-MM-DD and the guess
+/// count left empty when unknown. The header row itself and blank lines are optional.
+/// Imported games aren't marked as hard mode, since the columns don't say.
+pub fn parse_history_csv(csv: &str, guild_id: u64) -> (Vec<Completion>, Vec<RejectedRow>) {
+    let mut completions = Vec::new();
+    let mut rejected = Vec::new();
+
+    for (i, row) in csv.lines().enumerate() {
+        let row = row.trim();
+        if row.is_empty() || (i == 0 && row.eq_ignore_ascii_case(IMPORT_HEADER)) {
+            continue;
+        }
+        match parse_history_row(row, guild_id) {
+            Ok(completion) => completions.push(completion),
+            Err(reason) => rejected.push(RejectedRow {
+                line: i + 1,
+                reason,
+            }),
+        }
+    }
+
+    (completions, rejected)
+}
+
+/// One imported game, or why its row couldn't be read
+fn parse_history_row(row: &str, guild_id: u64) -> Result<Completion, String> {
+    let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+    let [user_id, date, active_time_ms, guess_count, outcome] = fields[..] else {
+        return Err(format!("expected 5 columns, found {}", fields.len()));
+    };
+
+    let user_id = user_id
+        .parse::<u64>()
+        .map_err(|_| format!("'{user_id}' isn't a user ID"))?;
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("'{date}' isn't a date like 2024-06-01"))?;
+    let active_time = active_time_ms
+        .parse::<u64>()
+        .map(Duration::from_millis)
+        .map_err(|_| format!("'{active_time_ms}' isn't a time in milliseconds"))?;
+    let guess_count = match guess_count {
+        "" => None,
+        count => match count.parse::<u32>() {
+            Ok(count @ 1..=6) => Some(count),
+            _ => return Err(format!("'{count}' isn't a guess count from 1 to 6")),
+        },
+    };
+    let outcome = outcome
+        .to_ascii_lowercase()
+        .parse::<Outcome>()
+        .map_err(|_| format!("'{outcome}' isn't solved or failed"))?;
+
+    Ok(Completion {
+        user_id,
+        guild_id,
+        date,
+        active_time,
+        guess_count,
+        outcome,
+        hard_mode: false,
+        display_name: None,
+    })
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -1,17 +1,120 @@
+pub mod announcements;
+pub mod archive;
+pub mod config;
+#[cfg(feature = "cv")]
 pub mod detection;
+pub mod error;
+pub mod events;
+pub mod game;
+pub mod history;
+pub mod locale;
+pub mod metrics;
+pub mod overrides;
+pub mod reminders;
+pub mod seen;
+pub mod server;
+pub mod share;
+#[cfg(not(feature = "cv"))]
+mod stub;
+#[cfg(feature = "cv")]
+mod vision;
 
-use anyhow::Result;
-use log::info;
-use opencv::imgcodecs;
-use tokio::{fs, io::AsyncWriteExt};
+// Detection is OpenCV's with the default `cv` feature, and stubbed out without it
+#[cfg(not(feature = "cv"))]
+pub use stub::*;
+#[cfg(feature = "cv")]
+pub use vision::*;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use config::{GuildConfig, WebhookConfig};
+use error::{Result, WordleError};
+use locale::{Locale, Strings, fill};
+use log::{debug, info, warn};
+use rand::Rng;
+use serenity::all::{
+    Attachment, Colour, CreateEmbed, CreateEmbedFooter, ExecuteWebhook, ReactionType,
+};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::{fs, io::AsyncWriteExt, sync::Semaphore};
 
 pub const PLAYING_TRIGGERS: [&str; 2] = ["is playing", "are playing"];
 pub const FINISHED_TRIGGERS: [&str; 2] = ["was playing", "were playing"];
 
-const DATA_DIR: &'static str = "./data";
+/// Downloads allowed to run at once unless configured otherwise
+pub const DEFAULT_MAX_DOWNLOADS: usize = 4;
+
+/// Longest a download may take, connecting included, unless configured otherwise
+pub const DEFAULT_DOWNLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Detection passes that must agree on a player unless configured otherwise
+pub const DEFAULT_CONFIDENCE_VOTES: usize = 1;
+
+/// The completion marker looked for unless others are configured, e.g. for each theme
+pub const SOLVED_TEMPLATE: &str = "./data/solved.png";
+/// Marks a hard-mode result on the end screen, hard mode goes undetected without it
+pub const HARD_MODE_TEMPLATE: &str = "./data/hardmode.png";
+
+/// Confidence completion markers must be matched with, unless set for their template
+///
+/// Markers are small, so the softening of a scaled or recompressed screenshot costs
+/// them more confidence than avatars: a marker typically scores around 0.9.
+pub const DEFAULT_MARKER_THRESHOLD: f64 = 0.85;
+
+/// How often downloads are checked against the retention period
+pub const CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// The channel games are tracked in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackedChannel {
+    Id(u64),      // Matched exactly, and survives renames
+    Name(String), // Matched loosely, see `channel_name_matches`
+}
+
+impl TrackedChannel {
+    /// Checks a channel against this one; `channel_name` is only needed for `Name`
+    pub fn matches(&self, channel_id: u64, channel_name: Option<&str>) -> bool {
+        match self {
+            TrackedChannel::Id(id) => *id == channel_id,
+            TrackedChannel::Name(name) => {
+                channel_name.is_some_and(|channel_name| channel_name_matches(name, channel_name))
+            }
+        }
+    }
+}
+
+/// Reduce a channel name to lowercase alphanumerics, dropping emoji and separators
+pub fn normalize_channel_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether a channel's name contains the configured name, ignoring case, emoji and
+/// punctuation, so e.g. `🧩-daily-puzzles` still matches `daily-puzzles`
+pub fn channel_name_matches(configured: &str, channel_name: &str) -> bool {
+    let configured = normalize_channel_name(configured);
+    !configured.is_empty() && normalize_channel_name(channel_name).contains(&configured)
+}
+
+/// Whether a channel is the configured debug channel, where any image is answered with
+/// what detection made of it rather than tracked, see `Config::debug_channel_name`
+///
+/// Unlike the tracked channel the whole name must match, ignoring case, emoji and
+/// punctuation, so the debug channel can't be mistaken for a similarly named one.
+pub fn is_debug_channel(debug_channel_name: Option<&str>, channel_name: &str) -> bool {
+    debug_channel_name.is_some_and(|configured| {
+        let configured = normalize_channel_name(configured);
+        !configured.is_empty() && normalize_channel_name(channel_name) == configured
+    })
+}
 
 /// Parse usernames from the server by seeing if their profile picture is in the picture.
-pub fn parse_usernames(content: &String) -> Vec<String> {
+pub fn parse_usernames(content: &str) -> Vec<String> {
     let content = content.to_lowercase();
 
     // Try each trigger to find which one matches
@@ -45,13 +148,12 @@ pub fn parse_usernames(content: &String) -> Vec<String> {
     };
 
     // Check for edge cases like "2 others", "3 others", etc.
-    if let Some(last_username) = usernames.last() {
-        if last_username.chars().next().unwrap_or(' ').is_numeric()
-            && last_username.ends_with(" others")
-        {
-            // Edge case with pattern like "2 others", "3 others", etc.
-            usernames.clear();
-        }
+    if let Some(last_username) = usernames.last()
+        && last_username.chars().next().unwrap_or(' ').is_numeric()
+        && last_username.ends_with(" others")
+    {
+        // Edge case with pattern like "2 others", "3 others", etc.
+        usernames.clear();
     }
 
     info!("Found {} usernames: {:?}", usernames.len(), usernames);
@@ -59,58 +161,683 @@ pub fn parse_usernames(content: &String) -> Vec<String> {
     usernames
 }
 
-async fn download_image(url: &String) -> Result<String> {
-    let file_path = format!("{DATA_DIR}/{}", url.split("/").last().unwrap());
+/// Caps how many image downloads run at once
+///
+/// Clones share the same permits, so one limiter handed to every caller bounds the
+/// downloads of the whole bot, keeping file descriptors and CDN rate limits in check.
+///
+/// Failed downloads are retried following the limiter's [`RetryPolicy`], without holding
+/// a slot while waiting.
+#[derive(Clone)]
+pub struct DownloadLimiter {
+    permits: Arc<Semaphore>,
+    retry: RetryPolicy,
+    client: reqwest::Client, // Shared so downloads reuse its connection pool
+    #[cfg_attr(not(feature = "cv"), allow(dead_code))] // Only real detection loads avatars
+    avatar_size: Option<u32>, // Longest side avatars are resized to, as downloaded if `None`
+    dir: PathBuf,            // Where downloaded images are saved
+}
+
+impl DownloadLimiter {
+    /// Allows up to `max_concurrent` downloads at once (at least one)
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            retry: RetryPolicy::default(),
+            client: http_client(None, DEFAULT_DOWNLOAD_TIMEOUT)
+                .expect("A client without a proxy always builds"),
+            avatar_size: None,
+            dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Retries failed downloads following `retry` instead of the default policy
+    pub fn with_retry(self, retry: RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Downloads with `client` instead of a default one, e.g. one from [`http_client`]
+    pub fn with_client(self, client: reqwest::Client) -> Self {
+        Self { client, ..self }
+    }
+
+    /// Resizes every downloaded avatar to `avatar_size`, see [`normalize_avatar`]
+    pub fn with_avatar_size(self, avatar_size: Option<u32>) -> Self {
+        Self {
+            avatar_size,
+            ..self
+        }
+    }
+
+    /// Saves downloaded images in `dir` instead of the system's temporary directory
+    pub fn with_dir(self, dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            ..self
+        }
+    }
+
+    /// The client downloads are made with, shared by every clone of this limiter
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Runs `task` once a download slot is free, holding the slot until it finishes
+    pub async fn run<F: Future>(&self, task: F) -> F::Output {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("Download semaphore is never closed");
+        task.await
+    }
+}
+
+impl Default for DownloadLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DOWNLOADS)
+    }
+}
+
+/// Builds the client images are downloaded with, sending every request through
+/// `proxy_url` if one is given and giving up on any taking longer than `timeout`
+///
+/// Without a proxy, reqwest still picks up the usual proxy environment variables.
+pub fn http_client(
+    proxy_url: Option<&str>,
+    timeout: std::time::Duration,
+) -> Result<reqwest::Client> {
+    let invalid_proxy = |url: &str| WordleError::InvalidSetting {
+        key: "proxy_url".to_string(),
+        value: url.to_string(),
+    };
+
+    let mut builder = reqwest::Client::builder()
+        .connect_timeout(timeout)
+        .timeout(timeout);
+    if let Some(url) = proxy_url {
+        builder = builder.proxy(reqwest::Proxy::all(url).map_err(|_| invalid_proxy(url))?);
+    }
+    builder
+        .build()
+        .map_err(|_| invalid_proxy(proxy_url.unwrap_or_default()))
+}
+
+/// How often, and how patiently, to retry operations that failed transiently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,                 // Retries after the first attempt
+    pub max_backoff: std::time::Duration, // Longest wait between attempts
+}
+
+impl RetryPolicy {
+    /// How long to wait before retry `attempt` (starting at 0)
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        backoff_delay(attempt).min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            max_backoff: std::time::Duration::from_secs(10),
+        }
+    }
+}
+
+/// Runs `operation` until it succeeds, fails permanently or runs out of retries
+///
+/// Only errors that are [transient](WordleError::is_transient) are retried. Missing
+/// templates or undecodable images would fail the same way again, so they are
+/// returned straight away.
+pub async fn retry_transient<T, F, Fut>(policy: &RetryPolicy, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(why) if why.is_transient() && attempt < policy.max_retries => {
+                let delay = policy.delay(attempt);
+                warn!("{}, retrying in {:?}", why, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "cv"), allow(dead_code))] // Stubbed detection downloads nothing
+async fn download_image(downloads: &DownloadLimiter, url: &str) -> Result<String> {
+    retry_transient(&downloads.retry, || async {
+        let result = download_image_once(downloads, url).await;
+        if result.is_err() {
+            metrics::metrics().download_failures.inc();
+        }
+        result
+    })
+    .await
+}
+
+async fn download_image_once(downloads: &DownloadLimiter, url: &str) -> Result<String> {
+    let file_path = downloads.dir.join(url.split("/").last().unwrap());
+    let download_error = |source: Box<dyn std::error::Error + Send + Sync>| WordleError::Download {
+        url: url.to_string(),
+        source,
+    };
     info!("Downloading image from {url}");
     // Send the HTTP request
-    let response = reqwest::get(url).await?.bytes().await?;
-
-    // Create and open the output file
-    let mut file = fs::File::create(&file_path).await?;
+    let response = downloads
+        .run(async { downloads.client().get(url).send().await?.bytes().await })
+        .await
+        .map_err(|why| {
+            if why.is_timeout() {
+                WordleError::Timeout {
+                    url: url.to_string(),
+                }
+            } else {
+                download_error(why.into())
+            }
+        })?;
 
-    // Write the image bytes to the file
-    file.write_all(&response).await?;
+    // Create the output file and write the image bytes to it
+    async {
+        fs::create_dir_all(&downloads.dir).await?;
+        let mut file = fs::File::create(&file_path).await?;
+        file.write_all(&response).await
+    }
+    .await
+    .map_err(|why| download_error(why.into()))?;
 
+    let file_path = file_path.display().to_string();
     info!("Succesfully downloaded image and saved to {file_path}");
 
     Ok(file_path)
 }
 
-pub struct Player {
-    uid: usize,
-    profile_url: String,
+/// Whether a file in the download directory is a downloaded image last modified more
+/// than `retention` before `now`
+///
+/// Anything named like one of the `protected` templates is kept whatever its age, as
+/// are directories and files that aren't images, such as the database.
+pub fn is_expired_download(
+    path: &Path,
+    is_file: bool,
+    modified: SystemTime,
+    now: SystemTime,
+    retention: std::time::Duration,
+    protected: &[&str],
+) -> bool {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    // Downloads are named after the URL, query string and all, e.g. image.png?ex=123
+    let lowercase = file_name.to_lowercase();
+    let is_image = [".png", ".jpg", ".jpeg", ".gif", ".webp"]
+        .iter()
+        .any(|extension| lowercase.contains(extension));
+    let is_protected = protected
+        .iter()
+        .any(|template| Path::new(template).file_name() == Some(file_name.as_ref()));
+
+    is_file
+        && is_image
+        && !is_protected
+        && now
+            .duration_since(modified)
+            .is_ok_and(|age| age > retention)
 }
 
-impl Player {
-    pub fn new(uid: usize, profile_url: String) -> Player {
-        Player { uid, profile_url }
+/// Deletes images downloaded into `dir` older than `retention`, never touching the
+/// `protected` templates, returning how many were deleted
+pub async fn clean_downloads(
+    dir: &Path,
+    retention: std::time::Duration,
+    protected: &[&str],
+) -> Result<usize> {
+    let cleanup_error = |path: &Path, source: std::io::Error| WordleError::Cleanup {
+        path: path.display().to_string(),
+        source,
+    };
+    let now = SystemTime::now();
+
+    let mut deleted = 0;
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|why| cleanup_error(dir, why))?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|why| cleanup_error(dir, why))?
+    {
+        let path = entry.path();
+        let metadata = entry
+            .metadata()
+            .await
+            .map_err(|why| cleanup_error(&path, why))?;
+        let modified = metadata
+            .modified()
+            .map_err(|why| cleanup_error(&path, why))?;
+        if is_expired_download(
+            &path,
+            metadata.is_file(),
+            modified,
+            now,
+            retention,
+            protected,
+        ) {
+            fs::remove_file(&path)
+                .await
+                .map_err(|why| cleanup_error(&path, why))?;
+            debug!("Deleted expired download {}", path.display());
+            deleted += 1;
+        }
     }
+
+    Ok(deleted)
 }
 
-pub async fn find_players_in_image(
-    players: Vec<Player>,
-    haystack_url: String,
-) -> Result<Vec<Player>> {
-    let haystack_fp = download_image(&haystack_url).await?;
-    let haystack = imgcodecs::imread(&haystack_fp, imgcodecs::IMREAD_COLOR_RGB)?;
-    let mut found_players = Vec::new();
+/// Whether an avatar URL points at an animated avatar (an `a_` hash or a `.gif`)
+pub fn is_animated_avatar_url(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name.starts_with("a_") || file_name.to_lowercase().ends_with(".gif")
+}
+
+/// File extensions treated as images when Discord doesn't give an attachment's type
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+
+/// Whether an attachment is an image, by its content type, or its file extension when
+/// it has none
+pub fn is_image_attachment(attachment: &Attachment) -> bool {
+    match attachment.content_type.as_deref() {
+        Some(content_type) => content_type.starts_with("image/"),
+        None => attachment
+            .filename
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| {
+                IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            }),
+    }
+}
 
-    for player in players {
-        let image_path = download_image(&player.profile_url).await?;
-        let needle = imgcodecs::imread(&image_path, imgcodecs::IMREAD_COLOR_RGB)?;
-        let found =
-            detection::detect_needle_in_haystack(&needle, &haystack, 1, 0.6, 1.4, 100, 0.95)?;
+/// A message's image attachments, in the order they were posted, any of which could be
+/// the game's screenshot
+pub fn image_attachments<'a>(
+    attachments: impl IntoIterator<Item = &'a Attachment>,
+) -> Vec<&'a Attachment> {
+    attachments
+        .into_iter()
+        .filter(|attachment| is_image_attachment(attachment))
+        .collect()
+}
 
-        if found.len() == 1 {
-            found_players.push(player);
+/// The details of a detection: every completion marker found, then how each avatar
+/// searched for fared, given the `names` of the avatars' players in the same order
+pub fn detection_summary(trace: &VerificationTrace, names: &[&str]) -> String {
+    let mut summary = format!("Found {} completion marker(s).", trace.markers.len());
+    for (i, (_, confidence)) in trace.markers.iter().enumerate() {
+        summary.push_str(&format!("\nMarker {i}: confidence {confidence:.3}"));
+    }
+    for (name, avatar) in names.iter().zip(&trace.avatars) {
+        match avatar.found {
+            Some((_, confidence)) => summary.push_str(&format!(
+                "\n{name}'s avatar found with confidence {confidence:.3}, {}.",
+                if avatar.credited {
+                    "credited with a completion"
+                } else {
+                    "not under any marker"
+                }
+            )),
+            None => summary.push_str(&format!("\n{name}'s avatar wasn't found.")),
         }
     }
+    summary
+}
+
+/// Activity names Wordle is tracked under unless configured otherwise
+pub const DEFAULT_ACTIVITY_NAMES: [&str; 1] = ["Wordle"];
 
-    Ok(found_players)
+/// Whether a Discord activity is one of the configured Wordle `names`, ignoring case and
+/// surrounding spaces
+pub fn is_wordle_activity(names: &[String], activity_name: &str) -> bool {
+    let activity_name = activity_name.trim().to_lowercase();
+    names
+        .iter()
+        .any(|name| name.trim().to_lowercase() == activity_name)
+}
+
+/// Whether a reaction is the configured completion emoji, given as the emoji itself or
+/// a custom emoji's name
+pub fn is_completion_reaction(configured: &str, emoji: &ReactionType) -> bool {
+    // Some clients add a variation selector after the emoji
+    let strip = |emoji: &str| emoji.replace('\u{FE0F}', "");
+    match emoji {
+        ReactionType::Unicode(emoji) => strip(emoji) == strip(configured),
+        ReactionType::Custom { name, .. } => name.as_deref() == Some(configured.trim_matches(':')),
+        _ => false,
+    }
+}
+
+/// Whether a player may be tracked, given the users who opted out
+///
+/// Players that can't be matched to a member are tracked, as there is nobody to check.
+pub fn is_tracked(user_id: Option<u64>, opted_out: &HashSet<u64>) -> bool {
+    user_id.is_none_or(|user_id| !opted_out.contains(&user_id))
 }
 
-/// Format a duration into a human-readable string
+/// Whether a message's author should be ignored outright: the bot itself, or any other
+/// bot or app that isn't one of `allowed_apps`
+///
+/// Checked before anything is downloaded, so the bot's own completion messages, or a
+/// screenshot reposted by another integration, can never feed back into tracking.
+pub fn is_ignored_author(
+    author_id: u64,
+    author_is_bot: bool,
+    own_id: u64,
+    allowed_apps: &[u64],
+) -> bool {
+    author_id == own_id || (author_is_bot && !allowed_apps.contains(&author_id))
+}
+
+/// What catch-up needs to know about a message from the tracked channel's history
+#[derive(Debug, Clone, PartialEq)]
+pub struct PastPost<'a> {
+    pub author_id: u64,
+    pub content: &'a str,
+    pub has_image: bool,          // Whether a screenshot is attached
+    pub posted_at: DateTime<Utc>, // When the message was first sent
+}
+
+/// Whether a message from before the bot started is a completion it may have missed:
+/// a Wordle-app screenshot of a finished game, posted within `lookback` of `now` and on
+/// today's puzzle day in `timezone`
+pub fn is_missed_completion(
+    post: &PastPost,
+    app_id: u64,
+    now: DateTime<Utc>,
+    lookback: std::time::Duration,
+    timezone: Tz,
+) -> bool {
+    let content = post.content.to_lowercase();
+    let is_finished = FINISHED_TRIGGERS
+        .iter()
+        .any(|&trigger| content.contains(trigger));
+    let is_recent = chrono::Duration::from_std(lookback)
+        .is_ok_and(|lookback| post.posted_at <= now && now - post.posted_at <= lookback);
+    let date_of = |at: DateTime<Utc>| at.with_timezone(&timezone).date_naive();
+
+    post.author_id == app_id
+        && post.has_image
+        && is_finished
+        && is_recent
+        && date_of(post.posted_at) == date_of(now)
+}
+
+/// Discord's JSON error codes for a channel or message that doesn't exist
+const UNKNOWN_CHANNEL: isize = 10003;
+const UNKNOWN_MESSAGE: isize = 10008;
+
+/// Whether a failed Discord request's status and JSON error code say its target is gone
+pub fn is_unknown_target(status: u16, code: isize) -> bool {
+    status == 404 || matches!(code, UNKNOWN_CHANNEL | UNKNOWN_MESSAGE)
+}
+
+/// Compute how long to wait before retry `attempt` (starting at 0)
+///
+/// The base delay doubles each attempt and is multiplied by a random factor in
+/// 0.5–1.5 so that concurrent retries don't hit the API in lockstep.
+pub fn backoff_delay(attempt: u32) -> std::time::Duration {
+    // Capped so that large attempt counts can't overflow
+    let base = std::time::Duration::from_secs(1 << attempt.min(30));
+    base.mul_f64(rand::thread_rng().gen_range(0.5..1.5))
+}
+
+/// Describe a completion for the embed, in English
+///
+/// Times shorter than `min_active_time` can't have been tracked properly (e.g. the
+/// puzzle was solved elsewhere and only the result was seen), so they are left out.
+/// So is a time of zero, which means no play was ever seen rather than an instant solve.
+pub fn completion_description(
+    user_name: &str,
+    total_time: std::time::Duration,
+    min_active_time: std::time::Duration,
+    hard_mode: bool,
+    is_update: bool,
+) -> String {
+    completion_description_in(
+        Locale::English.strings(),
+        user_name,
+        total_time,
+        min_active_time,
+        hard_mode,
+        is_update,
+    )
+}
+
+/// [`completion_description`] in the language of `strings`
+pub fn completion_description_in(
+    strings: &Strings,
+    user_name: &str,
+    total_time: std::time::Duration,
+    min_active_time: std::time::Duration,
+    hard_mode: bool,
+    is_update: bool,
+) -> String {
+    let mut description = if total_time.is_zero() || total_time < min_active_time {
+        info!(
+            "Omitting unreliable time of {:?} for {} (minimum {:?})",
+            total_time, user_name, min_active_time
+        );
+        fill(strings.finished, &[("name", user_name)])
+    } else {
+        fill(
+            strings.finished_in,
+            &[
+                ("name", user_name),
+                ("time", &format_duration_in(strings, total_time)),
+            ],
+        )
+    };
+
+    if hard_mode {
+        description.push(' ');
+        description.push_str(strings.hard_mode);
+    }
+    if is_update {
+        description.push(' ');
+        description.push_str(strings.updated);
+    }
+
+    description
+}
+
+/// Describe several players finishing in one screenshot, fastest first, in English
+///
+/// Players whose time is left out, see [`completion_description`], are listed last.
+pub fn combined_completion_description(
+    finishers: &[(&str, std::time::Duration)],
+    min_active_time: std::time::Duration,
+    is_update: bool,
+) -> String {
+    combined_completion_description_in(
+        Locale::English.strings(),
+        finishers,
+        min_active_time,
+        is_update,
+    )
+}
+
+/// [`combined_completion_description`] in the language of `strings`
+pub fn combined_completion_description_in(
+    strings: &Strings,
+    finishers: &[(&str, std::time::Duration)],
+    min_active_time: std::time::Duration,
+    is_update: bool,
+) -> String {
+    let is_timed = |time: &std::time::Duration| !time.is_zero() && *time >= min_active_time;
+    let mut ranked = finishers.to_vec();
+    ranked.sort_by_key(|(user_name, time)| (!is_timed(time), *time, *user_name));
+
+    let mut description = ranked
+        .iter()
+        .map(|(user_name, time)| {
+            if is_timed(time) {
+                fill(
+                    strings.combined_finished_in,
+                    &[
+                        ("name", user_name),
+                        ("time", &format_duration_in(strings, *time)),
+                    ],
+                )
+            } else {
+                fill(strings.combined_finished, &[("name", user_name)])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if is_update {
+        description.push('\n');
+        description.push_str(strings.updated);
+    }
+
+    description
+}
+
+/// Builds a completion embed in the guild's style and language, noting when the game
+/// was finished
+pub fn completion_embed(
+    config: &GuildConfig,
+    description: String,
+    completed_at: DateTime<Utc>,
+) -> CreateEmbed {
+    let (r, g, b) = config.embed.colour;
+    CreateEmbed::new()
+        .title(&config.embed.title)
+        .description(description)
+        .colour(Colour::from_rgb(r, g, b))
+        .footer(CreateEmbedFooter::new(format!(
+            "{} {}",
+            render_footer(&config.embed.footer, completed_at, config.timezone),
+            fill(
+                config.locale.strings().finished_at,
+                &[(
+                    "time",
+                    &format_completion_time(completed_at, config.timezone)
+                )],
+            )
+        )))
+}
+
+/// Fills in a footer template for a game finished at `at`
+///
+/// `{puzzle}` becomes the day's puzzle number (e.g. "1,123") and `{date}` the date
+/// (e.g. "12 Jun 2024"), both in the timezone the game was played in.
+pub fn render_footer(template: &str, at: DateTime<Utc>, tz: Tz) -> String {
+    let date = at.with_timezone(&tz).date_naive();
+    template
+        .replace(
+            "{puzzle}",
+            &format_thousands(share::wordle_number_for_date(date)),
+        )
+        .replace("{date}", &date.format("%-d %b %Y").to_string())
+}
+
+/// Writes a number with commas between the thousands, as Wordle shares do
+fn format_thousands(number: u32) -> String {
+    let digits = number.to_string();
+    let mut formatted = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Wraps a completion embed in a webhook message, posted under the webhook's configured
+/// name and avatar when set
+pub fn completion_webhook(webhook: &WebhookConfig, embed: CreateEmbed) -> ExecuteWebhook {
+    let mut execute = ExecuteWebhook::new().embed(embed);
+    if let Some(name) = &webhook.name {
+        execute = execute.username(name);
+    }
+    if let Some(avatar_url) = &webhook.avatar_url {
+        execute = execute.avatar_url(avatar_url);
+    }
+    execute
+}
+
+/// Describe when a player started today's game, for /when, in English
+///
+/// `elapsed` is the final time of a completed game, or the time so far of one in progress.
+pub fn start_description(
+    user_name: &str,
+    started_at: DateTime<Utc>,
+    elapsed: std::time::Duration,
+    completed: bool,
+    timezone: Tz,
+) -> String {
+    start_description_in(
+        Locale::English.strings(),
+        user_name,
+        started_at,
+        elapsed,
+        completed,
+        timezone,
+    )
+}
+
+/// [`start_description`] in the language of `strings`
+pub fn start_description_in(
+    strings: &Strings,
+    user_name: &str,
+    started_at: DateTime<Utc>,
+    elapsed: std::time::Duration,
+    completed: bool,
+    timezone: Tz,
+) -> String {
+    let started = fill(
+        strings.started_at,
+        &[
+            ("name", user_name),
+            ("time", &format_completion_time(started_at, timezone)),
+        ],
+    );
+    let template = if completed {
+        strings.started_finished
+    } else {
+        strings.started_playing
+    };
+    fill(
+        template,
+        &[
+            ("started", &started),
+            ("time", &format_duration_in(strings, elapsed)),
+        ],
+    )
+}
+
+/// Formats when a game was finished as a local wall-clock time, e.g. "9:41 PM AEST"
+pub fn format_completion_time(completed_at: DateTime<Utc>, timezone: Tz) -> String {
+    completed_at
+        .with_timezone(&timezone)
+        .format("%-I:%M %p %Z")
+        .to_string()
+}
+
+/// Format a duration into a human-readable string, in English
 pub fn format_duration(duration: std::time::Duration) -> String {
+    format_duration_in(Locale::English.strings(), duration)
+}
+
+/// [`format_duration`] in the language of `strings`
+pub fn format_duration_in(strings: &Strings, duration: std::time::Duration) -> String {
     let total_seconds = duration.as_secs();
     let hours = total_seconds / 3600;
     let remaining_seconds_after_hours = total_seconds % 3600;
@@ -122,30 +849,203 @@ pub fn format_duration(duration: std::time::Duration) -> String {
 
     if hours > 0 {
         time_parts.push(format!(
-            "{} hour{}",
+            "{} {}",
             hours,
-            if hours != 1 { "s" } else { "" }
+            if hours != 1 {
+                strings.hours
+            } else {
+                strings.hour
+            }
         ));
     }
     if minutes > 0 {
         time_parts.push(format!(
-            "{} minute{}",
+            "{} {}",
             minutes,
-            if minutes != 1 { "s" } else { "" }
+            if minutes != 1 {
+                strings.minutes
+            } else {
+                strings.minute
+            }
         ));
     }
     // Always include seconds and milliseconds
     time_parts.push(format!(
-        "{}.{:03} second{}",
+        "{}.{:03} {}",
         seconds,
         milliseconds,
-        if seconds != 1 { "s" } else { "" }
+        if seconds != 1 {
+            strings.seconds
+        } else {
+            strings.second
+        }
     ));
 
     if time_parts.len() == 1 {
         time_parts[0].clone()
     } else {
         let last_part = time_parts.pop().unwrap(); // Safe to unwrap as we always have milliseconds
-        format!("{} and {}", time_parts.join(", "), last_part)
+        format!("{} {} {}", time_parts.join(", "), strings.and, last_part)
+    }
+}
+
+/// Parses a solve time as people type one: units like "4m32s", "1h 2m" or "95.5s", a
+/// clock like "4:32" or "1:04:32.019", or as [`format_duration`] writes it, e.g.
+/// "1 hour, 4 minutes and 32.019 seconds"
+///
+/// Units must go from hours down to seconds, each at most once, and may be separated by
+/// commas or "and". Only seconds can have a fraction, to the millisecond.
+pub fn parse_duration(value: &str) -> Result<std::time::Duration> {
+    let invalid = |reason: &str| WordleError::InvalidDuration {
+        value: value.to_string(),
+        reason: reason.to_string(),
+    };
+    let text = value.trim().to_lowercase();
+    if text.is_empty() {
+        return Err(invalid("it's empty"));
+    }
+
+    if text.contains(':') {
+        parse_clock(&text).map_err(|reason| invalid(&reason))
+    } else {
+        parse_units(&text).map_err(|reason| invalid(&reason))
+    }
+}
+
+/// Seconds written as a whole number with an optional fraction of up to three digits
+fn parse_seconds(text: &str) -> std::result::Result<std::time::Duration, String> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{text:?} isn't a number of seconds"));
+    }
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) || (text.contains('.') && fraction.is_empty())
+    {
+        return Err(format!("{text:?} isn't a number of seconds"));
+    }
+    if fraction.len() > 3 {
+        return Err("times are only kept to the millisecond".to_string());
+    }
+
+    let seconds: u64 = whole
+        .parse()
+        .map_err(|_| "the time is too long".to_string())?;
+    let millis: u32 = format!("{fraction:0<3}").parse().unwrap_or(0);
+    Ok(std::time::Duration::from_secs(seconds) + std::time::Duration::from_millis(millis.into()))
+}
+
+/// A whole number of hours or minutes
+fn parse_count(text: &str, unit: &str) -> std::result::Result<u64, String> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{text:?} isn't a whole number of {unit}"));
+    }
+    text.parse().map_err(|_| "the time is too long".to_string())
+}
+
+/// A clock time: `M:SS` or `H:MM:SS`, with an optional fraction of a second
+fn parse_clock(text: &str) -> std::result::Result<std::time::Duration, String> {
+    let parts: Vec<&str> = text.split(':').map(str::trim).collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [minutes, seconds] => (0, parse_count(minutes, "minutes")?, *seconds),
+        [hours, minutes, seconds] => {
+            if minutes.len() != 2 {
+                return Err("minutes after the hours need two digits, e.g. 1:04:32".to_string());
+            }
+            let minutes = parse_count(minutes, "minutes")?;
+            if minutes >= 60 {
+                return Err("there are only 60 minutes in an hour".to_string());
+            }
+            (parse_count(hours, "hours")?, minutes, *seconds)
+        }
+        _ => return Err("a clock time is M:SS or H:MM:SS".to_string()),
+    };
+
+    if seconds
+        .split('.')
+        .next()
+        .is_none_or(|whole| whole.len() != 2)
+    {
+        return Err("seconds after the minutes need two digits, e.g. 4:05".to_string());
+    }
+    let seconds = parse_seconds(seconds)?;
+    if seconds.as_secs() >= 60 {
+        return Err("there are only 60 seconds in a minute".to_string());
+    }
+
+    Ok(std::time::Duration::from_secs(hours * 3600 + minutes * 60) + seconds)
+}
+
+/// Amounts of hours, minutes and seconds, largest first, e.g. `1h 2m 3.5s`
+fn parse_units(text: &str) -> std::result::Result<std::time::Duration, String> {
+    const UNITS: [(&str, u64); 3] = [("h", 3600), ("m", 60), ("s", 1)];
+
+    let mut total = std::time::Duration::ZERO;
+    let mut next_unit = 0; // Units before this one have been used, or skipped
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        if next_unit > 0 {
+            rest = skip_separator(rest)?;
+        }
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(|| format!("{rest:?} needs a unit, e.g. 4m32s"))?;
+        let (number, after) = rest.split_at(number_end);
+        let after = after.trim_start();
+        let unit_end = after
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_end);
+        match (number.is_empty(), unit.is_empty()) {
+            (true, true) => return Err(format!("unexpected {rest:?}")),
+            (true, false) => return Err(format!("{unit:?} needs a number before it")),
+            (false, true) => return Err(format!("{number:?} needs a unit, e.g. 4m32s")),
+            (false, false) => {}
+        }
+
+        let Some(position) =
+            unit_name(unit).and_then(|unit| UNITS.iter().position(|(name, _)| *name == unit))
+        else {
+            return Err(format!("{unit:?} isn't a unit; use h, m or s"));
+        };
+        if position < next_unit {
+            return Err("units go from hours down to seconds, each once".to_string());
+        }
+        next_unit = position + 1;
+
+        let (name, seconds) = UNITS[position];
+        total += if name == "s" {
+            parse_seconds(number)?
+        } else {
+            std::time::Duration::from_secs(
+                parse_count(number, if name == "h" { "hours" } else { "minutes" })?
+                    .checked_mul(seconds)
+                    .ok_or_else(|| "the time is too long".to_string())?,
+            )
+        };
+        rest = after.trim_start();
+    }
+
+    Ok(total)
+}
+
+/// The short name of a unit written out in full or abbreviated, e.g. "minutes" or "min"
+fn unit_name(unit: &str) -> Option<&'static str> {
+    match unit {
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some("h"),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some("m"),
+        "s" | "sec" | "secs" | "second" | "seconds" => Some("s"),
+        _ => None,
+    }
+}
+
+/// Skips a comma or "and" between two units, failing if nothing follows it
+fn skip_separator(text: &str) -> std::result::Result<&str, String> {
+    let rest = text.strip_prefix(',').unwrap_or(text).trim_start();
+    let rest = match rest.strip_prefix("and") {
+        Some(after) if after.starts_with(|c: char| c.is_whitespace()) => after.trim_start(),
+        _ => rest,
+    };
+    if rest.is_empty() {
+        return Err(format!("{text:?} needs a time after it"));
     }
+    Ok(rest)
 }
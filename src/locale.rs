@@ -0,0 +1,264 @@
+//! The bot's user-facing text in each language it can speak
+//!
+//! Templates mark what's filled in with names in braces, e.g. `{name}`, filled in with
+//! [`fill`].
+
+use std::str::FromStr;
+
+/// A language the bot's messages can be shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+    Spanish,
+}
+
+impl Locale {
+    /// The locale's language code, as written in the config
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::French => "fr",
+            Locale::Spanish => "es",
+        }
+    }
+
+    /// Every piece of text shown in the locale's language
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::French => &FRENCH,
+            Locale::Spanish => &SPANISH,
+        }
+    }
+}
+
+/// Parses a language code, ignoring any region, e.g. "fr" or "es-MX"
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let language = code.trim().split(['-', '_']).next().unwrap_or_default();
+        match language.to_lowercase().as_str() {
+            "en" => Ok(Locale::English),
+            "fr" => Ok(Locale::French),
+            "es" => Ok(Locale::Spanish),
+            _ => Err(format!("unsupported locale {code:?}")),
+        }
+    }
+}
+
+/// The text of the completion embeds and command responses in one language
+#[derive(Debug)]
+pub struct Strings {
+    // Completion embeds
+    pub embed_title: &'static str,
+    pub embed_footer: &'static str,
+    pub finished: &'static str,             // {name}
+    pub finished_in: &'static str,          // {name} and {time}
+    pub combined_finished: &'static str,    // {name}
+    pub combined_finished_in: &'static str, // {name} and {time}
+    pub hard_mode: &'static str,
+    pub updated: &'static str,
+    pub finished_at: &'static str, // {time}, the local time the game was finished
+
+    // Durations, see [`crate::format_duration_in`]
+    pub hour: &'static str,
+    pub hours: &'static str,
+    pub minute: &'static str,
+    pub minutes: &'static str,
+    pub second: &'static str,
+    pub seconds: &'static str,
+    pub and: &'static str,
+
+    // /help
+    pub help_title: &'static str,
+    pub help_tracked: &'static str, // {channel} and {timezone}
+    pub admins_only: &'static str,
+    pub moderators_only: &'static str,
+
+    // /progress
+    pub progress_title: &'static str,
+    pub progress_finished: &'static str,
+    pub progress_playing: &'static str,
+    pub progress_finished_in: &'static str, // {name} and {time}
+    pub progress_playing_for: &'static str, // {name} and {time}
+    pub nobody_yet: &'static str,
+
+    // /leaderboard
+    pub leaderboard_title: &'static str,
+    pub leaderboard_page: &'static str, // {page} and {pages}
+    pub previous: &'static str,
+    pub next: &'static str,
+    pub leaderboard_guild_only: &'static str,
+    pub leaderboard_unavailable: &'static str,
+
+    // /when
+    pub started_at: &'static str,       // {name} and {time}
+    pub started_finished: &'static str, // {started} and {time}
+    pub started_playing: &'static str,  // {started} and {time}
+    pub not_started: &'static str,      // {name}
+}
+
+pub static ENGLISH: Strings = Strings {
+    embed_title: "🧩 Wordle Solved!",
+    embed_footer: "Time tracked by Matt's third brain.",
+    finished: "{name} finished their Wordle!",
+    finished_in: "{name} finished their Wordle in **{time}**!",
+    combined_finished: "{name} finished",
+    combined_finished_in: "{name} finished in **{time}**",
+    hard_mode: "(hard mode)",
+    updated: "(Updated)",
+    finished_at: "Finished at {time}.",
+
+    hour: "hour",
+    hours: "hours",
+    minute: "minute",
+    minutes: "minutes",
+    second: "second",
+    seconds: "seconds",
+    and: "and",
+
+    help_title: "Wordle Timer Commands",
+    help_tracked: "Games are tracked in {channel} on {timezone} time.",
+    admins_only: "*(admins only)*",
+    moderators_only: "*(moderators only)*",
+
+    progress_title: "🧩 Today's Wordle Progress",
+    progress_finished: "✅ Finished",
+    progress_playing: "⏳ Still playing",
+    progress_finished_in: "**{name}** in {time}",
+    progress_playing_for: "**{name}** for {time}",
+    nobody_yet: "Nobody yet",
+
+    leaderboard_title: "🏆 All-time Fastest Solves",
+    leaderboard_page: "Page {page} of {pages}",
+    previous: "Prev",
+    next: "Next",
+    leaderboard_guild_only: "Leaderboards are only kept in servers.",
+    leaderboard_unavailable: "Unable to load the leaderboard right now.",
+
+    started_at: "{name} started today's Wordle at {time}",
+    started_finished: "{started} and finished in **{time}**.",
+    started_playing: "{started} and has been playing for **{time}** so far.",
+    not_started: "{name} hasn't started today's Wordle yet.",
+};
+
+pub static FRENCH: Strings = Strings {
+    embed_title: "🧩 Wordle résolu !",
+    embed_footer: "Temps mesuré par le troisième cerveau de Matt.",
+    finished: "{name} a terminé son Wordle !",
+    finished_in: "{name} a terminé son Wordle en **{time}** !",
+    combined_finished: "{name} a terminé",
+    combined_finished_in: "{name} a terminé en **{time}**",
+    hard_mode: "(mode difficile)",
+    updated: "(Mis à jour)",
+    finished_at: "Terminé à {time}.",
+
+    hour: "heure",
+    hours: "heures",
+    minute: "minute",
+    minutes: "minutes",
+    second: "seconde",
+    seconds: "secondes",
+    and: "et",
+
+    help_title: "Commandes de Wordle Timer",
+    help_tracked: "Les parties sont suivies dans {channel}, à l'heure de {timezone}.",
+    admins_only: "*(administrateurs uniquement)*",
+    moderators_only: "*(modérateurs uniquement)*",
+
+    progress_title: "🧩 Le Wordle du jour",
+    progress_finished: "✅ Terminé",
+    progress_playing: "⏳ En cours",
+    progress_finished_in: "**{name}** en {time}",
+    progress_playing_for: "**{name}** depuis {time}",
+    nobody_yet: "Personne pour l'instant",
+
+    leaderboard_title: "🏆 Meilleurs temps de tous les temps",
+    leaderboard_page: "Page {page} sur {pages}",
+    previous: "Préc.",
+    next: "Suiv.",
+    leaderboard_guild_only: "Les classements ne sont tenus que sur les serveurs.",
+    leaderboard_unavailable: "Impossible de charger le classement pour le moment.",
+
+    started_at: "{name} a commencé le Wordle du jour à {time}",
+    started_finished: "{started} et l'a terminé en **{time}**.",
+    started_playing: "{started} et joue depuis **{time}**.",
+    not_started: "{name} n'a pas encore commencé le Wordle du jour.",
+};
+
+pub static SPANISH: Strings = Strings {
+    embed_title: "🧩 ¡Wordle resuelto!",
+    embed_footer: "Tiempo medido por el tercer cerebro de Matt.",
+    finished: "¡{name} terminó su Wordle!",
+    finished_in: "¡{name} terminó su Wordle en **{time}**!",
+    combined_finished: "{name} terminó",
+    combined_finished_in: "{name} terminó en **{time}**",
+    hard_mode: "(modo difícil)",
+    updated: "(Actualizado)",
+    finished_at: "Terminado a las {time}.",
+
+    hour: "hora",
+    hours: "horas",
+    minute: "minuto",
+    minutes: "minutos",
+    second: "segundo",
+    seconds: "segundos",
+    and: "y",
+
+    help_title: "Comandos de Wordle Timer",
+    help_tracked: "Las partidas se siguen en {channel}, en hora de {timezone}.",
+    admins_only: "*(solo administradores)*",
+    moderators_only: "*(solo moderadores)*",
+
+    progress_title: "🧩 El Wordle de hoy",
+    progress_finished: "✅ Terminado",
+    progress_playing: "⏳ Jugando",
+    progress_finished_in: "**{name}** en {time}",
+    progress_playing_for: "**{name}** desde hace {time}",
+    nobody_yet: "Nadie todavía",
+
+    leaderboard_title: "🏆 Las soluciones más rápidas",
+    leaderboard_page: "Página {page} de {pages}",
+    previous: "Ant.",
+    next: "Sig.",
+    leaderboard_guild_only: "Las clasificaciones solo se llevan en servidores.",
+    leaderboard_unavailable: "No se puede cargar la clasificación ahora mismo.",
+
+    started_at: "{name} empezó el Wordle de hoy a las {time}",
+    started_finished: "{started} y lo terminó en **{time}**.",
+    started_playing: "{started} y lleva **{time}** jugando.",
+    not_started: "{name} todavía no ha empezado el Wordle de hoy.",
+};
+
+/// Fills in a template's `{key}`s with their values, leaving unknown keys as written
+///
+/// Done in one pass, so braces in a value (e.g. a player's name) are never filled in.
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(key, _)| *key == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                text.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
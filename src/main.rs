@@ -1,6 +1,12 @@
-use log::{debug, error, info};
+mod commands;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use log::{debug, error, info, warn};
 use serenity::all::{
-    Colour, CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage, MessageUpdateEvent,
+    ApplicationFlags, AutoArchiveDuration, ChannelType, Colour, Command, ConnectionStage,
+    CreateAttachment, CreateEmbed, CreateEmbedFooter, CreateMessage, CreateThread, EditMessage,
+    EditWebhookMessage, GetMessages, Http, HttpError, Interaction, MessageUpdateEvent, Presence,
+    Reaction, ResumedEvent, ShardStageUpdateEvent, Webhook,
 };
 use serenity::async_trait;
 use serenity::model::channel::Message;
@@ -8,140 +14,1324 @@ use serenity::model::gateway::Ready;
 use serenity::prelude::*;
 use std::collections::HashMap;
 use std::env;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use std::time::Instant;
+use wordle_timer_bot::announcements::{fastest_description, fastest_solvers};
+use wordle_timer_bot::archive::Archive;
+use wordle_timer_bot::config::{Config, DEFAULT_CONFIG_PATH, GuildConfig, WebhookConfig};
+use wordle_timer_bot::events::{CompletionEvent, CompletionEvents};
+use wordle_timer_bot::game::{
+    GameState, StartedAt, WordlePuzzles, completed_by_reaction, is_players_game, roll_over_day,
+};
+use wordle_timer_bot::history::{Completion, History};
+use wordle_timer_bot::metrics::metrics;
+use wordle_timer_bot::overrides::AvatarOverrides;
+use wordle_timer_bot::reminders::{
+    REMINDER_CHECK_INTERVAL, ReminderTime, is_reminder_due, players_to_remind,
+};
+use wordle_timer_bot::seen::RecentScreenshots;
+use wordle_timer_bot::server::{self, ServerState};
+use wordle_timer_bot::share::{WordleShare, parse_share};
 use wordle_timer_bot::{
-    FINISHED_TRIGGERS, PLAYING_TRIGGERS, Player, format_duration, parse_usernames,
+    CLEANUP_INTERVAL, DownloadLimiter, FINISHED_TRIGGERS, HARD_MODE_TEMPLATE, MarkerTemplate,
+    PLAYING_TRIGGERS, PastPost, Player, SOLVED_TEMPLATE, SearchRegion, TrackedChannel,
+    annotate_screenshot, clean_downloads, combined_completion_description_in,
+    completion_description_in, completion_embed, completion_webhook, detect_hard_mode,
+    detection_summary, find_players_in_image, http_client, image_attachments,
+    is_completion_reaction, is_debug_channel, is_ignored_author, is_image_attachment,
+    is_missed_completion, is_tracked, is_unknown_target, is_wordle_activity, parse_usernames,
+    render_footer, simulate_detection, validate_templates,
 };
 
-// Constants
-const WORDLE_APP_ID: u64 = 1211781489931452447;
-const EMBED_TITLE: &str = "🧩 Wordle Solved!";
-const EMBED_FOOTER: &str = "Time tracked by Matt's third brain.";
-const EMBED_COLOR: (u8, u8, u8) = (87, 242, 135); // A nice green color
-
-use chrono::{DateTime, Utc};
-use chrono_tz::Australia::Sydney;
-
-// Struct to store game state and metadata
-struct GameState {
-    last_start_time: Instant,               // When the current attempt started
-    total_active_time: std::time::Duration, // Total time spent actively solving
-    completion_msg_id: Option<serenity::model::id::MessageId>, // ID of the completion message if one exists
-    created_at: DateTime<Utc>, // When this game was first started (stored in UTC)
-    completed: bool,
+// Constants
+const WORDLE_APP_ID: u64 = 1211781489931452447;
+const ANNOTATED_FILE_NAME: &str = "detection.png";
+const MAX_AVATAR_CANDIDATES: usize = 25; // Avatars searched for in a screenshot naming nobody
+
+struct Handler {
+    defaults: GuildConfig, // Config for guilds that haven't changed it with /config
+    dry_run: bool,         // Log completion messages instead of posting them
+    min_active_time: std::time::Duration, // Completions faster than this don't show a time
+    midnight_grace: std::time::Duration, // Games started this close to midnight carry over
+    post_cooldown: std::time::Duration, // Re-detections this soon after a post are ignored
+    completion_reaction: Option<String>, // Reacting with this emoji finishes a game
+    connected: Arc<AtomicBool>, // Whether the gateway connection is up, for health checks
+    catch_up: Option<std::time::Duration>, // How far back to look for completions missed while down
+    caught_up: AtomicBool, // Whether the catch-up scan has run, so reconnects don't repeat it
+    history: History,      // Persistent record of finished games
+    events: CompletionEvents, // Where recorded completions are published for other integrations
+    use_threads: bool,     // Post completion messages in a daily thread instead of the channel
+    combine_completions: bool, // Post one message for everyone finishing in a screenshot
+    annotate_completions: bool, // Attach the screenshot with the detection boxed to completions
+    downloads: DownloadLimiter, // Bounds concurrent image downloads across all events
+    confidence_votes: usize, // Detection passes that must agree before a player is found
+    search_region: SearchRegion, // Where in a screenshot players are looked for
+    archive: Option<Archive>, // Where processed screenshots are kept, if anywhere
+    overrides: AvatarOverrides, // Avatars moderators set in place of players' own
+    webhook: Option<Webhook>, // Posts completion messages instead of the bot user, if set
+    webhook_config: WebhookConfig, // Name and avatar to post through the webhook with
+    marker_templates: Vec<MarkerTemplate>, // Completion markers to look for, e.g. one per theme
+    recent_screenshots: RecentScreenshots, // Screenshots processed lately, skipped if shown again
+    debug_channel_name: Option<String>, // Where any image is answered with detection details
+    activity_names: Option<Vec<String>>, // Wordle activities that time games, if presences are tracked
+}
+
+/// The message that finished a game, and where it was posted
+struct CompletionTrigger {
+    guild_id: serenity::model::id::GuildId,
+    channel_id: serenity::model::id::ChannelId,
+    msg_id: serenity::model::id::MessageId,
+    annotated: Option<Vec<u8>>, // The screenshot with the detection boxed, as a PNG
+}
+
+impl Handler {
+    /// Creates an embed for a Wordle completion message
+    fn create_completion_embed(
+        &self,
+        config: &GuildConfig,
+        user_name: &str,
+        game_state: &GameState,
+        completed_at: DateTime<Utc>,
+        is_update: bool,
+    ) -> CreateEmbed {
+        let description = completion_description_in(
+            config.locale.strings(),
+            user_name,
+            game_state.total_active_time,
+            self.min_active_time,
+            game_state.hard_mode,
+            is_update,
+        );
+        completion_embed(config, description, completed_at)
+    }
+
+    /// The config for a guild, or the defaults outside guilds or if it can't be loaded
+    async fn guild_config(&self, guild_id: Option<serenity::model::id::GuildId>) -> GuildConfig {
+        let Some(guild_id) = guild_id else {
+            return self.defaults.clone();
+        };
+
+        match self
+            .history
+            .guild_config(guild_id.get(), &self.defaults)
+            .await
+        {
+            Ok(config) => config,
+            Err(why) => {
+                error!("Error loading config for guild {}: {:?}", guild_id, why);
+                self.defaults.clone()
+            }
+        }
+    }
+
+    /// Validates if a message is from the Wordle app and in the guild's tracked channel,
+    /// returning the guild's config
+    async fn validate_message(
+        &self,
+        ctx: &Context,
+        guild_id: Option<serenity::model::id::GuildId>,
+        channel_id: serenity::model::id::ChannelId,
+        author_id: serenity::model::id::UserId,
+    ) -> Result<GuildConfig, &'static str> {
+        // Check if message is from Wordle app
+        if author_id != serenity::model::id::UserId::new(WORDLE_APP_ID) {
+            return Err("Not from Wordle app");
+        }
+
+        self.tracked_channel_config(ctx, guild_id, channel_id).await
+    }
+
+    /// The guild's config, if the channel is the one it tracks
+    async fn tracked_channel_config(
+        &self,
+        ctx: &Context,
+        guild_id: Option<serenity::model::id::GuildId>,
+        channel_id: serenity::model::id::ChannelId,
+    ) -> Result<GuildConfig, &'static str> {
+        let config = self.guild_config(guild_id).await;
+
+        // Check channel, only looking up its name when configured by name
+        let channel_name = match config.channel {
+            TrackedChannel::Id(_) => None,
+            TrackedChannel::Name(_) => Some(
+                channel_id
+                    .name(&ctx.http)
+                    .await
+                    .map_err(|_| "Unable to get channel information")?,
+            ),
+        };
+
+        if !config
+            .channel
+            .matches(channel_id.get(), channel_name.as_deref())
+        {
+            return Err("Not in daily puzzles channel");
+        }
+
+        Ok(config)
+    }
+
+    /// Sends a new completion embed, returning its message ID
+    ///
+    /// With a webhook configured the message is posted through it, in the day's thread
+    /// when that isn't the webhook's own channel. In dry-run mode the message is only
+    /// logged and the triggering message's ID is returned in its place, so later
+    /// detections still exercise the update path.
+    async fn send_completion_message(
+        &self,
+        ctx: &Context,
+        channel_id: serenity::model::id::ChannelId,
+        trigger_msg_id: serenity::model::id::MessageId,
+        embed_msg: CreateEmbed,
+        annotated: Option<&[u8]>,
+    ) -> Option<serenity::model::id::MessageId> {
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would send completion message to channel {}: {:?}",
+                channel_id, embed_msg
+            );
+            return Some(trigger_msg_id);
+        }
+
+        // Shown as the embed's image rather than as a separate file
+        let attachment = annotated.map(|png| CreateAttachment::bytes(png, ANNOTATED_FILE_NAME));
+        let embed_msg = match attachment {
+            Some(_) => embed_msg.image(format!("attachment://{ANNOTATED_FILE_NAME}")),
+            None => embed_msg,
+        };
+
+        let sent = match &self.webhook {
+            Some(webhook) => {
+                let mut execute = completion_webhook(&self.webhook_config, embed_msg);
+                if let Some(attachment) = attachment {
+                    execute = execute.add_file(attachment);
+                }
+                if webhook.channel_id != Some(channel_id) {
+                    execute = execute.in_thread(channel_id);
+                }
+                // Waiting makes Discord return the message, whose ID is needed for edits
+                webhook
+                    .execute(&ctx.http, true, execute)
+                    .await
+                    .map(|sent| sent.map(|sent| sent.id))
+            }
+            None => {
+                let mut message = CreateMessage::new().embed(embed_msg);
+                if let Some(attachment) = attachment {
+                    message = message.add_file(attachment);
+                }
+                channel_id
+                    .send_message(&ctx.http, message)
+                    .await
+                    .map(|sent| Some(sent.id))
+            }
+        };
+        match sent {
+            Ok(sent_id) => sent_id,
+            Err(why) => {
+                error!("Error sending completion message: {:?}", why);
+                None
+            }
+        }
+    }
+
+    /// Picks where completion messages for a puzzle day go
+    ///
+    /// With threads enabled this is the day's thread, created on first use and recreated
+    /// if someone deleted it. Otherwise, or if the thread can't be created, it's the
+    /// channel itself.
+    async fn completion_channel(
+        &self,
+        ctx: &Context,
+        channel_id: serenity::model::id::ChannelId,
+        date: chrono::NaiveDate,
+    ) -> serenity::model::id::ChannelId {
+        if !self.use_threads {
+            return channel_id;
+        }
+
+        match self.history.daily_thread(channel_id.get(), date).await {
+            Ok(Some(thread_id)) => {
+                let thread_id = serenity::model::id::ChannelId::new(thread_id);
+                match thread_id.to_channel(&ctx.http).await {
+                    Ok(_) => return thread_id,
+                    Err(why) if is_not_found(&why) => {
+                        info!("Daily thread {} was deleted, recreating it", thread_id);
+                    }
+                    Err(why) => {
+                        // Most likely a transient failure, so keep using the thread
+                        error!("Error checking daily thread {}: {:?}", thread_id, why);
+                        return thread_id;
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(why) => error!("Error looking up daily thread: {:?}", why),
+        }
+
+        let name = format!("Wordle — {}", date);
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would create thread \"{}\" in channel {}",
+                name, channel_id
+            );
+            return channel_id;
+        }
+
+        let builder = CreateThread::new(name)
+            .kind(ChannelType::PublicThread)
+            .auto_archive_duration(AutoArchiveDuration::OneDay);
+        match channel_id.create_thread(&ctx.http, builder).await {
+            Ok(thread) => {
+                info!("Created daily thread {} for {}", thread.id, date);
+                if let Err(why) = self
+                    .history
+                    .set_daily_thread(channel_id.get(), date, thread.id.get())
+                    .await
+                {
+                    error!("Error saving daily thread: {:?}", why);
+                }
+                thread.id
+            }
+            Err(why) => {
+                error!("Error creating daily thread: {:?}", why);
+                channel_id
+            }
+        }
+    }
+
+    /// Looks up the guild member the Wordle app refers to by (lowercased) display name
+    async fn resolve_member(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        user_name: &str,
+    ) -> Option<serenity::model::guild::Member> {
+        match guild_id
+            .search_members(&ctx.http, user_name, Some(10))
+            .await
+        {
+            Ok(members) => members
+                .into_iter()
+                .find(|member| member.display_name().to_lowercase() == user_name),
+            Err(why) => {
+                error!("Error searching for member {}: {:?}", user_name, why);
+                None
+            }
+        }
+    }
+
+    /// The members likely to be in a screenshot that doesn't name its players: those with
+    /// a game tracked in the guild, then those who played most recently, up to
+    /// [`MAX_AVATAR_CANDIDATES`]
+    ///
+    /// Every one of their avatars is downloaded, so the whole server never is.
+    async fn likely_players(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+    ) -> Vec<serenity::model::guild::Member> {
+        let mut user_ids: Vec<serenity::model::id::UserId> = {
+            let data_read = ctx.data.read().await;
+            let puzzle_map = data_read
+                .get::<WordlePuzzles>()
+                .expect("Expected WordlePuzzles in TypeMap")
+                .lock()
+                .await;
+            puzzle_map
+                .iter()
+                .filter(|((guild, _, _), game_state)| *guild == guild_id && game_state.is_current())
+                .map(|((_, _, user_id), _)| *user_id)
+                .collect()
+        };
+        match self
+            .history
+            .recent_players(guild_id.get(), MAX_AVATAR_CANDIDATES)
+            .await
+        {
+            Ok(recent) => user_ids.extend(recent.into_iter().map(serenity::model::id::UserId::new)),
+            Err(why) => error!("Error loading recent players: {:?}", why),
+        }
+        let mut seen = std::collections::HashSet::new();
+        user_ids.retain(|user_id| seen.insert(*user_id));
+        user_ids.truncate(MAX_AVATAR_CANDIDATES);
+
+        let mut members = Vec::new();
+        for user_id in user_ids {
+            match guild_id.member(ctx, user_id).await {
+                Ok(member) => members.push(member),
+                Err(why) => debug!("Unable to find member {}: {:?}", user_id, why),
+            }
+        }
+        members
+    }
+
+    /// The player to look for in screenshots for a member: by the avatar a moderator set
+    /// if there is one, otherwise by their server and global avatars
+    fn member_player(
+        &self,
+        guild_id: serenity::model::id::GuildId,
+        member: &serenity::model::guild::Member,
+    ) -> Option<Player> {
+        let uid = member.user.id.get() as usize;
+        // A moderator-set avatar is trusted over whatever Discord has
+        if let Some(path) = self.overrides.get(guild_id.get(), member.user.id.get()) {
+            return Some(Player::with_override(uid, path));
+        }
+        // A server avatar may be shown instead of the global one, so try both
+        let mut urls = member
+            .avatar_url()
+            .into_iter()
+            .chain(member.user.static_avatar_url());
+        let image_url = urls.next()?;
+        Some(urls.fold(Player::new(uid, image_url), Player::with_avatar))
+    }
+
+    /// The finished screenshot with the players' avatars and completion markers boxed,
+    /// kept in the archive if there is one
+    async fn annotate_completion(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        screenshot_url: &str,
+        user_ids: &[serenity::model::id::UserId],
+    ) -> Option<Vec<u8>> {
+        let mut players = Vec::new();
+        for user_id in user_ids {
+            match guild_id.member(ctx, *user_id).await {
+                Ok(member) => players.extend(self.member_player(guild_id, &member)),
+                Err(why) => error!("Error getting member {}: {:?}", user_id, why),
+            }
+        }
+
+        let annotated = match annotate_screenshot(
+            &self.downloads,
+            &self.marker_templates,
+            screenshot_url,
+            &players,
+        )
+        .await
+        {
+            Ok(annotated) => annotated,
+            Err(why) => {
+                error!("Error annotating screenshot: {:?}", why);
+                return None;
+            }
+        };
+
+        // Nothing else writes the annotated copy to disk, so there's nothing to clean up
+        if let Some(archive) = &self.archive {
+            match archive
+                .save_annotated(&annotated, screenshot_url, Utc::now())
+                .await
+            {
+                Ok(path) => info!("Archived annotated screenshot to {}", path.display()),
+                Err(why) => error!("Error archiving annotated screenshot: {}", why),
+            }
+        }
+        Some(annotated)
+    }
+
+    /// The members the Wordle app names on message `msg_id`, with the name it used,
+    /// leaving out anyone who opted out of tracking or can't be found
+    ///
+    /// Names already tracked on the message are taken from its games, as resolving a
+    /// member costs a request and the app edits the message for every player.
+    async fn resolve_players(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        msg_id: serenity::model::id::MessageId,
+        usernames: Vec<String>,
+    ) -> Vec<(serenity::model::id::UserId, String)> {
+        let opted_out = match self.history.opted_out_users(guild_id.get()).await {
+            Ok(opted_out) => opted_out,
+            Err(why) => {
+                error!("Error loading opted-out users: {:?}", why);
+                Default::default()
+            }
+        };
+        let known: HashMap<String, serenity::model::id::UserId> = {
+            let data_read = ctx.data.read().await;
+            let puzzle_map = data_read
+                .get::<WordlePuzzles>()
+                .expect("Expected WordlePuzzles in TypeMap")
+                .lock()
+                .await;
+            puzzle_map
+                .iter()
+                .filter(|((guild, msg, _), _)| *guild == guild_id && *msg == msg_id)
+                .map(|((_, _, user_id), game_state)| (game_state.player_name.clone(), *user_id))
+                .collect()
+        };
+
+        let mut players = Vec::new();
+        for username in usernames {
+            let user_id = match known.get(&username) {
+                Some(user_id) => *user_id,
+                None => match self.resolve_member(ctx, guild_id, &username).await {
+                    Some(member) => member.user.id,
+                    None => {
+                        info!("Unable to find member {}, not tracking them", username);
+                        continue;
+                    }
+                },
+            };
+            if is_tracked(Some(user_id.get()), &opted_out) {
+                players.push((user_id, username));
+            } else {
+                info!("Not tracking {}, they opted out", username);
+            }
+        }
+        players
+    }
+
+    /// Credits completions the Wordle app posted in a guild's tracked channel within
+    /// `lookback`, while the bot wasn't running to see them
+    async fn catch_up(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        lookback: std::time::Duration,
+    ) {
+        let config = self.guild_config(Some(guild_id)).await;
+        let channels = match guild_id.channels(&ctx.http).await {
+            Ok(channels) => channels,
+            Err(why) => {
+                error!("Error listing channels of guild {}: {:?}", guild_id, why);
+                return;
+            }
+        };
+        let Some(channel_id) = channels
+            .values()
+            .find(|channel| {
+                config
+                    .channel
+                    .matches(channel.id.get(), Some(&channel.name))
+            })
+            .map(|channel| channel.id)
+        else {
+            info!("Guild {} has no tracked channel to catch up on", guild_id);
+            return;
+        };
+
+        let messages = match channel_id
+            .messages(&ctx.http, GetMessages::new().limit(100))
+            .await
+        {
+            Ok(messages) => messages,
+            Err(why) => {
+                error!(
+                    "Error fetching recent messages in {}: {:?}",
+                    channel_id, why
+                );
+                return;
+            }
+        };
+        let now = Utc::now();
+        // Oldest first, so players are credited in the order they finished
+        let missed: Vec<&Message> = messages
+            .iter()
+            .rev()
+            .filter(|msg| {
+                let post = PastPost {
+                    author_id: msg.author.id.get(),
+                    content: &msg.content,
+                    has_image: msg.attachments.iter().any(is_image_attachment),
+                    posted_at: *msg.timestamp,
+                };
+                is_missed_completion(&post, WORDLE_APP_ID, now, lookback, config.timezone)
+            })
+            .collect();
+
+        info!(
+            "Catching up on {} completion(s) posted in guild {} while down",
+            missed.len(),
+            guild_id
+        );
+        for msg in missed {
+            self.credit_missed_completion(ctx, &config, guild_id, msg)
+                .await;
+        }
+    }
+
+    /// Finishes the games in a completion the bot missed, for players without one already
+    /// recorded that day
+    ///
+    /// The time is taken from the app posting the game until it edited it to say it was
+    /// finished, so any time spent away from the game counts too.
+    async fn credit_missed_completion(
+        &self,
+        ctx: &Context,
+        config: &GuildConfig,
+        guild_id: serenity::model::id::GuildId,
+        msg: &Message,
+    ) {
+        let started_at = *msg.timestamp;
+        let finished_at = msg.edited_timestamp.map_or(started_at, |edited| *edited);
+        let elapsed = (finished_at - started_at).to_std().unwrap_or_default();
+        let puzzle_date = GameState::in_timezone_at(config.timezone, started_at).puzzle_date;
+
+        let usernames = parse_usernames(&msg.content.to_lowercase());
+        let mut missed = Vec::new();
+        for (user_id, username) in self.resolve_players(ctx, guild_id, msg.id, usernames).await {
+            match self
+                .history
+                .has_completion(guild_id.get(), user_id.get(), puzzle_date)
+                .await
+            {
+                Ok(false) => missed.push((user_id, username)),
+                Ok(true) => debug!("{} was already recorded for {}", username, puzzle_date),
+                Err(why) => error!("Error checking history for {}: {:?}", username, why),
+            }
+        }
+
+        let trigger = CompletionTrigger {
+            guild_id,
+            channel_id: msg.channel_id,
+            msg_id: msg.id,
+            annotated: None,
+        };
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
+        for (user_id, username) in missed {
+            let std::collections::hash_map::Entry::Vacant(entry) =
+                puzzle_map.entry((guild_id, msg.id, user_id))
+            else {
+                continue;
+            };
+            info!("Catching up on {}'s game from {}", username, puzzle_date);
+            let mut game_state = GameState::in_timezone_at(config.timezone, started_at);
+            game_state.active_since = None;
+            game_state.total_active_time = elapsed;
+            game_state.player_name = username;
+            let game_state = entry.insert(game_state);
+            self.complete_game(ctx, config, &trigger, user_id, game_state)
+                .await;
+        }
+    }
+
+    /// Saves a finished game to the history store, returning what was saved
+    async fn save_completion(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        user_id: serenity::model::id::UserId,
+        game_state: &GameState,
+    ) -> Option<Completion> {
+        let member = match guild_id.member(ctx, user_id).await {
+            Ok(member) => member,
+            Err(why) => {
+                info!(
+                    "Unable to find member {}, not recording completion: {:?}",
+                    game_state.player_name, why
+                );
+                return None;
+            }
+        };
+
+        let completion = Completion::from_game(
+            user_id.get(),
+            guild_id.get(),
+            game_state,
+            Some(member.display_name().to_string()),
+        );
+        if let Err(why) = self.history.record_completion(&completion).await {
+            error!(
+                "Error recording completion for {}: {:?}",
+                game_state.player_name, why
+            );
+        }
+        Some(completion)
+    }
+
+    /// Saves the finished games replaced by a new day's, in case they weren't saved when
+    /// they were finished
+    async fn archive_previous_day(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        finished: Vec<(serenity::model::id::UserId, GameState)>,
+    ) {
+        for (user_id, game_state) in finished {
+            if self
+                .save_completion(ctx, guild_id, user_id, &game_state)
+                .await
+                .is_some()
+            {
+                info!(
+                    "Archived {}'s game from {} before the new day",
+                    game_state.player_name, game_state.puzzle_date
+                );
+            }
+        }
+    }
+
+    /// Saves a finished game to the history store and tells subscribers about it
+    async fn record_completion(
+        &self,
+        ctx: &Context,
+        guild_id: serenity::model::id::GuildId,
+        user_id: serenity::model::id::UserId,
+        game_state: &GameState,
+    ) {
+        let Some(completion) = self
+            .save_completion(ctx, guild_id, user_id, game_state)
+            .await
+        else {
+            return;
+        };
+
+        let user_name = &game_state.player_name;
+        let completed_at = game_state.completed_at.unwrap_or_else(Utc::now);
+        let subscribers =
+            self.events
+                .publish(CompletionEvent::new(&completion, user_name, completed_at));
+        debug!(
+            "Published {}'s completion to {} subscriber(s)",
+            user_name, subscribers
+        );
+    }
+
+    /// Finishes a tracked game, posting or updating its completion message and saving it
+    async fn complete_game(
+        &self,
+        ctx: &Context,
+        config: &GuildConfig,
+        trigger: &CompletionTrigger,
+        user_id: serenity::model::id::UserId,
+        game_state: &mut GameState,
+    ) {
+        let completed_at = self.finish_game(game_state);
+        let user_name = game_state.player_name.clone();
+
+        // Screenshots in quick succession would otherwise edit the message for each one
+        let now = Instant::now();
+        if game_state.in_post_cooldown(now, self.post_cooldown) {
+            info!(
+                "Completion message for {} was just posted, not updating it again",
+                user_name
+            );
+            return;
+        }
+
+        // Rewriting a combined message with one player would drop everyone else from it
+        if game_state.combined {
+            info!(
+                "Completion message for {} lists other players too, not updating it alone",
+                user_name
+            );
+        } else {
+            let posted = self
+                .post_completion(ctx, trigger, game_state, |is_update| {
+                    self.create_completion_embed(
+                        config,
+                        &user_name,
+                        game_state,
+                        completed_at,
+                        is_update,
+                    )
+                })
+                .await;
+            game_state.set_completion_message(posted, now);
+        }
+
+        self.record_completion(ctx, trigger.guild_id, user_id, game_state)
+            .await;
+    }
+
+    /// Finishes several games from one screenshot together, posting one message listing
+    /// every player, fastest first
+    ///
+    /// The games must share their completion message, if they have one yet.
+    async fn complete_games_together(
+        &self,
+        ctx: &Context,
+        config: &GuildConfig,
+        trigger: &CompletionTrigger,
+        mut games: Vec<(serenity::model::id::UserId, &mut GameState)>,
+    ) {
+        // The screenshot shows everyone finished once the last of them did
+        let Some(completed_at) = games
+            .iter_mut()
+            .map(|(_, game_state)| self.finish_game(game_state))
+            .max()
+        else {
+            return;
+        };
+
+        let now = Instant::now();
+        let first = &games[0].1;
+        if first.in_post_cooldown(now, self.post_cooldown) {
+            info!("Combined completion message was just posted, not updating it again");
+            return;
+        }
+
+        let finishers: Vec<(&str, std::time::Duration)> = games
+            .iter()
+            .map(|(_, game_state)| {
+                (
+                    game_state.player_name.as_str(),
+                    game_state.total_active_time,
+                )
+            })
+            .collect();
+        let posted = self
+            .post_completion(ctx, trigger, first, |is_update| {
+                let description = combined_completion_description_in(
+                    config.locale.strings(),
+                    &finishers,
+                    self.min_active_time,
+                    is_update,
+                );
+                completion_embed(config, description, completed_at)
+            })
+            .await;
+        for (_, game_state) in games.iter_mut() {
+            game_state.set_completion_message(posted, now);
+            game_state.combined = true;
+        }
+
+        for (user_id, game_state) in &games {
+            self.record_completion(ctx, trigger.guild_id, *user_id, game_state)
+                .await;
+        }
+    }
+
+    /// Ends a game's current attempt and marks it finished, returning when it was
+    /// (first) finished
+    fn finish_game(&self, game_state: &mut GameState) -> DateTime<Utc> {
+        // Add the time from the current attempt, if the game wasn't already finished
+        let previous_time = game_state.total_active_time;
+        game_state.pause(Instant::now());
+        let total_time = game_state.total_active_time;
+
+        info!(
+            "User {} completed game - Current attempt: {:?}, Total time: {:?}",
+            game_state.player_name,
+            total_time - previous_time,
+            total_time
+        );
+
+        if !game_state.completed {
+            metrics().completions_detected.inc();
+        }
+
+        // Update the game state with final time
+        game_state.mark_completed(total_time, Utc::now())
+    }
+
+    /// Updates a game's completion message, or sends one if there isn't one (any more),
+    /// returning the message and the channel it is in, if there is one now
+    ///
+    /// Only a new message gets the trigger's annotated screenshot, edits keep the first.
+    async fn post_completion(
+        &self,
+        ctx: &Context,
+        trigger: &CompletionTrigger,
+        game_state: &GameState,
+        embed: impl Fn(bool) -> CreateEmbed,
+    ) -> Option<(
+        serenity::model::id::MessageId,
+        serenity::model::id::ChannelId,
+    )> {
+        if let Some(msg_id) = game_state.completion_msg_id {
+            info!("Updating existing completion message");
+            let channel_id = game_state
+                .completion_channel_id
+                .unwrap_or(trigger.channel_id);
+            if self
+                .update_completion_message(ctx, channel_id, msg_id, embed(true))
+                .await
+            {
+                return Some((msg_id, channel_id));
+            }
+            // Editing a deleted message would fail on every later detection too, so a
+            // new one is sent instead
+        }
+
+        info!("Sending new completion message");
+        let target_channel = self
+            .completion_channel(ctx, trigger.channel_id, game_state.puzzle_date)
+            .await;
+        let sent_id = self
+            .send_completion_message(
+                ctx,
+                target_channel,
+                trigger.msg_id,
+                embed(false),
+                trigger.annotated.as_deref(),
+            )
+            .await?;
+        info!("Created new completion message with ID: {:?}", sent_id);
+        Some((sent_id, target_channel))
+    }
+
+    /// Credits a result someone shared as text to the game being tracked for them
+    ///
+    /// If the Wordle app already reported the game finished, only the guess count is
+    /// added to its saved completion.
+    async fn credit_share(&self, ctx: &Context, msg: &Message, share: WordleShare) {
+        let Some(guild_id) = msg.guild_id else {
+            return;
+        };
+        let config = match self
+            .tracked_channel_config(ctx, msg.guild_id, msg.channel_id)
+            .await
+        {
+            Ok(config) => config,
+            Err(why) => {
+                info!("Ignoring shared result: {}", why);
+                return;
+            }
+        };
+
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
+
+        // The most recently played of their games from today
+        let Some((_, game_state)) = puzzle_map
+            .iter_mut()
+            .filter(|(key, game)| {
+                is_players_game(key, Some(guild_id), msg.author.id)
+                    && game.is_current_at(Utc::now(), self.midnight_grace)
+            })
+            .max_by_key(|(_, game)| game.created_at)
+        else {
+            info!(
+                "No game being tracked for {}, ignoring shared result",
+                msg.author.name
+            );
+            return;
+        };
+        info!(
+            "{} shared Wordle {} in {:?} guesses",
+            game_state.player_name, share.puzzle_number, share.guesses
+        );
+        game_state.guess_count = share.guesses;
+        game_state.failed = !share.solved();
+
+        if game_state.completed {
+            self.record_completion(ctx, guild_id, msg.author.id, game_state)
+                .await;
+        } else {
+            let trigger = CompletionTrigger {
+                guild_id,
+                channel_id: msg.channel_id,
+                msg_id: msg.id,
+                annotated: None,
+            };
+            self.complete_game(ctx, &config, &trigger, msg.author.id, game_state)
+                .await;
+        }
+    }
+
+    /// Updates an existing completion message with a new embed, returning `false` if the
+    /// message or its channel no longer exists
+    async fn update_completion_message(
+        &self,
+        ctx: &Context,
+        channel_id: serenity::model::id::ChannelId,
+        msg_id: serenity::model::id::MessageId,
+        embed_msg: CreateEmbed,
+    ) -> bool {
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would update completion message {} in channel {}: {:?}",
+                msg_id, channel_id, embed_msg
+            );
+            return true;
+        }
+
+        // Webhook messages can only be edited through the webhook that sent them
+        let result = match &self.webhook {
+            Some(webhook) => {
+                let mut edit = EditWebhookMessage::new().embed(embed_msg);
+                if webhook.channel_id != Some(channel_id) {
+                    edit = edit.in_thread(channel_id);
+                }
+                webhook
+                    .edit_message(&ctx.http, msg_id, edit)
+                    .await
+                    .map(|_| ())
+            }
+            None => match channel_id.message(&ctx.http, msg_id).await {
+                Ok(mut message) => {
+                    message
+                        .edit(&ctx.http, EditMessage::new().embed(embed_msg))
+                        .await
+                }
+                Err(why) => Err(why),
+            },
+        };
+        match result {
+            Ok(()) => true,
+            Err(why) if is_not_found(&why) => {
+                info!(
+                    "Completion message {} in channel {} was deleted",
+                    msg_id, channel_id
+                );
+                false
+            }
+            Err(why) => {
+                error!("Error updating completion message: {:?}", why);
+                true
+            }
+        }
+    }
+
+    /// Whether a message was posted in the debug channel, looking up the channel's name
+    /// in the cache only when there is one
+    fn is_in_debug_channel(&self, ctx: &Context, msg: &Message) -> bool {
+        let Some(debug_channel_name) = self.debug_channel_name.as_deref() else {
+            return false;
+        };
+        // Checked for every message, so only the cache is asked, never the API
+        let channel_name = msg
+            .guild_id
+            .and_then(|guild_id| ctx.cache.guild(guild_id))
+            .and_then(|guild| Some(guild.channels.get(&msg.channel_id)?.name.clone()));
+        match channel_name {
+            Some(channel_name) => is_debug_channel(Some(debug_channel_name), &channel_name),
+            None => {
+                debug!(
+                    "Channel {} isn't cached, not a debug channel",
+                    msg.channel_id
+                );
+                false
+            }
+        }
+    }
+
+    /// Replies to each image in a debug channel message with the detection behind it:
+    /// the completion markers found and how confidently the author and anyone mentioned
+    /// were found, with the screenshot boxed
+    ///
+    /// Nothing is tracked or recorded, whoever posted the image.
+    async fn reply_with_detection(&self, ctx: &Context, msg: &Message) {
+        let screenshots = image_attachments(&msg.attachments);
+        if screenshots.is_empty() {
+            return;
+        }
+
+        let mut users = vec![&msg.author];
+        for user in &msg.mentions {
+            if !users.iter().any(|seen| seen.id == user.id) {
+                users.push(user);
+            }
+        }
+        let (mut avatar_urls, mut names, mut without_avatars) =
+            (Vec::new(), Vec::new(), Vec::new());
+        for user in users {
+            match user.static_avatar_url() {
+                Some(url) => {
+                    avatar_urls.push(url);
+                    names.push(user.name.as_str());
+                }
+                None => without_avatars.push(user.name.as_str()),
+            }
+        }
+        let avatar_urls: Vec<&str> = avatar_urls.iter().map(String::as_str).collect();
+
+        for screenshot in screenshots {
+            let mut reply = CreateMessage::new().reference_message(msg);
+            match simulate_detection(
+                &self.downloads,
+                &self.marker_templates,
+                &screenshot.url,
+                &avatar_urls,
+            )
+            .await
+            {
+                Ok((trace, annotated)) => {
+                    info!("Debug detection on {}: {:?}", screenshot.url, trace);
+                    let mut summary = detection_summary(&trace, &names);
+                    for name in &without_avatars {
+                        summary.push_str(&format!("\n{name} has no avatar to look for."));
+                    }
+                    reply = reply
+                        .content(summary)
+                        .add_file(CreateAttachment::bytes(annotated, ANNOTATED_FILE_NAME));
+                }
+                Err(why) => {
+                    error!(
+                        "Error running debug detection on {}: {}",
+                        screenshot.url, why
+                    );
+                    reply = reply.content(format!(
+                        "Unable to run detection on {}: {}",
+                        screenshot.filename, why
+                    ));
+                }
+            }
+            if let Err(why) = msg.channel_id.send_message(&ctx.http, reply).await {
+                error!("Error replying with debug detection: {:?}", why);
+            }
+        }
+    }
+}
+
+/// Whether a Discord request failed because the target no longer exists
+fn is_not_found(error: &serenity::Error) -> bool {
+    matches!(
+        error,
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response))
+            if is_unknown_target(response.status_code.as_u16(), response.error.code)
+    )
 }
 
-impl GameState {
-    /// Creates a new GameState instance
-    fn new() -> Self {
-        Self {
-            last_start_time: Instant::now(),
-            total_active_time: std::time::Duration::ZERO,
-            completion_msg_id: None,
-            created_at: Utc::now(),
-            completed: false,
+#[async_trait]
+impl EventHandler for Handler {
+    // Fired when the bot successfully connects to Discord
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+        self.connected.store(true, Ordering::Relaxed);
+
+        // Without message content the Wordle app's messages arrive empty, so games are
+        // never started or timed, and nothing else would say why
+        let message_content = ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+            | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED;
+        if !ready.application.flags.intersects(message_content) {
+            warn!(
+                "The Message Content intent isn't enabled for this bot, so no games will be \
+                 timed. Enable it under Bot > Privileged Gateway Intents in the Discord \
+                 Developer Portal"
+            );
+        }
+
+        // Without presences no activity arrives, so games are only timed between messages
+        let presence =
+            ApplicationFlags::GATEWAY_PRESENCE | ApplicationFlags::GATEWAY_PRESENCE_LIMITED;
+        if self.activity_names.is_some() && !ready.application.flags.intersects(presence) {
+            warn!(
+                "The Presence intent isn't enabled for this bot, so players' Wordle activity \
+                 won't time their games. Enable it under Bot > Privileged Gateway Intents in \
+                 the Discord Developer Portal"
+            );
+        }
+
+        if let Err(why) = Command::set_global_commands(&ctx.http, commands::register()).await {
+            error!("Error registering slash commands: {:?}", why);
         }
-    }
 
-    /// Checks if this game is from the current day in Sydney timezone
-    fn is_current(&self) -> bool {
-        let now_sydney = Utc::now().with_timezone(&Sydney);
-        let created_sydney = self.created_at.with_timezone(&Sydney);
-        created_sydney.date_naive() == now_sydney.date_naive()
+        if let Some(lookback) = self.catch_up
+            && !self.caught_up.swap(true, Ordering::Relaxed)
+        {
+            for guild in &ready.guilds {
+                self.catch_up(&ctx, guild.id, lookback).await;
+            }
+        }
     }
-}
 
-// Struct to store active games
-struct WordlePuzzles;
+    // Fired when the gateway connection is resumed after a drop
+    async fn resume(&self, _: Context, _: ResumedEvent) {
+        self.connected.store(true, Ordering::Relaxed);
+    }
 
-impl TypeMapKey for WordlePuzzles {
-    type Value = tokio::sync::Mutex<HashMap<(serenity::model::id::MessageId, String), GameState>>;
-}
+    // Fired when the shard connects, disconnects or reconnects
+    async fn shard_stage_update(&self, _: Context, event: ShardStageUpdateEvent) {
+        debug!("Shard stage changed from {} to {}", event.old, event.new);
+        self.connected
+            .store(event.new == ConnectionStage::Connected, Ordering::Relaxed);
+    }
 
-struct Handler {
-    daily_puzzles_channel_name: String,
-}
+    // Fired when a slash command is used or a button is pressed
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::Command(command) => {
+                commands::run(
+                    &ctx,
+                    &command,
+                    &self.history,
+                    &self.defaults,
+                    &self.downloads,
+                    &self.marker_templates,
+                    &self.overrides,
+                )
+                .await;
+            }
+            Interaction::Component(component) => {
+                commands::run_component(&ctx, &component, &self.history, &self.defaults).await;
+            }
+            _ => {}
+        }
+    }
 
-impl Handler {
-    /// Creates an embed for a Wordle completion message
-    fn create_completion_embed(
-        user_name: &str,
-        total_time: std::time::Duration,
-        is_update: bool,
-    ) -> CreateEmbed {
-        let description = if is_update {
-            format!(
-                "{} finished their Wordle in **{}**! (Updated)",
-                user_name,
-                format_duration(total_time)
-            )
-        } else {
-            format!(
-                "{} finished their Wordle in **{}**!",
-                user_name,
-                format_duration(total_time)
-            )
+    // Fired when someone's activity changes, timing their game while they're in Wordle
+    async fn presence_update(&self, ctx: Context, presence: Presence) {
+        let Some(activity_names) = &self.activity_names else {
+            return;
         };
+        let playing = presence
+            .activities
+            .iter()
+            .any(|activity| is_wordle_activity(activity_names, &activity.name));
 
-        CreateEmbed::new()
-            .title(EMBED_TITLE)
-            .description(description)
-            .colour(Colour::from_rgb(
-                EMBED_COLOR.0,
-                EMBED_COLOR.1,
-                EMBED_COLOR.2,
-            ))
-            .footer(CreateEmbedFooter::new(EMBED_FOOTER))
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
+
+        let now = Instant::now();
+        for (key, game_state) in puzzle_map.iter_mut() {
+            if !is_players_game(key, presence.guild_id, presence.user.id)
+                || !game_state.is_current_at(Utc::now(), self.midnight_grace)
+            {
+                continue;
+            }
+            if playing {
+                if game_state.resume(now) {
+                    info!(
+                        "Resumed game for {} from their activity",
+                        game_state.player_name
+                    );
+                }
+            } else if game_state.active_since.is_some() {
+                game_state.pause(now);
+                info!(
+                    "Paused game for {} (total time: {:?})",
+                    game_state.player_name, game_state.total_active_time
+                );
+            }
+        }
     }
 
-    /// Validates if a message is from the Wordle app and in the correct channel
-    async fn validate_message(
-        &self,
-        ctx: &Context,
-        channel_id: serenity::model::id::ChannelId,
-        author_id: serenity::model::id::UserId,
-    ) -> Result<(), &'static str> {
-        // Check if message is from Wordle app
-        if author_id != serenity::model::id::UserId::new(WORDLE_APP_ID) {
-            return Err("Not from Wordle app");
+    // Fired when someone reacts to a message, letting players confirm they finished
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        let Some(completion_reaction) = &self.completion_reaction else {
+            return;
+        };
+        if !is_completion_reaction(completion_reaction, &reaction.emoji) {
+            return;
         }
+        let (Some(guild_id), Some(author_id), Some(member)) = (
+            reaction.guild_id,
+            reaction.message_author_id,
+            reaction.member.as_ref(),
+        ) else {
+            return;
+        };
 
-        // Check channel name
-        let channel_name = channel_id
-            .name(&ctx.http)
+        // Only reactions to the Wordle app's messages in the tracked channel count
+        let config = match self
+            .validate_message(&ctx, Some(guild_id), reaction.channel_id, author_id)
             .await
-            .map_err(|_| "Unable to get channel information")?;
+        {
+            Ok(config) => config,
+            Err(why) => {
+                debug!("Ignoring completion reaction: {}", why);
+                return;
+            }
+        };
 
-        if channel_name.to_lowercase() != self.daily_puzzles_channel_name.to_lowercase() {
-            return Err("Not in daily puzzles channel");
-        }
+        let moderator = ctx
+            .cache
+            .guild(guild_id)
+            .is_some_and(|guild| guild.member_permissions(member).manage_messages());
 
-        Ok(())
-    }
-}
+        let data_read = ctx.data.read().await;
+        let mut puzzle_map = data_read
+            .get::<WordlePuzzles>()
+            .expect("Expected WordlePuzzles in TypeMap")
+            .lock()
+            .await;
 
-#[async_trait]
-impl EventHandler for Handler {
-    // Fired when the bot successfully connects to Discord
-    async fn ready(&self, _: Context, ready: Ready) {
-        info!("{} is connected!", ready.user.name);
+        let keys: Vec<_> = puzzle_map
+            .iter()
+            .filter(|(key, game)| {
+                !game.completed
+                    && completed_by_reaction(
+                        key,
+                        guild_id,
+                        reaction.message_id,
+                        member.user.id,
+                        moderator,
+                    )
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        if keys.is_empty() {
+            info!(
+                "No unfinished game for {} on message {}, ignoring reaction",
+                member.user.name, reaction.message_id
+            );
+            return;
+        }
+
+        let trigger = CompletionTrigger {
+            guild_id,
+            channel_id: reaction.channel_id,
+            msg_id: reaction.message_id,
+            annotated: None,
+        };
+        for key in keys {
+            let (_, _, user_id) = key;
+            if let Some(game_state) = puzzle_map.get_mut(&key) {
+                info!(
+                    "{} marked {}'s game finished with a reaction",
+                    member.user.name, game_state.player_name
+                );
+                self.complete_game(&ctx, &config, &trigger, user_id, game_state)
+                    .await;
+            }
+        }
     }
 
     // Fired when a new message is created
     async fn message(&self, ctx: Context, msg: Message) {
-        // Validate message is from Wordle app and in correct channel
-        if let Err(why) = self
-            .validate_message(&ctx, msg.channel_id, msg.author.id)
-            .await
+        if is_ignored_author(
+            msg.author.id.get(),
+            msg.author.bot,
+            ctx.cache.current_user().id.get(),
+            &[WORDLE_APP_ID],
+        ) {
+            debug!("Ignoring message {} from {}", msg.id, msg.author.name);
+            return;
+        }
+
+        // The debug channel is only for trying out detection, so nothing in it is tracked
+        if self.is_in_debug_channel(&ctx, &msg) {
+            self.reply_with_detection(&ctx, &msg).await;
+            return;
+        }
+
+        // Players can share their result as text, which finishes their game too
+        if msg.author.id != serenity::model::id::UserId::new(WORDLE_APP_ID)
+            && let Some(share) = parse_share(&msg.content)
         {
-            info!("Message validation failed: {}", why);
+            self.credit_share(&ctx, &msg, share).await;
             return;
         }
 
+        // Validate message is from Wordle app and in correct channel
+        let config = match self
+            .validate_message(&ctx, msg.guild_id, msg.channel_id, msg.author.id)
+            .await
+        {
+            Ok(config) => config,
+            Err(why) => {
+                info!("Message validation failed: {}", why);
+                return;
+            }
+        };
+
         let content = msg.content.to_lowercase();
         debug!("{}", content);
 
-        let Some(_guild_id) = msg.guild_id else {
+        let Some(guild_id) = msg.guild_id else {
             info!("Missing guild id");
             return;
         };
@@ -151,50 +1341,62 @@ impl EventHandler for Handler {
             .iter()
             .any(|&trigger| content.contains(trigger))
         {
-            let data_read = ctx.data.read().await;
-            let puzzle_lock = data_read
-                .get::<WordlePuzzles>()
-                .expect("Expected WordlePuzzles in TypeMap")
-                .lock();
-
-            // Parse all usernames from the message
+            // Parse all usernames from the message, leaving out anyone who opted out
             let usernames = parse_usernames(&content);
+            let players = self
+                .resolve_players(&ctx, guild_id, msg.id, usernames)
+                .await;
 
             // Create a timer entry for each user
-            let mut puzzle_map = puzzle_lock.await;
-            for username in &usernames {
-                let mut entry = puzzle_map.entry((msg.id, username.clone()));
+            let data_read = ctx.data.read().await;
+            let mut puzzle_map = data_read
+                .get::<WordlePuzzles>()
+                .expect("Expected WordlePuzzles in TypeMap")
+                .lock()
+                .await;
+            let mut finished_yesterday = Vec::new();
+            for (user_id, username) in &players {
+                let mut entry = puzzle_map.entry((guild_id, msg.id, *user_id));
                 match entry {
                     std::collections::hash_map::Entry::Occupied(ref mut entry) => {
                         // Check if game is from a previous day
-                        let is_current = entry.get().is_current();
+                        let is_current = entry.get().is_current_at(Utc::now(), self.midnight_grace);
                         if !is_current {
-                            info!("Resetting game from previous day");
-                            // Reset game state for new day
-                            entry.insert(GameState::new());
+                            // Log the old game's time before it's replaced
+                            info!(
+                                "Resetting game from previous day (previous time: {:?})",
+                                entry.get().elapsed()
+                            );
+                            // Reset game state for new day, keeping a finished game to save
+                            let fresh = GameState::in_timezone(config.timezone);
+                            if let Some(previous) = roll_over_day(entry.get_mut(), fresh) {
+                                finished_yesterday.push((*user_id, previous));
+                            }
+                            entry.get_mut().player_name = username.clone();
                             info!("Previous day's game replaced for user: {}", username);
-                        } else {
-                            // This is a resume - update total active time and start new attempt
-                            let game_state = entry.get_mut();
-                            game_state.total_active_time +=
-                                Instant::now().duration_since(game_state.last_start_time);
-                            game_state.last_start_time = Instant::now();
+                        } else if entry.get_mut().resume(Instant::now()) {
                             info!("Resumed game for user: {}", username);
+                        } else {
+                            debug!("Game already in progress for user: {}", username);
                         }
                     }
                     std::collections::hash_map::Entry::Vacant(vacant) => {
                         // This is a new game
-                        vacant.insert(GameState::new());
+                        let mut game_state = GameState::in_timezone(config.timezone);
+                        game_state.player_name = username.clone();
+                        vacant.insert(game_state);
                         info!("Started new game for user: {}", username);
                     }
                 }
             }
             drop(puzzle_map);
+            self.archive_previous_day(&ctx, guild_id, finished_yesterday)
+                .await;
 
             info!(
                 "Tracking Wordle for message ID: {} with {} users",
                 msg.id,
-                usernames.len()
+                players.len()
             );
         }
     }
@@ -219,13 +1421,16 @@ impl EventHandler for Handler {
         };
 
         // Validate message is from Wordle app and in correct channel
-        if let Err(why) = self
-            .validate_message(&ctx, event.channel_id, author.id)
+        let config = match self
+            .validate_message(&ctx, event.guild_id, event.channel_id, author.id)
             .await
         {
-            info!("Message validation failed: {}", why);
-            return;
-        }
+            Ok(config) => config,
+            Err(why) => {
+                info!("Message validation failed: {}", why);
+                return;
+            }
+        };
 
         // Get content from event
         let Some(content) = event.content else {
@@ -233,7 +1438,7 @@ impl EventHandler for Handler {
             return;
         };
 
-        let Some(_guild_id) = event.guild_id else {
+        let Some(guild_id) = event.guild_id else {
             info!("Missing guild id");
             return;
         };
@@ -246,6 +1451,24 @@ impl EventHandler for Handler {
             .iter()
             .any(|&trigger| content.contains(trigger));
 
+        // The screenshot isn't always the only or the last attachment, so every image is
+        // checked and anything else is skipped
+        let screenshots = image_attachments(event.attachments.iter().flatten());
+        // An edit or redelivered event can show screenshots that were already processed.
+        // Every one is recorded, so none is checked again
+        let now = Instant::now();
+        let unseen = screenshots
+            .iter()
+            .filter(|screenshot| !self.recent_screenshots.is_repeat(&screenshot.url, now))
+            .count();
+        if !screenshots.is_empty() && unseen == 0 {
+            info!(
+                "Skipping message {}, its screenshots were processed recently",
+                event.id
+            );
+            return;
+        }
+
         // Get the shared data
         let data_read = ctx.data.read().await;
         let puzzle_lock = data_read
@@ -253,129 +1476,411 @@ impl EventHandler for Handler {
             .expect("Expected WordlePuzzles in TypeMap")
             .lock();
 
-        // Parse usernames from content
+        // Parse usernames from content, leaving out anyone who opted out
         let usernames = parse_usernames(&content);
+        let unnamed = usernames.is_empty();
+        let mut players = self
+            .resolve_players(&ctx, guild_id, event.id, usernames)
+            .await;
+        // Checked along with the players found by their avatars, on the same download
+        let mut detected_hard_mode = None;
 
-        if usernames.is_empty() {
-            let channel = match event.channel_id.to_channel(&ctx.http).await {
-                Ok(channel) => channel,
-                Err(why) => {
-                    error!("Error getting channel: {:?}", why);
-                    return;
-                }
-            };
+        // Players the Wordle app doesn't name, e.g. "and 3 others", can only be told apart
+        // by their avatars in the finished screenshot
+        if unnamed && is_finished {
+            let members = self.likely_players(&ctx, guild_id).await;
 
-            let guild = match channel.guild() {
-                Some(guild) => guild,
-                None => {
-                    info!("Channel is not in a guild");
-                    return;
-                }
-            };
+            // Games are tracked by the names the Wordle app shows, members' display names
+            let names: HashMap<usize, String> = members
+                .iter()
+                .map(|member| {
+                    let uid = member.user.id.get() as usize;
+                    (uid, member.display_name().to_lowercase())
+                })
+                .collect();
 
-            let members = match guild.members(ctx.cache) {
-                Ok(members) => members,
-                Err(why) => {
-                    error!("Error getting guild members: {:?}", why);
-                    return;
+            // Find whose avatars appear in the screenshot of the game, trying each image
+            // until one shows players
+            for screenshot in &screenshots {
+                let mut candidates: Vec<Player> = Vec::new();
+                for member in &members {
+                    candidates.extend(self.member_player(guild_id, member));
                 }
-            };
-
-            let players: Vec<Player> = Vec::new();
+                info!("Collected {} player avatars", candidates.len());
 
-            for member in members {
-                if let Some(image_url) = member.user.static_avatar_url() {}
+                match find_players_in_image(
+                    candidates,
+                    screenshot.url.clone(),
+                    &self.downloads,
+                    self.confidence_votes,
+                    &self.marker_templates,
+                    self.search_region,
+                    self.archive.as_ref(),
+                )
+                .await
+                {
+                    Ok(found) if found.players.is_empty() => {
+                        info!("Found no players in {}", screenshot.filename)
+                    }
+                    Ok(found) => {
+                        info!(
+                            "Found {} players in {}",
+                            found.players.len(),
+                            screenshot.filename
+                        );
+                        detected_hard_mode = Some(found.hard_mode);
+                        players = found
+                            .players
+                            .iter()
+                            .filter_map(|player| {
+                                let name = names.get(&player.uid())?.clone();
+                                let user_id = serenity::model::id::UserId::new(player.uid() as u64);
+                                Some((user_id, name))
+                            })
+                            .collect();
+                        break;
+                    }
+                    Err(why) => error!(
+                        "Error finding players in {}: {:?}",
+                        screenshot.filename, why
+                    ),
+                }
             }
         }
 
         info!(
             "Message update - Found {} users: {:?}",
-            usernames.len(),
-            usernames
+            players.len(),
+            players
         );
 
+        // The hard-mode marker can only be attributed when the screenshot is one player's
+        let mut hard_mode = detected_hard_mode.unwrap_or(false);
+        if detected_hard_mode.is_none() && is_finished && players.len() == 1 {
+            for screenshot in &screenshots {
+                match detect_hard_mode(&self.downloads, &screenshot.url).await {
+                    Ok(found) => hard_mode = found,
+                    Err(why) => error!(
+                        "Error checking {} for hard mode: {:?}",
+                        screenshot.filename, why
+                    ),
+                }
+                if hard_mode {
+                    break;
+                }
+            }
+        }
+
+        // Prepared before the games are locked, as it downloads every finisher's avatar
+        let annotated = match screenshots.first() {
+            Some(screenshot) if is_finished && self.annotate_completions => {
+                let user_ids: Vec<_> = players.iter().map(|(user_id, _)| *user_id).collect();
+                self.annotate_completion(&ctx, guild_id, &screenshot.url, &user_ids)
+                    .await
+            }
+            _ => None,
+        };
+
         let mut puzzle_map = puzzle_lock.await;
 
         if is_playing {
             info!("Processing game start/resume from message edit");
             // Handle game start/resume
-            for username in &usernames {
-                let mut entry = puzzle_map.entry((event.id, username.clone()));
+            let mut finished_yesterday = Vec::new();
+            for (user_id, username) in &players {
+                let mut entry = puzzle_map.entry((guild_id, event.id, *user_id));
                 match entry {
                     std::collections::hash_map::Entry::Occupied(ref mut entry) => {
                         // Check if game is from a previous day
-                        let is_current = entry.get().is_current();
+                        let is_current = entry.get().is_current_at(Utc::now(), self.midnight_grace);
                         if !is_current {
-                            info!("Resetting game from previous day for {}", username);
-                            // Reset game state for new day
-                            entry.insert(GameState::new());
-                        } else {
-                            // This is a resume - update total active time and start new attempt
-                            let game_state = entry.get_mut();
-                            game_state.total_active_time +=
-                                Instant::now().duration_since(game_state.last_start_time);
-                            game_state.last_start_time = Instant::now();
+                            // Log the old game's time before it's replaced
                             info!(
-                                "Resumed game for {} (total time: {:?})",
-                                username, game_state.total_active_time
+                                "Resetting game from previous day for {} (previous time: {:?})",
+                                username,
+                                entry.get().elapsed()
                             );
+                            // Reset game state for new day, keeping a finished game to save
+                            let fresh = GameState::in_timezone(config.timezone);
+                            if let Some(previous) = roll_over_day(entry.get_mut(), fresh) {
+                                finished_yesterday.push((*user_id, previous));
+                            }
+                            entry.get_mut().player_name = username.clone();
+                        } else {
+                            let game_state = entry.get_mut();
+                            if game_state.resume(Instant::now()) {
+                                info!(
+                                    "Resumed game for {} (total time: {:?})",
+                                    username, game_state.total_active_time
+                                );
+                            } else {
+                                debug!("Game already in progress for {}", username);
+                            }
                         }
                     }
                     std::collections::hash_map::Entry::Vacant(vacant) => {
                         // This is a new game
-                        vacant.insert(GameState::new());
+                        let mut game_state = GameState::in_timezone(config.timezone);
+                        game_state.player_name = username.clone();
+                        vacant.insert(game_state);
                         info!("Started new game for {}", username);
                     }
                 }
             }
+            drop(puzzle_map);
+            self.archive_previous_day(&ctx, guild_id, finished_yesterday)
+                .await;
         } else if is_finished {
             info!("Processing game completion from message edit");
+            let trigger = CompletionTrigger {
+                guild_id,
+                channel_id: event.channel_id,
+                msg_id: event.id,
+                annotated,
+            };
             // Handle game completion
-            for user_name in &usernames {
-                if let Some(game_state) = puzzle_map.get_mut(&(event.id, user_name.clone())) {
-                    // Add the time from the current attempt
-                    let current_attempt_time =
-                        Instant::now().duration_since(game_state.last_start_time);
-                    let total_time = game_state.total_active_time + current_attempt_time;
+            let is_player =
+                |user_id: &serenity::model::id::UserId| players.iter().any(|(id, _)| id == user_id);
+            let mut games: Vec<(serenity::model::id::UserId, &mut GameState)> = puzzle_map
+                .iter_mut()
+                .filter(|((guild, msg, user_id), _)| {
+                    *guild == guild_id && *msg == event.id && is_player(user_id)
+                })
+                .map(|((_, _, user_id), game_state)| (*user_id, game_state))
+                .collect();
+            for (user_id, user_name) in &players {
+                if !games.iter().any(|(player, _)| player == user_id) {
+                    info!("No game state found for user {}", user_name);
+                }
+            }
+            if hard_mode {
+                for (_, game_state) in games.iter_mut() {
+                    info!("{} played in hard mode", game_state.player_name);
+                    game_state.hard_mode = true;
+                }
+            }
 
-                    info!(
-                        "User {} completed game - Current attempt: {:?}, Total time: {:?}",
-                        user_name, current_attempt_time, total_time
-                    );
+            // Later re-detections of a single player keep updating their own message
+            let shared_message = games.iter().all(|(_, game_state)| {
+                game_state.completion_msg_id == games[0].1.completion_msg_id
+            });
+            if self.combine_completions && games.len() > 1 && shared_message {
+                self.complete_games_together(&ctx, &config, &trigger, games)
+                    .await;
+            } else {
+                for (user_id, game_state) in games {
+                    self.complete_game(&ctx, &config, &trigger, user_id, game_state)
+                        .await;
+                }
+            }
+        }
+    }
+}
 
-                    // Send or update completion message
-                    if let Some(msg_id) = game_state.completion_msg_id {
-                        info!("Updating existing completion message");
-                        // Update existing completion message
-                        let embed_msg = Self::create_completion_embed(user_name, total_time, true);
-                        if let Ok(mut message) = event.channel_id.message(&ctx.http, msg_id).await {
-                            if let Err(why) = message
-                                .edit(&ctx.http, EditMessage::new().embed(embed_msg))
-                                .await
-                            {
-                                error!("Error updating completion message: {:?}", why);
-                            }
-                        }
-                    } else {
-                        info!("Sending new completion message");
-                        // Send new completion message
-                        let embed_msg = Self::create_completion_embed(user_name, total_time, false);
-                        if let Ok(sent_msg) = event
-                            .channel_id
-                            .send_message(&ctx.http, CreateMessage::new().embed(embed_msg))
-                            .await
-                        {
-                            game_state.completion_msg_id = Some(sent_msg.id);
-                            info!("Created new completion message with ID: {:?}", sent_msg.id);
-                        }
-                    }
+/// What the reminder task needs to DM players who haven't finished today's Wordle
+struct Reminders {
+    http: Arc<Http>,
+    data: Arc<RwLock<TypeMap>>,
+    history: History,
+    defaults: GuildConfig,
+    midnight_grace: std::time::Duration,
+    at: NaiveTime, // In each guild's timezone
+}
 
-                    // Update the game state with final time
-                    game_state.total_active_time = total_time;
-                } else {
-                    info!("No game state found for user {}", user_name);
+impl Reminders {
+    /// Reminds the players of every guild whose reminder time has come, recording the
+    /// day in `last_sent` so each guild is reminded once a day
+    async fn send_due(&self, last_sent: &mut HashMap<serenity::model::id::GuildId, NaiveDate>) {
+        let reminded = match self.history.reminded_users().await {
+            Ok(reminded) => reminded,
+            Err(why) => {
+                error!("Error loading reminders: {:?}", why);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        for (guild_id, user_ids) in reminded {
+            let guild_id = serenity::model::id::GuildId::new(guild_id);
+            let config = match self
+                .history
+                .guild_config(guild_id.get(), &self.defaults)
+                .await
+            {
+                Ok(config) => config,
+                Err(why) => {
+                    error!("Error loading config for guild {}: {:?}", guild_id, why);
+                    self.defaults.clone()
+                }
+            };
+            if !is_reminder_due(
+                now,
+                config.timezone,
+                self.at,
+                last_sent.get(&guild_id).copied(),
+            ) {
+                continue;
+            }
+            last_sent.insert(guild_id, now.with_timezone(&config.timezone).date_naive());
+
+            let opted_in: Vec<u64> = user_ids.into_iter().collect();
+            let to_remind = {
+                let data_read = self.data.read().await;
+                let puzzle_map = data_read
+                    .get::<WordlePuzzles>()
+                    .expect("Expected WordlePuzzles in TypeMap")
+                    .lock()
+                    .await;
+                players_to_remind(&puzzle_map, guild_id, &opted_in, now, self.midnight_grace)
+            };
+            for user_id in to_remind {
+                self.remind(serenity::model::id::UserId::new(user_id)).await;
+            }
+        }
+    }
+
+    /// DMs a player to play today's Wordle, skipping them if their DMs are closed
+    async fn remind(&self, user_id: serenity::model::id::UserId) {
+        let message = CreateMessage::new()
+            .content("🧩 You haven't finished today's Wordle yet, there's still time!");
+        let sent = match user_id.create_dm_channel(&self.http).await {
+            Ok(channel) => channel.send_message(&self.http, message).await.map(|_| ()),
+            Err(why) => Err(why),
+        };
+        match sent {
+            Ok(()) => info!("Reminded {} to play", user_id),
+            Err(why) => info!(
+                "Unable to DM {} a reminder, their DMs may be closed: {:?}",
+                user_id, why
+            ),
+        }
+    }
+}
+
+/// What the announcement task needs to post each day's fastest solvers
+struct FastestAnnouncements {
+    http: Arc<Http>,
+    history: History,
+    defaults: GuildConfig,
+    dry_run: bool,
+    at: NaiveTime, // In each guild's timezone
+}
+
+impl FastestAnnouncements {
+    /// Announces the fastest solvers in every guild whose announcement time has come,
+    /// recording the day in `last_sent` so each guild hears once a day
+    async fn announce_due(&self, last_sent: &mut HashMap<serenity::model::id::GuildId, NaiveDate>) {
+        let guilds = match self.http.get_guilds(None, None).await {
+            Ok(guilds) => guilds,
+            Err(why) => {
+                error!("Error listing guilds to announce to: {:?}", why);
+                return;
+            }
+        };
+
+        let now = Utc::now();
+        for guild in guilds {
+            let config = match self
+                .history
+                .guild_config(guild.id.get(), &self.defaults)
+                .await
+            {
+                Ok(config) => config,
+                Err(why) => {
+                    error!("Error loading config for guild {}: {:?}", guild.id, why);
+                    self.defaults.clone()
+                }
+            };
+            // Due the same way reminders are, once a day from the configured time
+            if !is_reminder_due(
+                now,
+                config.timezone,
+                self.at,
+                last_sent.get(&guild.id).copied(),
+            ) {
+                continue;
+            }
+            let date = now.with_timezone(&config.timezone).date_naive();
+            last_sent.insert(guild.id, date);
+
+            let leaderboard = match self
+                .history
+                .leaderboard_for_date(guild.id.get(), date)
+                .await
+            {
+                Ok(leaderboard) => leaderboard,
+                Err(why) => {
+                    error!(
+                        "Error loading {} leaderboard for guild {}: {:?}",
+                        date, guild.id, why
+                    );
+                    continue;
                 }
+            };
+            let fastest = fastest_solvers(&leaderboard);
+            if fastest.is_empty() {
+                info!(
+                    "Nobody in guild {} solved the {} Wordle, not announcing",
+                    guild.id, date
+                );
+                continue;
+            }
+            self.announce(guild.id, &config, &fastest, now).await;
+        }
+    }
+
+    /// Posts a guild's fastest solvers in its tracked channel
+    async fn announce(
+        &self,
+        guild_id: serenity::model::id::GuildId,
+        config: &GuildConfig,
+        fastest: &[(u64, std::time::Duration)],
+        now: chrono::DateTime<Utc>,
+    ) {
+        let (r, g, b) = config.embed.colour;
+        let embed = CreateEmbed::new()
+            .title("🏆 Fastest today")
+            .description(fastest_description(fastest))
+            .colour(Colour::from_rgb(r, g, b))
+            .footer(CreateEmbedFooter::new(render_footer(
+                &config.embed.footer,
+                now,
+                config.timezone,
+            )));
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would announce in guild {}: {:?}",
+                guild_id, embed
+            );
+            return;
+        }
+
+        let channels = match guild_id.channels(&self.http).await {
+            Ok(channels) => channels,
+            Err(why) => {
+                error!("Error listing channels of guild {}: {:?}", guild_id, why);
+                return;
             }
+        };
+        let Some(channel_id) = channels
+            .values()
+            .find(|channel| {
+                config
+                    .channel
+                    .matches(channel.id.get(), Some(&channel.name))
+            })
+            .map(|channel| channel.id)
+        else {
+            info!("Guild {} has no tracked channel to announce in", guild_id);
+            return;
+        };
+
+        match channel_id
+            .send_message(&self.http, CreateMessage::new().embed(embed))
+            .await
+        {
+            Ok(_) => info!("Announced the fastest solver(s) in guild {}", guild_id),
+            Err(why) => error!("Error announcing in {}: {:?}", channel_id, why),
         }
     }
 }
@@ -383,29 +1888,216 @@ impl EventHandler for Handler {
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file
+    let started_at = Instant::now();
     dotenv::dotenv().expect("Failed to load .env file");
     env_logger::init();
 
-    // Configure the Discord bot token and channel name from environment variables
-    let token = env::var("DISCORD_TOKEN").expect("Expected a DISCORD_TOKEN in the environment");
-    let daily_puzzles_channel_name =
-        env::var("DAILY_PUZZLES_CHANNEL_NAME").unwrap_or_else(|_| "daily-puzzles".to_string()); // Default to "daily-puzzles" if not set
+    // Settings come from the config file, with environment variables taking precedence
+    let config_path = env::var("WORDLE_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+    let config = Config::load(std::path::Path::new(&config_path)).unwrap_or_else(|why| {
+        error!("Invalid configuration: {}", why);
+        std::process::exit(1);
+    });
+    // Fail fast if detection assets are missing rather than silently never matching
+    let marker_templates: Vec<&str> = config.marker_templates.iter().map(String::as_str).collect();
+    if marker_templates.is_empty() {
+        error!("Template validation failed: no completion marker templates configured");
+        std::process::exit(1);
+    }
+    if let Err(why) = validate_templates(&marker_templates) {
+        error!("Template validation failed: {:?}", why);
+        std::process::exit(1);
+    }
+    let marker_templates = marker_templates
+        .into_iter()
+        .map(|path| MarkerTemplate::load(path, config.marker_threshold(path)))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|why| {
+            error!("Unable to load marker templates: {:?}", why);
+            std::process::exit(1);
+        });
+
+    let token = config
+        .token
+        .clone()
+        .expect("Expected a DISCORD_TOKEN in the environment or config file");
 
-    // Create a new instance of the Discord client
-    let mut client = Client::builder(
-        &token,
-        GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT,
+    if config.dry_run {
+        info!("[DRY RUN] Completion messages will be logged instead of posted");
+    }
+
+    let webhook = match &config.webhook.url {
+        Some(url) => match Webhook::from_url(Http::new(&token), url).await {
+            Ok(webhook) => Some(webhook),
+            Err(why) => {
+                error!("Unable to load completion webhook: {:?}", why);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let http_client = http_client(
+        config.proxy_url.as_deref(),
+        std::time::Duration::from_secs_f64(config.download_timeout_secs),
     )
-    .event_handler(Handler {
-        daily_puzzles_channel_name,
-    })
-    .await
-    .expect("Error creating client");
+    .unwrap_or_else(|why| {
+        error!("Unable to set up image downloads: {}", why);
+        std::process::exit(1);
+    });
+
+    let history = History::connect(&config.database_url())
+        .await
+        .expect("Failed to open history database");
+    let reminder_history = history.clone();
+    let announcement_history = history.clone();
+    let connected = Arc::new(AtomicBool::new(false));
+
+    // Reactions are only needed when they can finish games
+    let mut intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+    if config.completion_reaction.is_some() {
+        intents |= GatewayIntents::GUILD_MESSAGE_REACTIONS;
+    }
+    // Activity changes only arrive with presences, which Discord sends on request
+    if config.track_presence {
+        intents |= GatewayIntents::GUILD_PRESENCES;
+    }
+    // The debug channel is recognised from the cached channels, which this fills
+    if config.debug_channel_name.is_some() {
+        intents |= GatewayIntents::GUILDS;
+    }
+
+    // Create a new instance of the Discord client
+    let mut client = Client::builder(&token, intents)
+        .event_handler(Handler {
+            // Guilds can override these with /config
+            defaults: config.guild_defaults(),
+            dry_run: config.dry_run,
+            min_active_time: std::time::Duration::from_secs_f64(config.min_active_secs),
+            midnight_grace: std::time::Duration::from_secs_f64(config.midnight_grace_secs),
+            completion_reaction: config.completion_reaction.clone(),
+            post_cooldown: std::time::Duration::from_secs_f64(config.post_cooldown_secs),
+            connected: connected.clone(),
+            catch_up: config
+                .catch_up_hours
+                .map(|hours| std::time::Duration::from_secs_f64(hours * 60.0 * 60.0)),
+            caught_up: AtomicBool::new(false),
+            history,
+            events: CompletionEvents::new(),
+            // Keep the main channel clean by posting completions in a daily thread
+            use_threads: config.completion_threads,
+            combine_completions: config.combine_completions,
+            annotate_completions: config.annotate_completions,
+            downloads: DownloadLimiter::new(config.max_downloads)
+                .with_retry(config.retry_policy())
+                .with_client(http_client)
+                .with_avatar_size(config.avatar_size)
+                .with_dir(&config.data_dir),
+            confidence_votes: config.confidence_votes,
+            search_region: config.search_region(),
+            archive: config
+                .archive_screenshots
+                .then(|| Archive::new(&config.data_dir)),
+            overrides: AvatarOverrides::new(&config.data_dir),
+            webhook,
+            webhook_config: config.webhook.clone(),
+            marker_templates,
+            recent_screenshots: RecentScreenshots::default(),
+            debug_channel_name: config.debug_channel_name.clone(),
+            activity_names: config.track_presence.then(|| config.activity_names.clone()),
+        })
+        .await
+        .expect("Error creating client");
 
     // Initialize the shared data for storing active puzzles
     {
         let mut data = client.data.write().await;
         data.insert::<WordlePuzzles>(Mutex::new(HashMap::new()));
+        data.insert::<StartedAt>(started_at);
+        if let Some(at) = config.reminder_time {
+            data.insert::<ReminderTime>(at);
+        }
+    }
+
+    // Delete old downloads if a retention period was configured, keeping every template
+    if let Some(hours) = config.download_retention_hours {
+        let retention = std::time::Duration::from_secs_f64(hours * 60.0 * 60.0);
+        let protected: Vec<String> = [SOLVED_TEMPLATE, HARD_MODE_TEMPLATE]
+            .into_iter()
+            .map(String::from)
+            .chain(config.marker_templates.iter().cloned())
+            .collect();
+        let data_dir = config.data_dir.clone();
+        tokio::spawn(async move {
+            let protected: Vec<&str> = protected.iter().map(String::as_str).collect();
+            let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match clean_downloads(&data_dir, retention, &protected).await {
+                    Ok(0) => {}
+                    Ok(deleted) => info!("Deleted {} expired download(s)", deleted),
+                    Err(why) => warn!("Error cleaning up downloads: {}", why),
+                }
+            }
+        });
+    }
+
+    // Remind players who asked to at the configured time in their guild's timezone
+    if let Some(at) = config.reminder_time {
+        let reminders = Reminders {
+            http: client.http.clone(),
+            data: client.data.clone(),
+            history: reminder_history,
+            defaults: config.guild_defaults(),
+            midnight_grace: std::time::Duration::from_secs_f64(config.midnight_grace_secs),
+            at,
+        };
+        tokio::spawn(async move {
+            let mut last_sent = HashMap::new();
+            let mut interval = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                reminders.send_due(&mut last_sent).await;
+            }
+        });
+    }
+
+    // Announce each day's fastest solvers at the configured time in each guild's timezone
+    if let Some(at) = config.fastest_time {
+        let announcements = FastestAnnouncements {
+            http: client.http.clone(),
+            history: announcement_history,
+            defaults: config.guild_defaults(),
+            dry_run: config.dry_run,
+            at,
+        };
+        tokio::spawn(async move {
+            let mut last_sent = HashMap::new();
+            let mut interval = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                announcements.announce_due(&mut last_sent).await;
+            }
+        });
+    }
+
+    // Serve the health-check and status endpoints if a port was configured
+    if let Some(port) = config.http_port {
+        let state = ServerState {
+            connected,
+            data: client.data.clone(),
+        };
+        tokio::spawn(async move {
+            match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+                Ok(listener) => {
+                    info!("Serving health checks on port {}", port);
+                    if let Err(why) = server::serve(listener, state).await {
+                        error!("HTTP server error: {:?}", why);
+                    }
+                }
+                Err(why) => error!("Unable to bind HTTP port {}: {:?}", port, why),
+            }
+        });
     }
 
     // Start the client, blocking until it's disconnected
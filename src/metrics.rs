@@ -0,0 +1,101 @@
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
+use std::sync::OnceLock;
+
+/// Counters and timings exported on `/metrics` for Prometheus to scrape
+pub struct Metrics {
+    registry: Registry,
+    pub completions_detected: IntCounter, // Games reported finished, by the Wordle app or a share
+    pub verifications: IntCounter,        // Screenshots checked for players
+    pub detection_failures: IntCounter,   // Checks that failed with an error
+    pub download_failures: IntCounter,    // Download attempts that failed, retries included
+    pub resize_cache_hits: IntCounter,    // Needle resizes reused from an earlier search
+    pub resize_cache_misses: IntCounter,  // Needle resizes that had to be done
+    pub presence_rejections: IntCounter,  // Avatars ruled out by colour without a search
+    pub detection_latency: Histogram,     // Seconds taken to check a screenshot
+}
+
+impl Metrics {
+    /// A fresh set of metrics in their own registry, all at zero
+    pub fn new() -> Self {
+        let counter = |name: &str, help: &str| {
+            IntCounter::new(name, help).expect("Metric names and help are valid")
+        };
+        let metrics = Self {
+            registry: Registry::new(),
+            completions_detected: counter(
+                "wordle_completions_detected_total",
+                "Games reported finished",
+            ),
+            verifications: counter(
+                "wordle_verifications_total",
+                "Screenshots checked for players",
+            ),
+            detection_failures: counter(
+                "wordle_detection_failures_total",
+                "Screenshot checks that failed with an error",
+            ),
+            download_failures: counter(
+                "wordle_download_failures_total",
+                "Image download attempts that failed",
+            ),
+            resize_cache_hits: counter(
+                "wordle_resize_cache_hits_total",
+                "Needle resizes reused from an earlier search",
+            ),
+            resize_cache_misses: counter(
+                "wordle_resize_cache_misses_total",
+                "Needle resizes that had to be done",
+            ),
+            presence_rejections: counter(
+                "wordle_presence_rejections_total",
+                "Avatars ruled out by their colours without being searched for",
+            ),
+            detection_latency: Histogram::with_opts(
+                HistogramOpts::new(
+                    "wordle_detection_latency_seconds",
+                    "Time taken to check a screenshot for players",
+                )
+                .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            )
+            .expect("Metric names and help are valid"),
+        };
+
+        for collector in [
+            Box::new(metrics.completions_detected.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.verifications.clone()),
+            Box::new(metrics.detection_failures.clone()),
+            Box::new(metrics.download_failures.clone()),
+            Box::new(metrics.resize_cache_hits.clone()),
+            Box::new(metrics.resize_cache_misses.clone()),
+            Box::new(metrics.presence_rejections.clone()),
+            Box::new(metrics.detection_latency.clone()),
+        ] {
+            metrics
+                .registry
+                .register(collector)
+                .expect("Each metric is registered once");
+        }
+        metrics
+    }
+
+    /// Every metric in Prometheus' text exposition format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Writing to a Vec can't fail");
+        String::from_utf8(buffer).expect("The text format is UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The metrics the whole bot updates
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
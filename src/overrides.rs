@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::error::{Result, WordleError};
+use crate::read_image;
+
+/// Avatars set by moderators for players whose Discord avatar doesn't match them in
+/// screenshots, stored as `avatar_overrides/<guild>/<user>.png` under the data dir
+#[derive(Debug, Clone)]
+pub struct AvatarOverrides {
+    dir: PathBuf,
+}
+
+impl AvatarOverrides {
+    /// Overrides in the `avatar_overrides` directory of `data_dir`
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            dir: data_dir.join("avatar_overrides"),
+        }
+    }
+
+    fn path(&self, guild_id: u64, user_id: u64) -> PathBuf {
+        self.dir
+            .join(guild_id.to_string())
+            .join(format!("{user_id}.png"))
+    }
+
+    /// Where the user's override is kept, if they have one
+    pub fn get(&self, guild_id: u64, user_id: u64) -> Option<PathBuf> {
+        let path = self.path(guild_id, user_id);
+        path.is_file().then_some(path)
+    }
+
+    /// Stores `image` as the user's avatar, replacing any previous override
+    ///
+    /// Images that can't be decoded are rejected rather than left to fail every
+    /// later detection.
+    pub async fn save(&self, guild_id: u64, user_id: u64, image: &[u8]) -> Result<PathBuf> {
+        let path = self.path(guild_id, user_id);
+        let override_error = |path: &Path, source: std::io::Error| WordleError::AvatarOverride {
+            path: path.display().to_string(),
+            source,
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(|why| override_error(dir, why))?;
+        }
+        // Written beside the override first so a bad upload doesn't replace a good one
+        let upload_path = path.with_extension("upload");
+        fs::write(&upload_path, image)
+            .await
+            .map_err(|why| override_error(&upload_path, why))?;
+        if let Err(why) = read_image(&upload_path.display().to_string()) {
+            let _ = fs::remove_file(&upload_path).await;
+            return Err(why);
+        }
+        fs::rename(&upload_path, &path)
+            .await
+            .map_err(|why| override_error(&path, why))?;
+
+        Ok(path)
+    }
+}
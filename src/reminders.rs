@@ -0,0 +1,57 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+
+use crate::game::{GameKey, GameState, is_players_game};
+
+/// How often the reminder time is checked for
+pub const REMINDER_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How late reminders are still sent, so a restart long after the reminder time
+/// doesn't remind everyone then
+const REMINDER_WINDOW: chrono::Duration = chrono::Duration::hours(1);
+
+/// When players who asked are reminded to play, in each guild's timezone; only in
+/// the TypeMap when reminders are configured
+pub struct ReminderTime;
+
+impl TypeMapKey for ReminderTime {
+    type Value = NaiveTime;
+}
+
+/// Whether a guild's reminders should be sent at `now`: from `at` in its `timezone`,
+/// unless they were already sent on that day
+pub fn is_reminder_due(
+    now: DateTime<Utc>,
+    timezone: Tz,
+    at: NaiveTime,
+    last_sent: Option<NaiveDate>,
+) -> bool {
+    let local = now.with_timezone(&timezone).naive_local();
+    let due_at = local.date().and_time(at);
+    last_sent != Some(local.date()) && due_at <= local && local < due_at + REMINDER_WINDOW
+}
+
+/// The users in `guild_id` to remind to play, out of those `opted_in`: those without a
+/// finished game from today
+pub fn players_to_remind(
+    games: &HashMap<GameKey, GameState>,
+    guild_id: GuildId,
+    opted_in: &[u64],
+    now: DateTime<Utc>,
+    midnight_grace: std::time::Duration,
+) -> Vec<u64> {
+    opted_in
+        .iter()
+        .copied()
+        .filter(|user_id| {
+            !games.iter().any(|(key, game)| {
+                is_players_game(key, Some(guild_id), UserId::new(*user_id))
+                    && game.completed
+                    && game.is_current_at(now, midnight_grace)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Screenshots remembered before the least recently processed is forgotten
+pub const RECENT_SCREENSHOT_CAPACITY: usize = 256;
+
+/// How long a processed screenshot is skipped for
+pub const RECENT_SCREENSHOT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Remembers the screenshots processed recently, by attachment URL, so that an edit or
+/// redelivered event showing the same screenshot isn't downloaded and checked again
+///
+/// Bounded to a fixed number of URLs, forgetting the least recently processed first.
+pub struct RecentScreenshots {
+    capacity: usize,
+    window: Duration,
+    seen: Mutex<VecDeque<(String, Instant)>>, // Least recently processed first
+}
+
+impl Default for RecentScreenshots {
+    fn default() -> Self {
+        Self::new(RECENT_SCREENSHOT_CAPACITY, RECENT_SCREENSHOT_WINDOW)
+    }
+}
+
+impl RecentScreenshots {
+    pub fn new(capacity: usize, window: Duration) -> Self {
+        Self {
+            capacity,
+            window,
+            seen: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records `url` as processed at `now`, returning whether it already was within the
+    /// window, in which case it shouldn't be processed again
+    pub fn is_repeat(&self, url: &str, now: Instant) -> bool {
+        let mut seen = self
+            .seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(i) = seen.iter().position(|(seen_url, _)| seen_url == url) {
+            let (seen_url, processed_at) = seen.remove(i).expect("index was just found");
+            let is_repeat = now.saturating_duration_since(processed_at) < self.window;
+            // A repeat keeps its original time, so the window runs from the first time
+            seen.push_back((seen_url, if is_repeat { processed_at } else { now }));
+            return is_repeat;
+        }
+
+        while seen.len() >= self.capacity.max(1) {
+            seen.pop_front();
+        }
+        seen.push_back((url.to_string(), now));
+        false
+    }
+}
@@ -0,0 +1,88 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{StatusCode, header},
+    routing::get,
+};
+use serde::Serialize;
+use serenity::prelude::{RwLock, TypeMap};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use tokio::net::TcpListener;
+
+use crate::game::WordlePuzzles;
+use crate::metrics::metrics;
+
+/// State shared between the Discord client and the HTTP server
+#[derive(Clone)]
+pub struct ServerState {
+    pub connected: Arc<AtomicBool>, // Whether the Discord client is currently connected
+    pub data: Arc<RwLock<TypeMap>>, // The Discord client's shared data
+}
+
+/// A single tracked game as reported by `/status`
+#[derive(Debug, Serialize)]
+pub struct GameStatus {
+    pub guild_id: u64,
+    pub user_id: u64,
+    pub username: String,
+    pub elapsed_ms: u128,
+    pub completed: bool,
+}
+
+/// Builds the router exposing `/health`, `/status` and `/metrics`
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/status", get(status))
+        .route("/metrics", get(export_metrics))
+        .with_state(state)
+}
+
+/// Serves the health-check and status endpoints until the listener fails
+pub async fn serve(listener: TcpListener, state: ServerState) -> std::io::Result<()> {
+    axum::serve(listener, router(state)).await
+}
+
+/// 200 while the Discord client is connected, 503 otherwise
+async fn health(State(state): State<ServerState>) -> StatusCode {
+    if state.connected.load(Ordering::Relaxed) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Dumps every game tracked for the current day
+async fn status(State(state): State<ServerState>) -> Json<Vec<GameStatus>> {
+    let data_read = state.data.read().await;
+    let Some(puzzles) = data_read.get::<WordlePuzzles>() else {
+        return Json(Vec::new());
+    };
+
+    let games = puzzles
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, game_state)| game_state.is_current())
+        .map(|((guild_id, _, user_id), game_state)| GameStatus {
+            guild_id: guild_id.get(),
+            user_id: user_id.get(),
+            username: game_state.player_name.clone(),
+            elapsed_ms: game_state.elapsed().as_millis(),
+            completed: game_state.completed,
+        })
+        .collect();
+
+    Json(games)
+}
+
+/// The bot's metrics for Prometheus to scrape
+async fn export_metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics().render(),
+    )
+}
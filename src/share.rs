@@ -0,0 +1,90 @@
+use chrono::NaiveDate;
+
+/// A Wordle result shared as text, e.g. "Wordle 1,123 4/6*" followed by the emoji grid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordleShare {
+    pub puzzle_number: u32,
+    pub guesses: Option<u32>, // `None` when the puzzle wasn't solved (X/6)
+    pub hard_mode: bool,      // Marked with a trailing `*`
+    pub grid: Vec<String>,    // One row of squares per guess
+}
+
+impl WordleShare {
+    /// Whether the puzzle was solved
+    pub fn solved(&self) -> bool {
+        self.guesses.is_some()
+    }
+}
+
+/// Day of the first puzzle, number 0
+const FIRST_PUZZLE: NaiveDate = NaiveDate::from_ymd_opt(2021, 6, 19).unwrap();
+
+/// The number of the puzzle played on `date`, counted in the player's local time
+///
+/// The New York Times has swapped and removed answers since taking Wordle over, but
+/// the numbering has stayed one per day since launch, so no offset is needed. Dates
+/// before launch give 0.
+pub fn wordle_number_for_date(date: NaiveDate) -> u32 {
+    (date - FIRST_PUZZLE).num_days().max(0) as u32
+}
+
+/// Squares used in the grid, including the high contrast colours
+const GRID_SQUARES: [char; 6] = ['🟩', '🟨', '⬛', '⬜', '🟧', '🟦'];
+const MAX_GUESSES: u32 = 6;
+
+/// Parses a text share, or returns `None` if the message isn't one
+///
+/// The grid is optional, but if present must have one row per guess.
+pub fn parse_share(content: &str) -> Option<WordleShare> {
+    let mut lines = content.lines().map(str::trim);
+    let header = lines.find(|line| line.starts_with("Wordle "))?;
+
+    let mut parts = header.split_whitespace().skip(1);
+    let puzzle_number = parts
+        .next()?
+        .chars()
+        .filter(|c| !matches!(c, ',' | '.'))
+        .collect::<String>()
+        .parse()
+        .ok()?;
+
+    let score = parts.next()?;
+    let (score, hard_mode) = match score.strip_suffix('*') {
+        Some(score) => (score, true),
+        None => (score, false),
+    };
+    let (guesses, out_of) = score.split_once('/')?;
+    if out_of != MAX_GUESSES.to_string() {
+        return None;
+    }
+    let guesses = match guesses {
+        "X" | "x" => None,
+        guesses => match guesses.parse() {
+            Ok(guesses) if (1..=MAX_GUESSES).contains(&guesses) => Some(guesses),
+            _ => return None,
+        },
+    };
+
+    let grid: Vec<String> = lines
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| is_grid_row(line))
+        .map(str::to_string)
+        .collect();
+    if !grid.is_empty() && grid.len() as u32 != guesses.unwrap_or(MAX_GUESSES) {
+        return None;
+    }
+
+    Some(WordleShare {
+        puzzle_number,
+        guesses,
+        hard_mode,
+        grid,
+    })
+}
+
+/// Whether a line is a row of five grid squares
+fn is_grid_row(line: &str) -> bool {
+    // Some clients add variation selectors after the squares
+    let squares: Vec<char> = line.chars().filter(|c| *c != '\u{FE0F}').collect();
+    squares.len() == 5 && squares.iter().all(|c| GRID_SQUARES.contains(c))
+}
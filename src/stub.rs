@@ -0,0 +1,199 @@
+//! Stand-ins for the OpenCV detection, built without the `cv` feature so the bot's
+//! Discord and game logic can be worked on without OpenCV installed
+//!
+//! Nothing is downloaded or decoded. Every player is found in a screenshot, or none
+//! are, as set with [`set_stubbed_detection`].
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::archive::Archive;
+use crate::error::{Result, WordleError};
+use crate::{DEFAULT_MARKER_THRESHOLD, DownloadLimiter};
+
+/// Whether stubbed detection finds the players it's given
+static PLAYERS_FOUND: AtomicBool = AtomicBool::new(true);
+
+/// Sets whether stubbed detection finds every player it's given, or none of them
+pub fn set_stubbed_detection(found: bool) {
+    PLAYERS_FOUND.store(found, Ordering::Relaxed);
+}
+
+/// Stands in for a decoded image
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Image;
+
+/// A pixel position, as OpenCV's `Point`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A match's box and confidence, as `detection::MatchResult`
+pub type MatchResult = ((Point, Point), f64);
+
+/// Checks that an image file can be read, without decoding it
+pub fn read_image(path: &str) -> Result<Image> {
+    if !std::path::Path::new(path).is_file() {
+        return Err(WordleError::Decode {
+            path: path.to_string(),
+            reason: "not a readable file".to_string(),
+        });
+    }
+    Ok(Image)
+}
+
+/// Checks that every template image is there, failing with the first missing path
+pub fn validate_templates(paths: &[&str]) -> Result<()> {
+    for path in paths {
+        if !std::path::Path::new(path).is_file() {
+            return Err(WordleError::TemplateMissing {
+                path: path.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// A completion marker template and the confidence it must be matched with
+#[derive(Debug, Clone)]
+pub struct MarkerTemplate {
+    pub image: Image,
+    pub threshold: f64,
+}
+
+impl MarkerTemplate {
+    /// A template matched at [`DEFAULT_MARKER_THRESHOLD`]
+    pub fn new(image: Image) -> Self {
+        Self {
+            image,
+            threshold: DEFAULT_MARKER_THRESHOLD,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Checks the template at `path` is there, to be matched at `threshold`
+    pub fn load(path: &str, threshold: f64) -> Result<Self> {
+        Ok(Self::new(read_image(path)?).with_threshold(threshold))
+    }
+}
+
+#[derive(Debug)]
+pub struct Player {
+    uid: usize,
+}
+
+impl Player {
+    pub fn new(uid: usize, _profile_url: String) -> Player {
+        Player { uid }
+    }
+
+    pub fn with_override(uid: usize, _avatar_path: PathBuf) -> Player {
+        Player { uid }
+    }
+
+    pub fn with_avatar(self, _profile_url: String) -> Player {
+        self
+    }
+
+    /// The ID the player was created with
+    pub fn uid(&self) -> usize {
+        self.uid
+    }
+}
+
+/// What [`find_players_in_image`] made of a screenshot, as `vision::ScreenshotDetection`
+#[derive(Debug, Default)]
+pub struct ScreenshotDetection {
+    pub players: Vec<Player>,
+    pub hard_mode: bool, // Never, without detection
+}
+
+/// Every player, or none of them, as set with [`set_stubbed_detection`]
+pub async fn find_players_in_image(
+    players: Vec<Player>,
+    _haystack_url: String,
+    _downloads: &DownloadLimiter,
+    _votes: usize,
+    _solved_markers: &[MarkerTemplate],
+    _region: SearchRegion,
+    _archive: Option<&Archive>,
+) -> Result<ScreenshotDetection> {
+    let players = if PLAYERS_FOUND.load(Ordering::Relaxed) {
+        players
+    } else {
+        Vec::new()
+    };
+    Ok(ScreenshotDetection {
+        players,
+        hard_mode: false,
+    })
+}
+
+/// How a single avatar fared in detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvatarTrace {
+    pub found: Option<MatchResult>,
+    pub center: Option<Point>,
+    pub candidate_markers: Vec<usize>,
+    pub credited: bool,
+}
+
+/// Every decision made while working out who finished
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerificationTrace {
+    pub markers: Vec<MatchResult>,
+    pub avatars: Vec<AvatarTrace>,
+}
+
+impl VerificationTrace {
+    /// Indices of the avatars credited with a completion
+    pub fn credited(&self) -> Vec<usize> {
+        self.avatars
+            .iter()
+            .enumerate()
+            .filter(|(_, avatar)| avatar.credited)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Where avatars are looked for in a screenshot, as `vision::SearchRegion`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchRegion {
+    #[default]
+    Whole,
+    AroundMarkers {
+        reach: f64,
+    },
+}
+
+/// No game is hard mode without detection
+pub async fn detect_hard_mode(_downloads: &DownloadLimiter, _screenshot_url: &str) -> Result<bool> {
+    Ok(false)
+}
+
+/// Screenshots can't be traced without detection
+pub async fn simulate_detection(
+    _downloads: &DownloadLimiter,
+    _marker_templates: &[MarkerTemplate],
+    _screenshot_url: &str,
+    _avatar_urls: &[&str],
+) -> Result<(VerificationTrace, Vec<u8>)> {
+    Err(WordleError::DetectionUnavailable)
+}
+
+/// Screenshots can't be annotated without detection
+pub async fn annotate_screenshot(
+    _downloads: &DownloadLimiter,
+    _marker_templates: &[MarkerTemplate],
+    _screenshot_url: &str,
+    _players: &[Player],
+) -> Result<Vec<u8>> {
+    Err(WordleError::DetectionUnavailable)
+}
@@ -0,0 +1,1001 @@
+//! Completion detection on screenshots with OpenCV, built with the `cv` feature
+
+use chrono::Utc;
+use log::{debug, info};
+use opencv::core::Point;
+use opencv::prelude::*;
+use opencv::{core, imgcodecs, imgproc, videoio};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::archive::{Archive, PlayerOutcome};
+use crate::detection::{self, DetectionParams, MatchResult};
+use crate::error::{Result, WordleError};
+use crate::{
+    DEFAULT_MARKER_THRESHOLD, DownloadLimiter, HARD_MODE_TEMPLATE, download_image,
+    is_animated_avatar_url, metrics,
+};
+
+/// Read an image in RGB order, treating an unreadable file as a decode error
+///
+/// `imread` returns an empty image rather than an error when it can't decode a file.
+pub fn read_image(path: &str) -> Result<Mat> {
+    let image = imgcodecs::imread(path, imgcodecs::IMREAD_COLOR_RGB)?;
+    if image.empty() {
+        return Err(WordleError::Decode {
+            path: path.to_string(),
+            reason: "not a supported image".to_string(),
+        });
+    }
+
+    Ok(image)
+}
+
+/// Read the first frame of a possibly animated image (GIF, APNG), in BGR order
+///
+/// `imread` handles animated images inconsistently between OpenCV builds, so frames are
+/// decoded explicitly, falling back to the video decoders if the image codecs can't.
+pub fn load_first_frame(path: &str) -> Result<Mat> {
+    let mut frames = core::Vector::<Mat>::new();
+    if imgcodecs::imreadmulti_range(path, &mut frames, 0, 1, imgcodecs::IMREAD_COLOR)
+        .unwrap_or(false)
+        && let Some(frame) = frames.iter().find(|frame| !frame.empty())
+    {
+        return Ok(frame);
+    }
+
+    let mut capture = videoio::VideoCapture::from_file(path, videoio::CAP_ANY)?;
+    let mut frame = Mat::default();
+    if !capture.read(&mut frame)? || frame.empty() {
+        return Err(WordleError::Decode {
+            path: path.to_string(),
+            reason: "no frames could be read".to_string(),
+        });
+    }
+    if frame.channels() == 4 {
+        let mut bgr = Mat::default();
+        imgproc::cvt_color_def(&frame, &mut bgr, imgproc::COLOR_BGRA2BGR)?;
+        frame = bgr;
+    }
+
+    Ok(frame)
+}
+
+/// Downloads an avatar, saving animated ones as a static PNG of their first frame
+///
+/// If the limiter has an avatar size, the avatar is also resized to it and saved as a
+/// PNG, so every avatar reaches detection at the same scale.
+async fn download_avatar(downloads: &DownloadLimiter, url: &str) -> Result<String> {
+    let mut file_path = download_image(downloads, url).await?;
+    if is_animated_avatar_url(url) {
+        let frame = load_first_frame(&file_path)?;
+        file_path = save_png(url, &format!("{file_path}.png"), &frame)?;
+        info!("Saved first frame of animated avatar to {file_path}");
+    }
+
+    if let Some(size) = downloads.avatar_size {
+        // Kept in BGR order throughout, as imwrite expects
+        let avatar = imgcodecs::imread(&file_path, imgcodecs::IMREAD_COLOR)?;
+        if avatar.empty() {
+            return Err(WordleError::Decode {
+                path: file_path,
+                reason: "not a supported image".to_string(),
+            });
+        }
+        let normalized = normalize_avatar(&avatar, size as i32)?;
+        file_path = save_png(url, &format!("{file_path}.{size}px.png"), &normalized)?;
+        debug!("Resized avatar to {size}px at {file_path}");
+    }
+
+    Ok(file_path)
+}
+
+/// Saves an image derived from the download at `url` as a PNG, returning its path
+fn save_png(url: &str, path: &str, image: &Mat) -> Result<String> {
+    if !imgcodecs::imwrite(path, image, &core::Vector::new())? {
+        return Err(WordleError::Download {
+            url: url.to_string(),
+            source: format!("unable to save it to {path}").into(),
+        });
+    }
+    Ok(path.to_string())
+}
+
+/// Resizes an avatar so its longest side is `size` pixels, keeping its aspect ratio
+///
+/// Avatar URLs come in sizes from 32 to 4096 pixels, so normalising them lets the
+/// detector's scale range stay tight whichever size was downloaded.
+pub fn normalize_avatar(avatar: &Mat, size: i32) -> Result<Mat> {
+    let longest = avatar.cols().max(avatar.rows()).max(1);
+    let scale = size as f64 / longest as f64;
+    let target = core::Size::new(
+        ((avatar.cols() as f64 * scale).round() as i32).max(1),
+        ((avatar.rows() as f64 * scale).round() as i32).max(1),
+    );
+    if target == avatar.size()? {
+        return Ok(avatar.try_clone()?);
+    }
+
+    // Area averaging avoids aliasing when shrinking, linear is smoother when enlarging
+    let interpolation = if scale < 1.0 {
+        imgproc::INTER_AREA
+    } else {
+        imgproc::INTER_LINEAR
+    };
+    let mut resized = Mat::default();
+    imgproc::resize(avatar, &mut resized, target, 0.0, 0.0, interpolation)?;
+    Ok(resized)
+}
+
+/// Load every template image, failing with the offending path if any is missing or unreadable
+pub fn validate_templates(paths: &[&str]) -> Result<()> {
+    for path in paths {
+        if !std::path::Path::new(path).is_file() {
+            return Err(WordleError::TemplateMissing {
+                path: path.to_string(),
+            });
+        }
+
+        let template = read_image(path)?;
+
+        info!(
+            "Loaded template {path} ({}x{})",
+            template.cols(),
+            template.rows()
+        );
+    }
+
+    Ok(())
+}
+
+/// A completion marker template and the confidence it must be matched with
+#[derive(Clone)]
+pub struct MarkerTemplate {
+    pub image: Mat,
+    pub threshold: f64,
+    fingerprint: Option<u64>, // Of `image`, see [`detection::needle_fingerprint`]
+}
+
+impl MarkerTemplate {
+    /// A template matched at [`DEFAULT_MARKER_THRESHOLD`]
+    pub fn new(image: Mat) -> Self {
+        Self {
+            fingerprint: detection::needle_fingerprint(&image).ok(),
+            image,
+            threshold: DEFAULT_MARKER_THRESHOLD,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Reads the template at `path`, to be matched at `threshold`
+    pub fn load(path: &str, threshold: f64) -> Result<Self> {
+        Ok(Self::new(read_image(path)?).with_threshold(threshold))
+    }
+}
+
+#[derive(Debug)]
+pub struct Player {
+    uid: usize,
+    profile_urls: Vec<String>, // Every avatar the player may appear with, tried in order
+    avatar_override: Option<PathBuf>, // Used in place of the avatars when set
+}
+
+impl Player {
+    pub fn new(uid: usize, profile_url: String) -> Player {
+        Player {
+            uid,
+            profile_urls: vec![profile_url],
+            avatar_override: None,
+        }
+    }
+
+    /// A player looked for by an avatar a moderator set, see
+    /// [`AvatarOverrides`](overrides::AvatarOverrides), without downloading theirs
+    pub fn with_override(uid: usize, avatar_path: PathBuf) -> Player {
+        Player {
+            uid,
+            profile_urls: Vec::new(),
+            avatar_override: Some(avatar_path),
+        }
+    }
+
+    /// The ID the player was created with
+    pub fn uid(&self) -> usize {
+        self.uid
+    }
+
+    /// Also looks for the player under another avatar, such as a server avatar or one
+    /// they've since changed from
+    pub fn with_avatar(mut self, profile_url: String) -> Player {
+        if !self.profile_urls.contains(&profile_url) {
+            self.profile_urls.push(profile_url);
+        }
+        self
+    }
+
+    /// Every avatar the player may appear with, read from their override if they have
+    /// one and downloaded otherwise
+    pub async fn load_avatars(&self, downloads: &DownloadLimiter) -> Result<Vec<Mat>> {
+        if let Some(path) = &self.avatar_override {
+            return Ok(vec![read_image(&path.display().to_string())?]);
+        }
+
+        let mut avatars = Vec::new();
+        for url in &self.profile_urls {
+            avatars.push(read_image(&download_avatar(downloads, url).await?)?);
+        }
+        Ok(avatars)
+    }
+}
+
+/// What [`find_players_in_image`] made of a screenshot
+#[derive(Debug, Default)]
+pub struct ScreenshotDetection {
+    pub players: Vec<Player>, // Credited with a completion marker
+    pub hard_mode: bool,      // Only checked when one player was credited, see [`is_hard_mode`]
+}
+
+/// Downloads the screenshot and every player's avatar, returning the players credited
+/// with a completion marker in the screenshot
+///
+/// Nobody can have finished in a screenshot without any of `solved_markers`, e.g. a
+/// share of a game in progress, so then no avatar is downloaded or searched for at all.
+/// Otherwise each player must be found by a majority of `votes` detection passes, see
+/// [`is_player_in_image_with_votes`], though players whose colours aren't in the
+/// screenshot are ruled out without a search, see [`may_show_player`]. With an
+/// `archive`, the screenshot and who was found in it are kept for later re-analysis.
+///
+/// A player found is only credited beneath a completion marker, and each marker credits
+/// at most one player, see [`detection::credit_completions`]. Players are only looked
+/// for within `region`. A single player's game is also checked for hard mode on the
+/// same download.
+pub async fn find_players_in_image(
+    players: Vec<Player>,
+    haystack_url: String,
+    downloads: &DownloadLimiter,
+    votes: usize,
+    solved_markers: &[MarkerTemplate],
+    region: SearchRegion,
+    archive: Option<&Archive>,
+) -> Result<ScreenshotDetection> {
+    let haystack_fp = download_image(downloads, &haystack_url).await?;
+    let haystack = Arc::new(read_image(&haystack_fp)?);
+
+    let markers = {
+        let (haystack, solved_markers) = (haystack.clone(), solved_markers.to_vec());
+        run_blocking(move || find_completion_markers(&haystack, &solved_markers)).await?
+    };
+    if markers.is_empty() {
+        info!(
+            "No completion markers in {}, not checking {} player(s)",
+            haystack_url,
+            players.len()
+        );
+        return Ok(ScreenshotDetection::default());
+    }
+
+    let mut avatars = Vec::new();
+    for player in &players {
+        avatars.push(fingerprint_variants(player.load_avatars(downloads).await?));
+    }
+
+    // Every player is located before any is credited, so that two close enough to
+    // share a marker can't both be credited with it. Each player is searched for on
+    // their own thread, as in [`verify_finished_players_parallel`].
+    let trace = {
+        let haystack = haystack.clone();
+        run_blocking(move || {
+            record_verification(|| {
+                let histogram = detection::colour_histogram(&haystack)?;
+                let frame = haystack.size()?;
+                let bands = marker_bands(&markers, region, frame);
+                let found = map_in_parallel(&avatars, verification_workers(), |variants| {
+                    locate_player(variants, &haystack, &histogram, region, &bands, votes)
+                })?;
+                Ok(credit_found(found, markers, frame))
+            })
+        })
+        .await?
+    };
+
+    let mut found_players = Vec::new();
+    let mut outcomes = Vec::new();
+    for (player, avatar) in players.into_iter().zip(trace.avatars) {
+        outcomes.push(PlayerOutcome {
+            uid: player.uid,
+            found: avatar.credited,
+        });
+        if avatar.credited {
+            info!("Found player {} in image at {:?}", player.uid, avatar.found);
+            found_players.push(player);
+        }
+    }
+
+    // Failing to archive shouldn't lose the detection itself
+    if let Some(archive) = archive {
+        match archive
+            .save(&haystack_fp, outcomes, &haystack_url, Utc::now())
+            .await
+        {
+            Ok(path) => info!("Archived screenshot to {}", path.display()),
+            Err(why) => log::error!("Error archiving screenshot: {}", why),
+        }
+    }
+
+    // The hard-mode marker can only be attributed when the screenshot is one player's
+    let hard_mode = match found_players.len() {
+        1 => match hard_mode_marker() {
+            Ok(Some(marker)) => {
+                run_blocking(move || record_verification(|| is_hard_mode(&haystack, &marker))).await
+            }
+            Ok(None) => Ok(false),
+            Err(why) => Err(why),
+        }
+        .unwrap_or_else(|why| {
+            log::error!("Error checking {} for hard mode: {:?}", haystack_url, why);
+            false
+        }),
+        _ => false,
+    };
+
+    Ok(ScreenshotDetection {
+        players: found_players,
+        hard_mode,
+    })
+}
+
+/// Least share of an avatar's colours a screenshot must show for the avatar to be
+/// searched for, see [`detection::colour_coverage`]
+///
+/// Kept low enough that an avatar drawn over by a marker or cut off at the edge is still
+/// searched for, while one in colours the screenshot lacks altogether is not.
+pub const MIN_COLOUR_COVERAGE: f64 = 0.75;
+
+/// Whether the screenshot, going by its [`detection::colour_histogram`], has enough of
+/// the avatar's colours that the avatar could be in it
+///
+/// A cheap check before the full search, ruling out most players who aren't pictured.
+pub fn may_show_player(avatar: &Mat, haystack_histogram: &Mat) -> Result<bool> {
+    let coverage = detection::colour_coverage(avatar, haystack_histogram)?;
+    if coverage < MIN_COLOUR_COVERAGE {
+        debug!(
+            "Screenshot has {:.0}% of the avatar's colours, not searching for it",
+            coverage * 100.0
+        );
+        metrics::metrics().presence_rejections.inc();
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Runs a check of a screenshot, counting it and timing it in the metrics
+/// Runs CPU-bound detection on one of tokio's blocking threads, so that OpenCV doesn't
+/// hold up the event handlers sharing the async runtime
+async fn run_blocking<T: Send + 'static>(
+    check: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(check)
+        .await
+        .expect("Detection thread panicked")
+}
+
+fn record_verification<T>(check: impl FnOnce() -> Result<T>) -> Result<T> {
+    let metrics = metrics::metrics();
+    metrics.verifications.inc();
+    let timer = metrics.detection_latency.start_timer();
+    let result = check();
+    timer.observe_duration();
+
+    if result.is_err() {
+        metrics.detection_failures.inc();
+    }
+    result
+}
+
+/// Checks whether an already loaded avatar appears in an already loaded screenshot
+///
+/// This is the computation behind [`find_players_in_image`] without any I/O, so callers
+/// that already hold the images can skip reloading them.
+pub fn is_player_in_image(avatar: &Mat, haystack: &Mat) -> Result<bool> {
+    is_player_in_image_with_votes(avatar, haystack, 1)
+}
+
+/// Same as [`is_player_in_image`], requiring a majority of `votes` detection passes to
+/// find the avatar, see [`detection::vote_on_detection`]
+pub fn is_player_in_image_with_votes(avatar: &Mat, haystack: &Mat, votes: usize) -> Result<bool> {
+    Ok(check_player_in_image(avatar, haystack, votes)?.found)
+}
+
+/// What detection made of one avatar in a screenshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerMatch {
+    pub found: bool,               // Whether a majority of the votes found the avatar
+    pub best: Option<MatchResult>, // The best match of any vote, even if outvoted
+}
+
+/// Same as [`is_player_in_image_with_votes`], also returning where and how confidently
+/// the avatar was matched, for one-off checks that want the details
+pub fn check_player_in_image(avatar: &Mat, haystack: &Mat, votes: usize) -> Result<PlayerMatch> {
+    let params = DetectionParams {
+        circular: true,
+        ..Default::default()
+    };
+    let (found, best) = detection::vote_on_best_match(avatar, haystack, &params, votes)?;
+    Ok(PlayerMatch { found, best })
+}
+
+/// Same as [`check_player_in_image`] for a player with several avatars, finding them if
+/// any one of the avatars is found
+///
+/// The match of the first avatar found is returned, or the best of all of them if none
+/// was.
+pub fn check_avatar_variants(avatars: &[Mat], haystack: &Mat, votes: usize) -> Result<PlayerMatch> {
+    let mut best: Option<MatchResult> = None;
+    for (i, avatar) in avatars.iter().enumerate() {
+        let player_match = check_player_in_image(avatar, haystack, votes)?;
+        if player_match.found {
+            debug!("Matched avatar variant {} of {}", i, avatars.len());
+            return Ok(player_match);
+        }
+        if let Some(candidate) = player_match.best
+            && best.is_none_or(|(_, confidence)| candidate.1 > confidence)
+        {
+            best = Some(candidate);
+        }
+    }
+
+    Ok(PlayerMatch { found: false, best })
+}
+
+/// How a single avatar fared in [`verify_finished_players`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvatarTrace {
+    pub found: Option<MatchResult>, // Best match of the avatar, if any passed the threshold
+    pub center: Option<Point>,      // Centre of that match
+    pub candidate_markers: Vec<usize>, // Markers whose x-range contains the centre
+    pub credited: bool,
+}
+
+/// Every decision made while working out who finished, for debugging missed completions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerificationTrace {
+    pub markers: Vec<MatchResult>, // Completion markers found in the screenshot
+    pub avatars: Vec<AvatarTrace>, // One per avatar, in the order given
+}
+
+impl VerificationTrace {
+    /// Indices of the avatars credited with a completion
+    pub fn credited(&self) -> Vec<usize> {
+        self.avatars
+            .iter()
+            .enumerate()
+            .filter(|(_, avatar)| avatar.credited)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Works out which of the given avatars finished, from the completion markers in the
+/// screenshot, returning their indices
+///
+/// Any of `solved_markers` counts as a completion marker, so one per screenshot theme
+/// (light and dark) can be given. All avatars are located before any is credited so
+/// that avatars close enough to share a marker can be de-conflicted, see
+/// [`detection::credit_completions`].
+pub fn find_finished_players(
+    avatars: &[Mat],
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+) -> Result<Vec<usize>> {
+    Ok(verify_finished_players(avatars, haystack, solved_markers)?.credited())
+}
+
+/// Where [`verify_finished_players_in`] looks for avatars
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SearchRegion {
+    #[default]
+    Whole, // The full screenshot
+    /// Only full-width bands reaching `reach` marker heights above and below each
+    /// completion marker, so copies of an avatar elsewhere (e.g. in a member list)
+    /// can't be credited
+    AroundMarkers { reach: f64 },
+}
+
+/// Same as [`find_finished_players`], returning the full trace of how it decided
+pub fn verify_finished_players(
+    avatars: &[Mat],
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+) -> Result<VerificationTrace> {
+    verify_finished_players_in(avatars, haystack, solved_markers, SearchRegion::Whole)
+}
+
+/// Same as [`verify_finished_players`], only looking for avatars in the given region
+pub fn verify_finished_players_in(
+    avatars: &[Mat],
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+    region: SearchRegion,
+) -> Result<VerificationTrace> {
+    verify_finished_players_parallel(avatars, haystack, solved_markers, region, 1)
+}
+
+/// Same as [`verify_finished_players_in`], searching for the avatars on up to `workers`
+/// threads at once
+///
+/// Each avatar's search is independent of the others', and `match_template` spends its
+/// time in OpenCV's native code, so a screenshot with many players is verified several
+/// times faster. The trace is the same as a serial verification's.
+pub fn verify_finished_players_parallel(
+    avatars: &[Mat],
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+    region: SearchRegion,
+    workers: usize,
+) -> Result<VerificationTrace> {
+    record_verification(|| {
+        trace_finished_players(avatars, haystack, solved_markers, region, workers)
+    })
+}
+
+/// Threads to verify screenshots on, one per available core
+pub fn verification_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+}
+
+/// `f` applied to every item on up to `workers` scoped threads, in the items' order
+fn map_in_parallel<T: Sync, R: Send>(
+    items: &[T],
+    workers: usize,
+    f: impl Fn(&T) -> Result<R> + Sync,
+) -> Result<Vec<R>> {
+    if workers <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(workers);
+    std::thread::scope(|scope| {
+        let f = &f;
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Result<Vec<R>>>()))
+            .collect();
+
+        let mut results = Vec::with_capacity(items.len());
+        for handle in handles {
+            results.extend(handle.join().expect("Verification thread panicked")?);
+        }
+        Ok(results)
+    })
+}
+
+/// Best match of an avatar within `band` of the haystack under `params`, found by a
+/// majority of `votes` passes, in the haystack's coordinates
+fn find_avatar_in_band(
+    needle: &Mat,
+    params: &DetectionParams,
+    haystack: &Mat,
+    band: &detection::BoundingBox,
+    votes: usize,
+) -> Result<Option<MatchResult>> {
+    let (top_left, bottom_right) = band;
+    let rect = core::Rect::new(
+        top_left.x,
+        top_left.y,
+        bottom_right.x - top_left.x,
+        bottom_right.y - top_left.y,
+    );
+    let cropped = haystack.roi(rect)?.try_clone()?;
+    let (found, best) = detection::vote_on_best_match(needle, &cropped, params, votes)?;
+
+    Ok(best.filter(|_| found).map(|((found_tl, found_br), score)| {
+        let offset = |point: Point| Point::new(point.x + top_left.x, point.y + top_left.y);
+        ((offset(found_tl), offset(found_br)), score)
+    }))
+}
+
+/// Best match of a circular avatar in `region` of the haystack, found by a
+/// majority of `votes` passes, in the haystack's coordinates
+///
+/// `bands` are the regions around the markers, see [`marker_bands`], and only searched
+/// for [`SearchRegion::AroundMarkers`]. The needle is told apart among those already
+/// resized by `fingerprint`, see [`DetectionParams::needle_fingerprint`].
+fn locate_avatar(
+    needle: &Mat,
+    fingerprint: Option<u64>,
+    haystack: &Mat,
+    region: SearchRegion,
+    bands: &[detection::BoundingBox],
+    votes: usize,
+) -> Result<Option<MatchResult>> {
+    let params = DetectionParams {
+        circular: true,
+        needle_fingerprint: fingerprint,
+        ..Default::default()
+    };
+    if region == SearchRegion::Whole {
+        let (found, best) = detection::vote_on_best_match(needle, haystack, &params, votes)?;
+        return Ok(best.filter(|_| found));
+    }
+
+    // Bands of nearby markers overlap, so keep the best match across all of them
+    let mut best: Option<MatchResult> = None;
+    for band in bands {
+        if let Some(found) = find_avatar_in_band(needle, &params, haystack, band, votes)?
+            && best.is_none_or(|best| {
+                detection::scale_normalized_confidence(&found)
+                    > detection::scale_normalized_confidence(&best)
+            })
+        {
+            best = Some(found);
+        }
+    }
+    Ok(best)
+}
+
+/// Where a player is in the screenshot, by the first of their avatar variants found
+///
+/// Each variant comes with its fingerprint, see [`fingerprint_variants`]. Variants in
+/// colours the screenshot lacks aren't searched for, see [`may_show_player`].
+fn locate_player(
+    variants: &[(Mat, Option<u64>)],
+    haystack: &Mat,
+    haystack_histogram: &Mat,
+    region: SearchRegion,
+    bands: &[detection::BoundingBox],
+    votes: usize,
+) -> Result<Option<MatchResult>> {
+    for (i, (variant, fingerprint)) in variants.iter().enumerate() {
+        if !may_show_player(variant, haystack_histogram)? {
+            continue;
+        }
+        if let Some(found) = locate_avatar(variant, *fingerprint, haystack, region, bands, votes)? {
+            debug!("Matched avatar variant {} of {}", i, variants.len());
+            return Ok(Some(found));
+        }
+    }
+    Ok(None)
+}
+
+/// A player's avatar variants as loaded, each with its fingerprint, so that it is only
+/// hashed once however often it is searched for
+///
+/// A variant whose pixels can't be read is still searched for, only
+/// without reusing its resizes.
+fn fingerprint_variants(variants: Vec<Mat>) -> Vec<(Mat, Option<u64>)> {
+    variants
+        .into_iter()
+        .map(|variant| {
+            let fingerprint = detection::needle_fingerprint(&variant).ok();
+            (variant, fingerprint)
+        })
+        .collect()
+}
+
+/// The bands around `markers` searched for [`SearchRegion::AroundMarkers`], or none for
+/// the whole screenshot
+fn marker_bands(
+    markers: &[MatchResult],
+    region: SearchRegion,
+    frame: core::Size,
+) -> Vec<detection::BoundingBox> {
+    match region {
+        SearchRegion::Whole => Vec::new(),
+        SearchRegion::AroundMarkers { reach } => markers
+            .iter()
+            .filter_map(|(marker, _)| detection::marker_band(marker, reach, frame))
+            .collect(),
+    }
+}
+
+/// Every completion marker in the screenshot, however many players finished, not just
+/// those whose avatars are being checked
+pub fn find_completion_markers(
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+) -> Result<Vec<MatchResult>> {
+    let mut markers = Vec::new();
+    for solved_marker in solved_markers {
+        markers.extend(detection::detect_all_above_threshold(
+            &solved_marker.image,
+            haystack,
+            &DetectionParams {
+                threshold: solved_marker.threshold,
+                needle_fingerprint: solved_marker.fingerprint,
+                ..Default::default()
+            },
+        )?);
+    }
+    // Templates for different themes could still both match the same marker
+    detection::rank_matches(&mut markers);
+    let markers = detection::suppress_overlaps(&markers);
+    debug!("Found {} completion markers: {:?}", markers.len(), markers);
+    Ok(markers)
+}
+
+/// What [`verify_player_completion`] made of one player in a screenshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerVerification {
+    NoCompletionsInImage, // Nobody finished, so no other player needs checking either
+    PlayerNotCompleted,   // Someone finished, but the player wasn't found by a marker
+    PlayerCompleted,
+}
+
+/// Whether the player with `avatar` finished in the screenshot, telling a screenshot
+/// nobody finished in apart from one the player didn't
+///
+/// The avatar isn't searched for at all when there are no completion markers, or when
+/// the screenshot lacks its colours, see [`may_show_player`]. This is for checking one
+/// player at a time; the bot checks every player at once through
+/// [`find_players_in_image`], which skips them all the same way.
+pub fn verify_player_completion(
+    avatar: &Mat,
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+) -> Result<PlayerVerification> {
+    record_verification(|| {
+        let markers = find_completion_markers(haystack, solved_markers)?;
+        if markers.is_empty() {
+            return Ok(PlayerVerification::NoCompletionsInImage);
+        }
+        if !may_show_player(avatar, &detection::colour_histogram(haystack)?)? {
+            return Ok(PlayerVerification::PlayerNotCompleted);
+        }
+
+        let trace = trace_markers(
+            std::slice::from_ref(avatar),
+            haystack,
+            markers,
+            SearchRegion::Whole,
+            1,
+        )?;
+        Ok(if trace.credited().is_empty() {
+            PlayerVerification::PlayerNotCompleted
+        } else {
+            PlayerVerification::PlayerCompleted
+        })
+    })
+}
+
+fn trace_finished_players(
+    avatars: &[Mat],
+    haystack: &Mat,
+    solved_markers: &[MarkerTemplate],
+    region: SearchRegion,
+    workers: usize,
+) -> Result<VerificationTrace> {
+    let markers = find_completion_markers(haystack, solved_markers)?;
+    trace_markers(avatars, haystack, markers, region, workers)
+}
+
+/// Locates the avatars and credits them with the already found `markers`
+fn trace_markers(
+    avatars: &[Mat],
+    haystack: &Mat,
+    markers: Vec<MatchResult>,
+    region: SearchRegion,
+    workers: usize,
+) -> Result<VerificationTrace> {
+    let frame = haystack.size()?;
+    let bands = marker_bands(&markers, region, frame);
+
+    // The haystack is only ever read, and each band search works on its own copy of the
+    // band, so nothing OpenCV writes to is shared between threads
+    let found = map_in_parallel(avatars, workers, |avatar| {
+        let fingerprint = detection::needle_fingerprint(avatar).ok();
+        locate_avatar(avatar, fingerprint, haystack, region, &bands, 1)
+    })?;
+    Ok(credit_found(found, markers, frame))
+}
+
+/// Credits the avatars `found` in a screenshot of size `frame` with its `markers`, one
+/// avatar per marker, see [`detection::credit_completions`]
+fn credit_found(
+    found: Vec<Option<MatchResult>>,
+    markers: Vec<MatchResult>,
+    frame: core::Size,
+) -> VerificationTrace {
+    let marker_boxes: Vec<_> = markers
+        .iter()
+        .map(|(bounding_box, _)| *bounding_box)
+        .collect();
+
+    // Boxes partly outside the screenshot are clamped, and dropped if nothing is left
+    let found: Vec<_> = found
+        .into_iter()
+        .map(|found| {
+            found.and_then(|(bounding_box, score)| {
+                detection::clamp_to_frame(&bounding_box, frame).map(|clamped| (clamped, score))
+            })
+        })
+        .collect();
+    let avatar_boxes: Vec<_> = found
+        .iter()
+        .map(|found| found.map(|(bounding_box, _)| bounding_box))
+        .collect();
+    let credited = detection::credit_completions(&marker_boxes, &avatar_boxes);
+
+    let avatars = found
+        .into_iter()
+        .enumerate()
+        .map(|(i, found)| {
+            let center = found.map(|(bounding_box, _)| detection::center(&bounding_box));
+            let candidate_markers = center.map_or_else(Vec::new, |center| {
+                marker_boxes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, marker)| detection::is_under_marker(marker, center))
+                    .map(|(m, _)| m)
+                    .collect()
+            });
+            let trace = AvatarTrace {
+                found,
+                center,
+                candidate_markers,
+                credited: credited.contains(&i),
+            };
+            debug!("Avatar {}: {:?}", i, trace);
+            trace
+        })
+        .collect();
+
+    let trace = VerificationTrace { markers, avatars };
+    info!(
+        "Verified {} avatar(s) against {} marker(s), credited {:?}",
+        trace.avatars.len(),
+        trace.markers.len(),
+        trace.credited()
+    );
+    trace
+}
+
+/// Whether the end screen in `haystack` shows the hard-mode marker
+pub fn is_hard_mode(haystack: &Mat, hard_mode_marker: &Mat) -> Result<bool> {
+    let params = DetectionParams {
+        num_matches: 1,
+        ..Default::default()
+    };
+    let found = detection::detect_needle_in_haystack(hard_mode_marker, haystack, &params)?;
+    Ok(!found.is_empty())
+}
+
+/// Downloads a screenshot and checks it for the hard-mode marker, see [`is_hard_mode`]
+///
+/// The marker template is optional, so without one no game is hard mode.
+pub async fn detect_hard_mode(downloads: &DownloadLimiter, screenshot_url: &str) -> Result<bool> {
+    let Some(hard_mode_marker) = hard_mode_marker()? else {
+        return Ok(false);
+    };
+
+    let haystack = read_image(&download_image(downloads, screenshot_url).await?)?;
+    run_blocking(move || record_verification(|| is_hard_mode(&haystack, &hard_mode_marker))).await
+}
+
+/// The hard-mode marker template, or `None` if there isn't one
+fn hard_mode_marker() -> Result<Option<Mat>> {
+    if !std::path::Path::new(HARD_MODE_TEMPLATE).is_file() {
+        debug!("No {HARD_MODE_TEMPLATE}, not checking for hard mode");
+        return Ok(None);
+    }
+    Ok(Some(read_image(HARD_MODE_TEMPLATE)?))
+}
+
+/// Runs completion detection on an arbitrary screenshot, looking for the avatars at
+/// `avatar_urls` in it, returning the trace along with the screenshot annotated as a PNG
+///
+/// This is the detection behind a real completion, for trying out screenshots that
+/// were missed or misread, see [`annotate_trace`].
+pub async fn simulate_detection(
+    downloads: &DownloadLimiter,
+    marker_templates: &[MarkerTemplate],
+    screenshot_url: &str,
+    avatar_urls: &[&str],
+) -> Result<(VerificationTrace, Vec<u8>)> {
+    let haystack = read_image(&download_image(downloads, screenshot_url).await?)?;
+    let mut avatars = Vec::new();
+    for url in avatar_urls {
+        avatars.push(read_image(&download_avatar(downloads, url).await?)?);
+    }
+    let marker_templates = marker_templates.to_vec();
+    run_blocking(move || {
+        let trace = verify_finished_players(&avatars, &haystack, &marker_templates)?;
+        let annotated = annotate_trace(&haystack, &trace)?;
+        Ok((trace, annotated))
+    })
+    .await
+}
+
+/// Boxes the completion markers and the given avatars in a finished screenshot, as proof
+/// of a detection, returning it as a PNG, see [`annotate_trace`]
+pub fn annotate_completion(
+    haystack: &Mat,
+    avatars: &[Mat],
+    solved_markers: &[MarkerTemplate],
+) -> Result<Vec<u8>> {
+    let trace = verify_finished_players_parallel(
+        avatars,
+        haystack,
+        solved_markers,
+        SearchRegion::Whole,
+        verification_workers(),
+    )?;
+    annotate_trace(haystack, &trace)
+}
+
+/// Downloads a finished screenshot and the players' avatars for [`annotate_completion`],
+/// looking for each player by their first avatar
+///
+/// The annotated screenshot is only returned, never written next to the downloads.
+pub async fn annotate_screenshot(
+    downloads: &DownloadLimiter,
+    marker_templates: &[MarkerTemplate],
+    screenshot_url: &str,
+    players: &[Player],
+) -> Result<Vec<u8>> {
+    let haystack = read_image(&download_image(downloads, screenshot_url).await?)?;
+    let mut avatars = Vec::new();
+    for player in players {
+        avatars.extend(player.load_avatars(downloads).await?.into_iter().next());
+    }
+    let marker_templates = marker_templates.to_vec();
+    run_blocking(move || annotate_completion(&haystack, &avatars, &marker_templates)).await
+}
+
+/// Draws every marker and avatar match of a trace onto a copy of the screenshot it
+/// came from, labelled with their confidence, and encodes it as a PNG
+///
+/// Markers are boxed in yellow, credited avatars in green and uncredited ones in red.
+pub fn annotate_trace(haystack: &Mat, trace: &VerificationTrace) -> Result<Vec<u8>> {
+    // Screenshots are read in RGB order, so colours are given as RGB too
+    let yellow = core::Scalar::new(255.0, 210.0, 0.0, 0.0);
+    let green = core::Scalar::new(0.0, 220.0, 0.0, 0.0);
+    let red = core::Scalar::new(230.0, 0.0, 0.0, 0.0);
+
+    let mut annotated = haystack.try_clone()?;
+    let mut draw = |((top_left, bottom_right), confidence): &MatchResult,
+                    label: String,
+                    colour: core::Scalar|
+     -> Result<()> {
+        imgproc::rectangle_points(
+            &mut annotated,
+            *top_left,
+            *bottom_right,
+            colour,
+            2,
+            imgproc::LINE_8,
+            0,
+        )?;
+        imgproc::put_text(
+            &mut annotated,
+            &format!("{label} {confidence:.3}"),
+            Point::new(top_left.x, (top_left.y - 4).max(12)),
+            imgproc::FONT_HERSHEY_SIMPLEX,
+            0.4,
+            colour,
+            1,
+            imgproc::LINE_AA,
+            false,
+        )?;
+        Ok(())
+    };
+
+    for (i, marker) in trace.markers.iter().enumerate() {
+        draw(marker, format!("marker {i}"), yellow)?;
+    }
+    for (i, avatar) in trace.avatars.iter().enumerate() {
+        if let Some(found) = &avatar.found {
+            let colour = if avatar.credited { green } else { red };
+            draw(found, format!("avatar {i}"), colour)?;
+        }
+    }
+
+    let mut bgr = Mat::default();
+    imgproc::cvt_color_def(&annotated, &mut bgr, imgproc::COLOR_RGB2BGR)?;
+    let mut png = core::Vector::<u8>::new();
+    imgcodecs::imencode(".png", &bgr, &mut png, &core::Vector::new())?;
+    Ok(png.to_vec())
+}
@@ -0,0 +1,77 @@
+#![allow(dead_code)]
+
+use anyhow::Result;
+use opencv::{
+    core::{CV_8UC3, Mat, Point, Rect, Scalar},
+    imgproc::{self, LINE_8},
+    prelude::*,
+};
+
+/// Create a solid colour image
+pub fn blank(width: i32, height: i32, colour: Scalar) -> Result<Mat> {
+    Ok(Mat::new_rows_cols_with_default(
+        height, width, CV_8UC3, colour,
+    )?)
+}
+
+/// Draw a deterministic, asymmetric pattern to stand in for an avatar
+pub fn pattern(size: i32) -> Result<Mat> {
+    let mut image = blank(size, size, Scalar::new(160.0, 90.0, 40.0, 0.0))?;
+    imgproc::rectangle(
+        &mut image,
+        Rect::new(size / 8, size / 8, size / 3, size / 4),
+        Scalar::new(40.0, 200.0, 230.0, 0.0),
+        -1,
+        LINE_8,
+        0,
+    )?;
+    imgproc::circle(
+        &mut image,
+        Point::new(size * 2 / 3, size * 5 / 8),
+        size / 5,
+        Scalar::new(120.0, 220.0, 20.0, 0.0),
+        -1,
+        LINE_8,
+        0,
+    )?;
+    imgproc::line(
+        &mut image,
+        Point::new(0, size - 1),
+        Point::new(size / 2, size / 3),
+        Scalar::new(250.0, 250.0, 250.0, 0.0),
+        3,
+        LINE_8,
+        0,
+    )?;
+    Ok(image)
+}
+
+/// Copy `src` onto `dst` with its top-left corner at `at`
+pub fn paste(src: &Mat, dst: &mut Mat, at: Point) -> Result<()> {
+    let mut region = dst.roi_mut(Rect::new(at.x, at.y, src.cols(), src.rows()))?;
+    src.copy_to(&mut region)?;
+    Ok(())
+}
+
+/// Copy `src` onto `dst` through a circular mask, as Discord renders avatars
+pub fn paste_circle(src: &Mat, dst: &mut Mat, at: Point) -> Result<()> {
+    let mut mask = Mat::new_rows_cols_with_default(
+        src.rows(),
+        src.cols(),
+        opencv::core::CV_8UC1,
+        Scalar::all(0.0),
+    )?;
+    imgproc::circle(
+        &mut mask,
+        Point::new(src.cols() / 2, src.rows() / 2),
+        src.cols().min(src.rows()) / 2,
+        Scalar::all(255.0),
+        -1,
+        LINE_8,
+        0,
+    )?;
+
+    let mut region = dst.roi_mut(Rect::new(at.x, at.y, src.cols(), src.rows()))?;
+    src.copy_to_masked(&mut region, &mask)?;
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+use wordle_timer_bot::announcements::{fastest_description, fastest_solvers};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn test_single_fastest_solver() {
+    let leaderboard = [(10, ms(61_000)), (20, ms(75_000)), (30, ms(90_000))];
+
+    let fastest = fastest_solvers(&leaderboard);
+    assert_eq!(fastest, vec![(10, ms(61_000))]);
+    assert_eq!(
+        fastest_description(&fastest),
+        "<@10> solved today's Wordle fastest, in 1 minute and 1.000 second!"
+    );
+}
+
+#[test]
+fn test_ties_list_every_fastest_solver() {
+    // Given out of order, so the minimum isn't just the first entry
+    let leaderboard = [
+        (10, ms(75_000)),
+        (20, ms(61_000)),
+        (30, ms(61_000)),
+        (40, ms(61_001)),
+    ];
+
+    let fastest = fastest_solvers(&leaderboard);
+    assert_eq!(fastest, vec![(20, ms(61_000)), (30, ms(61_000))]);
+    assert_eq!(
+        fastest_description(&fastest),
+        "Tied for today's fastest solve, in 1 minute and 1.000 second: <@20>, <@30>"
+    );
+}
+
+#[test]
+fn test_no_solvers() {
+    assert!(fastest_solvers(&[]).is_empty());
+    assert_eq!(fastest_description(&[]), "Nobody solved today's Wordle.");
+}
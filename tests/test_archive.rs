@@ -0,0 +1,65 @@
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use wordle_timer_bot::archive::{Archive, ArchiveRecord, PlayerOutcome};
+
+#[tokio::test]
+async fn test_archive_writes_image_and_sidecar() -> Result<()> {
+    let data_dir = std::env::temp_dir().join("wordle_archive_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    std::fs::create_dir_all(&data_dir)?;
+    let screenshot = data_dir.join("image.png?ex=123");
+    std::fs::write(&screenshot, b"not really a png")?;
+
+    let at = Utc.with_ymd_and_hms(2025, 3, 4, 5, 6, 7).unwrap();
+    let players = vec![
+        PlayerOutcome {
+            uid: 1,
+            found: true,
+        },
+        PlayerOutcome {
+            uid: 2,
+            found: false,
+        },
+    ];
+    let copy = Archive::new(&data_dir)
+        .save(
+            screenshot.to_str().unwrap(),
+            players.clone(),
+            "https://cdn.example/image.png",
+            at,
+        )
+        .await?;
+
+    assert_eq!(
+        copy,
+        data_dir.join("archive/2025-03-04/050607000-image.png")
+    );
+    assert_eq!(std::fs::read(&copy)?, b"not really a png");
+
+    let sidecar = std::fs::read_to_string(copy.with_file_name("050607000-image.png.json"))?;
+    let record: ArchiveRecord = serde_json::from_str(&sidecar)?;
+    assert_eq!(record.source_url, "https://cdn.example/image.png");
+    assert_eq!(record.archived_at, "2025-03-04T05:06:07+00:00");
+    assert_eq!(record.players, players);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_annotated_screenshot_is_archived() -> Result<()> {
+    let data_dir = std::env::temp_dir().join("wordle_annotated_archive_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+
+    let at = Utc.with_ymd_and_hms(2025, 3, 4, 5, 6, 7).unwrap();
+    let path = Archive::new(&data_dir)
+        .save_annotated(b"annotated png", "https://cdn.example/image.png?ex=123", at)
+        .await?;
+
+    assert_eq!(
+        path,
+        data_dir.join("archive/2025-03-04/050607000-image-annotated.png")
+    );
+    assert_eq!(std::fs::read(&path)?, b"annotated png");
+
+    Ok(())
+}
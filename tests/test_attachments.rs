@@ -0,0 +1,52 @@
+use anyhow::Result;
+use serenity::all::Attachment;
+use wordle_timer_bot::{image_attachments, is_image_attachment};
+
+fn attachment(id: u64, filename: &str, content_type: Option<&str>) -> Result<Attachment> {
+    let url = format!("https://cdn.discordapp.com/attachments/1/{id}/{filename}");
+    Ok(serde_json::from_value(serde_json::json!({
+        "id": id.to_string(),
+        "filename": filename,
+        "size": 1024,
+        "url": url,
+        "proxy_url": url,
+        "content_type": content_type,
+    }))?)
+}
+
+#[test]
+fn test_screenshot_after_other_attachment_is_found() -> Result<()> {
+    let attachments = [
+        attachment(1, "notes.txt", Some("text/plain; charset=utf-8"))?,
+        attachment(2, "wordle.png", Some("image/png"))?,
+    ];
+
+    let images = image_attachments(&attachments);
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].filename, "wordle.png");
+    Ok(())
+}
+
+#[test]
+fn test_every_image_attachment_is_kept_in_order() -> Result<()> {
+    let attachments = [
+        attachment(1, "board.jpg", Some("image/jpeg"))?,
+        attachment(2, "clip.mp4", Some("video/mp4"))?,
+        attachment(3, "wordle.png", Some("image/png"))?,
+    ];
+
+    let names: Vec<&str> = image_attachments(&attachments)
+        .iter()
+        .map(|attachment| attachment.filename.as_str())
+        .collect();
+    assert_eq!(names, ["board.jpg", "wordle.png"]);
+    Ok(())
+}
+
+#[test]
+fn test_untyped_attachments_go_by_extension() -> Result<()> {
+    assert!(is_image_attachment(&attachment(1, "Wordle.PNG", None)?));
+    assert!(!is_image_attachment(&attachment(2, "wordle.txt", None)?));
+    assert!(!is_image_attachment(&attachment(3, "wordle", None)?));
+    Ok(())
+}
@@ -0,0 +1,316 @@
+// Exercises the OpenCV detection, which the stub built without it doesn't have
+#![cfg(feature = "cv")]
+
+mod common;
+
+use anyhow::Result;
+use opencv::core::Vector;
+use opencv::core::{Mat, MatTraitConst, Point, Scalar, Vec3b};
+use opencv::{imgcodecs, imgproc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::overrides::AvatarOverrides;
+use wordle_timer_bot::{
+    DownloadLimiter, MarkerTemplate, Player, SearchRegion, find_players_in_image,
+    is_animated_avatar_url, load_first_frame, normalize_avatar,
+};
+
+#[test]
+fn test_animated_avatar_urls_are_detected() {
+    assert!(is_animated_avatar_url(
+        "https://cdn.discordapp.com/avatars/1/a_0123abcd.gif?size=1024"
+    ));
+    assert!(is_animated_avatar_url(
+        "https://cdn.discordapp.com/avatars/1/a_0123abcd.webp"
+    ));
+    assert!(!is_animated_avatar_url(
+        "https://cdn.discordapp.com/avatars/1/0123abcd.webp?size=1024"
+    ));
+    // Only the file name matters, not other path segments
+    assert!(!is_animated_avatar_url(
+        "https://cdn.discordapp.com/a_dir/0123abcd.png"
+    ));
+}
+
+#[test]
+fn test_first_frame_of_animated_avatar() -> Result<()> {
+    // Two 16x16 frames: red over black, then solid blue
+    let frame = load_first_frame("./tests/fixtures/animated_avatar.gif")?;
+
+    assert_eq!((frame.cols(), frame.rows()), (16, 16));
+    assert_eq!(frame.channels(), 3);
+    assert_eq!(*frame.at_2d::<Vec3b>(0, 0)?, Vec3b::from([40, 40, 220]));
+    assert_eq!(*frame.at_2d::<Vec3b>(15, 0)?, Vec3b::from([0, 0, 0]));
+
+    Ok(())
+}
+
+#[test]
+fn test_avatars_are_resized_to_canonical_size() -> Result<()> {
+    let large = common::pattern(512)?;
+    let normalized = normalize_avatar(&large, 128)?;
+    assert_eq!((normalized.cols(), normalized.rows()), (128, 128));
+    assert_eq!(normalized.typ(), large.typ());
+
+    // Small avatars are enlarged, keeping their aspect ratio
+    let wide = common::blank(64, 32, Scalar::all(90.0))?;
+    let normalized = normalize_avatar(&wide, 128)?;
+    assert_eq!((normalized.cols(), normalized.rows()), (128, 64));
+
+    let tall = common::blank(300, 600, Scalar::all(90.0))?;
+    let normalized = normalize_avatar(&tall, 128)?;
+    assert_eq!((normalized.cols(), normalized.rows()), (64, 128));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_avatar_override_is_used_instead_of_downloading() -> Result<()> {
+    let data_dir = std::env::temp_dir().join("wordle_override_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    let overrides = AvatarOverrides::new(&data_dir);
+    assert_eq!(overrides.get(1, 2), None);
+
+    let mut png = Vector::<u8>::new();
+    imgcodecs::imencode(".png", &common::pattern(64)?, &mut png, &Vector::new())?;
+    let path = overrides.save(1, 2, png.as_slice()).await?;
+    assert_eq!(overrides.get(1, 2), Some(path.clone()));
+    assert_eq!(overrides.get(1, 3), None);
+
+    // Nothing is listening on the avatar URL, so only the override can be loaded
+    let downloads = DownloadLimiter::new(1);
+    let player = Player::with_override(2, path);
+    let avatars = player.load_avatars(&downloads).await?;
+    assert_eq!(avatars.len(), 1);
+    assert_eq!((avatars[0].cols(), avatars[0].rows()), (64, 64));
+
+    let player = Player::new(2, "http://127.0.0.1:9/avatar.png".to_string());
+    assert!(player.load_avatars(&downloads).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_undecodable_avatar_override_is_rejected() -> Result<()> {
+    let data_dir = std::env::temp_dir().join("wordle_bad_override_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    let overrides = AvatarOverrides::new(&data_dir);
+
+    let result = overrides.save(1, 2, b"not really a png").await;
+    assert!(matches!(result, Err(WordleError::Decode { .. })));
+    assert_eq!(overrides.get(1, 2), None);
+
+    Ok(())
+}
+
+/// Encodes an image as a PNG file's bytes
+fn encode_png(image: &Mat) -> Result<Vec<u8>> {
+    let mut png = Vector::<u8>::new();
+    imgcodecs::imencode(".png", image, &mut png, &Vector::new())?;
+    Ok(png.as_slice().to_vec())
+}
+
+/// A completion marker `width` pixels wide: a green bar with a white tick
+fn tick_marker(width: i32) -> Result<Mat> {
+    let mut marker = common::blank(width, 24, Scalar::new(80.0, 200.0, 80.0, 0.0))?;
+    imgproc::line(
+        &mut marker,
+        Point::new(4, 12),
+        Point::new(20, 4),
+        Scalar::all(255.0),
+        3,
+        imgproc::LINE_8,
+        0,
+    )?;
+    Ok(marker)
+}
+
+/// Serves each file at its path on a local port, and anything else as not found,
+/// returning the server's base URL and the paths asked for so far
+async fn serve(files: Vec<(&str, Vec<u8>)>) -> Result<(String, Arc<Mutex<Vec<String>>>)> {
+    let files: HashMap<String, Vec<u8>> = files
+        .into_iter()
+        .map(|(path, body)| (path.to_string(), body))
+        .collect();
+    let server = TcpListener::bind("127.0.0.1:0").await?;
+    let base = format!("http://{}", server.local_addr()?);
+    let requested = Arc::new(Mutex::new(Vec::new()));
+    let seen = requested.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = server.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let read = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            seen.lock().unwrap().push(path.to_string());
+            let (status, body) = match files.get(path) {
+                Some(body) => ("200 OK", body.as_slice()),
+                None => ("404 Not Found", &[][..]),
+            };
+            let header = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).await.unwrap();
+            stream.write_all(body).await.unwrap();
+        }
+    });
+    Ok((base, requested))
+}
+
+#[tokio::test]
+async fn test_no_avatars_downloaded_without_completion_markers() -> Result<()> {
+    // A game still in progress: the player's avatar, but no completion marker
+    let avatar = common::pattern(64)?;
+    let mut screenshot = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut screenshot, Point::new(100, 40))?;
+    let marker = tick_marker(24)?;
+
+    let (base, requested) = serve(vec![(
+        "/attachments/in_progress_share.png",
+        encode_png(&screenshot)?,
+    )])
+    .await?;
+
+    let players = vec![Player::new(1, format!("{base}/avatars/1/avatar.png"))];
+    let found = find_players_in_image(
+        players,
+        format!("{base}/attachments/in_progress_share.png"),
+        &DownloadLimiter::new(1),
+        1,
+        &[MarkerTemplate::new(marker)],
+        SearchRegion::Whole,
+        None,
+    )
+    .await?;
+
+    assert!(found.players.is_empty());
+    assert_eq!(
+        *requested.lock().unwrap(),
+        vec!["/attachments/in_progress_share.png"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_players_are_credited_with_one_marker() -> Result<()> {
+    // Both avatars' centres (x 132 and 200) fall within the one wide marker, whose
+    // centre (x 170) is nearer the second
+    let first = common::pattern(64)?;
+    let mut second = Mat::default();
+    opencv::core::bitwise_not(&first, &mut second, &opencv::core::no_array())?;
+    let marker = tick_marker(100)?;
+    let mut screenshot = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&first, &mut screenshot, Point::new(100, 40))?;
+    common::paste_circle(&second, &mut screenshot, Point::new(168, 40))?;
+    common::paste(&marker, &mut screenshot, Point::new(120, 130))?;
+
+    let (base, _) = serve(vec![
+        ("/attachments/close_players.png", encode_png(&screenshot)?),
+        ("/avatars/1/close_player_1.png", encode_png(&first)?),
+        ("/avatars/2/close_player_2.png", encode_png(&second)?),
+    ])
+    .await?;
+
+    let players = vec![
+        Player::new(1, format!("{base}/avatars/1/close_player_1.png")),
+        Player::new(2, format!("{base}/avatars/2/close_player_2.png")),
+    ];
+    let found = find_players_in_image(
+        players,
+        format!("{base}/attachments/close_players.png"),
+        &DownloadLimiter::new(1),
+        1,
+        &[MarkerTemplate::new(marker)],
+        SearchRegion::Whole,
+        None,
+    )
+    .await?;
+
+    let found: Vec<_> = found.players.iter().map(Player::uid).collect();
+    assert_eq!(found, vec![2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_player_under_several_markers_is_not_credited() -> Result<()> {
+    // Two separate markers, one above the other, both span the avatar's centre, so
+    // which of them is the player's can't be told
+    let avatar = common::pattern(64)?;
+    let marker = tick_marker(24)?;
+    let mut screenshot = common::blank(320, 240, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut screenshot, Point::new(100, 20))?;
+    common::paste(&marker, &mut screenshot, Point::new(120, 110))?;
+    common::paste(&marker, &mut screenshot, Point::new(120, 170))?;
+
+    let (base, _) = serve(vec![
+        ("/attachments/stacked_markers.png", encode_png(&screenshot)?),
+        ("/avatars/1/stacked_player.png", encode_png(&avatar)?),
+    ])
+    .await?;
+
+    let found = find_players_in_image(
+        vec![Player::new(
+            1,
+            format!("{base}/avatars/1/stacked_player.png"),
+        )],
+        format!("{base}/attachments/stacked_markers.png"),
+        &DownloadLimiter::new(1),
+        1,
+        &[MarkerTemplate::new(marker)],
+        SearchRegion::Whole,
+        None,
+    )
+    .await?;
+
+    assert!(found.players.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_players_outside_marker_bands_are_not_searched_for() -> Result<()> {
+    // The player only appears in a sidebar far above the marker, though in its x-range
+    let avatar = common::pattern(64)?;
+    let marker = tick_marker(24)?;
+    let mut screenshot = common::blank(320, 320, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut screenshot, Point::new(100, 10))?;
+    common::paste(&marker, &mut screenshot, Point::new(120, 240))?;
+
+    let (base, _) = serve(vec![
+        ("/attachments/sidebar.png", encode_png(&screenshot)?),
+        ("/avatars/1/sidebar_player.png", encode_png(&avatar)?),
+    ])
+    .await?;
+
+    let mut credited = Vec::new();
+    for region in [
+        SearchRegion::Whole,
+        SearchRegion::AroundMarkers { reach: 2.0 },
+    ] {
+        let found = find_players_in_image(
+            vec![Player::new(
+                1,
+                format!("{base}/avatars/1/sidebar_player.png"),
+            )],
+            format!("{base}/attachments/sidebar.png"),
+            &DownloadLimiter::new(1),
+            1,
+            &[MarkerTemplate::new(marker.clone())],
+            region,
+            None,
+        )
+        .await?;
+        credited.push(found.players.len());
+    }
+
+    assert_eq!(credited, vec![1, 0]);
+
+    Ok(())
+}
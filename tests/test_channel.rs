@@ -0,0 +1,120 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Australia::Sydney;
+use wordle_timer_bot::{
+    PastPost, TrackedChannel, channel_name_matches, is_debug_channel, is_ignored_author,
+    is_missed_completion, normalize_channel_name,
+};
+
+#[test]
+fn test_normalize_channel_name() {
+    assert_eq!(normalize_channel_name("🧩-daily-puzzles"), "dailypuzzles");
+    assert_eq!(normalize_channel_name("Daily_Puzzles 2"), "dailypuzzles2");
+}
+
+#[test]
+fn test_channel_name_matches_renamed_channels() {
+    assert!(channel_name_matches("daily-puzzles", "daily-puzzles"));
+    assert!(channel_name_matches("daily-puzzles", "🧩-daily-puzzles"));
+    assert!(channel_name_matches("Daily Puzzles", "daily-puzzles-chat"));
+    assert!(!channel_name_matches("daily-puzzles", "general"));
+    assert!(!channel_name_matches("🧩", "general"));
+}
+
+#[test]
+fn test_tracked_channel_by_id_ignores_name() {
+    let channel = TrackedChannel::Id(1234);
+    assert!(channel.matches(1234, None));
+    assert!(channel.matches(1234, Some("renamed")));
+    assert!(!channel.matches(5678, Some("daily-puzzles")));
+}
+
+#[test]
+fn test_tracked_channel_by_name_needs_name() {
+    let channel = TrackedChannel::Name("daily-puzzles".to_string());
+    assert!(channel.matches(1234, Some("🧩-daily-puzzles")));
+    assert!(!channel.matches(1234, None));
+}
+
+#[test]
+fn test_debug_channel_routing() {
+    assert!(is_debug_channel(Some("wordle-debug"), "wordle-debug"));
+    assert!(is_debug_channel(Some("wordle-debug"), "🐛-Wordle_Debug"));
+    // Unlike the tracked channel, a name merely containing it isn't the debug channel
+    assert!(!is_debug_channel(
+        Some("wordle-debug"),
+        "wordle-debug-archive"
+    ));
+    assert!(!is_debug_channel(Some("debug"), "daily-puzzles"));
+    // Without one configured, every channel is handled as usual
+    assert!(!is_debug_channel(None, "wordle-debug"));
+    assert!(!is_debug_channel(Some("🐛"), "general"));
+}
+
+#[test]
+fn test_own_and_unknown_app_messages_are_ignored() {
+    let own_id = 10;
+    let wordle_app = 20;
+
+    // The bot's own messages, whether or not flagged as a bot's
+    assert!(is_ignored_author(own_id, true, own_id, &[wordle_app]));
+    assert!(is_ignored_author(own_id, false, own_id, &[wordle_app]));
+    // Another integration reposting a screenshot
+    assert!(is_ignored_author(30, true, own_id, &[wordle_app]));
+
+    assert!(!is_ignored_author(wordle_app, true, own_id, &[wordle_app]));
+    assert!(!is_ignored_author(40, false, own_id, &[wordle_app]));
+}
+
+#[test]
+fn test_missed_completions_are_todays_finished_app_screenshots() {
+    const APP: u64 = 1211781489931452447;
+    let lookback = std::time::Duration::from_secs(12 * 60 * 60);
+    // 10:00 in Sydney
+    let now = Utc.with_ymd_and_hms(2024, 3, 5, 23, 0, 0).unwrap();
+    let finished = PastPost {
+        author_id: APP,
+        content: "alice was playing",
+        has_image: true,
+        posted_at: now - chrono::Duration::hours(2),
+    };
+    assert!(is_missed_completion(&finished, APP, now, lookback, Sydney));
+
+    let rejected = [
+        PastPost {
+            author_id: 42,
+            ..finished.clone()
+        },
+        PastPost {
+            has_image: false,
+            ..finished.clone()
+        },
+        PastPost {
+            content: "alice is playing",
+            ..finished.clone()
+        },
+        // Yesterday in Sydney, though within the lookback
+        PastPost {
+            posted_at: now - chrono::Duration::hours(11),
+            ..finished.clone()
+        },
+        PastPost {
+            posted_at: now + chrono::Duration::minutes(1),
+            ..finished.clone()
+        },
+    ];
+    for post in &rejected {
+        assert!(
+            !is_missed_completion(post, APP, now, lookback, Sydney),
+            "{post:?}"
+        );
+    }
+
+    let short_lookback = std::time::Duration::from_secs(60 * 60);
+    assert!(!is_missed_completion(
+        &finished,
+        APP,
+        now,
+        short_lookback,
+        Sydney
+    ));
+}
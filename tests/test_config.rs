@@ -0,0 +1,255 @@
+use anyhow::Result;
+use chrono_tz::{Australia::Sydney, Europe::London};
+use std::collections::HashMap;
+use wordle_timer_bot::config::{
+    Config, EmbedStyle, GridColours, GuildConfig, GuildOverrides, parse_hex_colour, parse_timezone,
+};
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::history::History;
+use wordle_timer_bot::locale::Locale;
+use wordle_timer_bot::{DEFAULT_MARKER_THRESHOLD, SearchRegion, TrackedChannel};
+
+fn defaults() -> GuildConfig {
+    GuildConfig {
+        channel: TrackedChannel::Name("daily-puzzles".to_string()),
+        timezone: Sydney,
+        embed: EmbedStyle {
+            title: "Solved!".to_string(),
+            footer: "Tracked.".to_string(),
+            colour: (87, 242, 135),
+        },
+        locale: Locale::English,
+    }
+}
+
+#[test]
+fn test_no_overrides_uses_defaults() {
+    assert_eq!(GuildOverrides::default().apply(&defaults()), defaults());
+}
+
+#[test]
+fn test_overrides_replace_only_what_they_set() {
+    let overrides = GuildOverrides {
+        timezone: Some(London),
+        embed_colour: Some((1, 2, 3)),
+        ..Default::default()
+    };
+
+    let config = overrides.apply(&defaults());
+    assert_eq!(config.timezone, London);
+    assert_eq!(config.embed.colour, (1, 2, 3));
+    assert_eq!(config.channel, defaults().channel);
+    assert_eq!(config.embed.title, defaults().embed.title);
+}
+
+#[tokio::test]
+async fn test_guild_config_lookup_falls_back_to_defaults() -> Result<()> {
+    let history = History::in_memory().await?;
+    history
+        .set_guild_overrides(
+            1,
+            &GuildOverrides {
+                channel: Some(TrackedChannel::Id(42)),
+                timezone: Some(London),
+                embed_title: Some("GG".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let configured = history.guild_config(1, &defaults()).await?;
+    assert_eq!(configured.channel, TrackedChannel::Id(42));
+    assert_eq!(configured.timezone, London);
+    assert_eq!(configured.embed.title, "GG");
+    assert_eq!(configured.embed.footer, defaults().embed.footer);
+
+    // A guild that never ran /config gets the defaults
+    assert_eq!(history.guild_config(2, &defaults()).await?, defaults());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_hex_colour() {
+    assert_eq!(parse_hex_colour("#57F287"), Some((87, 242, 135)));
+    assert_eq!(parse_hex_colour("57f287"), Some((87, 242, 135)));
+    assert_eq!(parse_hex_colour("#57F28"), None);
+    assert_eq!(parse_hex_colour("#GGGGGG"), None);
+}
+
+const SAMPLE_CONFIG: &str = r##"
+token = "file-token"
+channel_name = "wordle"
+timezone = "Europe/London"
+min_active_secs = 5.0
+max_downloads = 2
+data_dir = "/var/lib/wordle"
+
+[embed]
+title = "Done!"
+colour = "#FF8800"
+"##;
+
+#[test]
+fn test_config_from_toml() -> Result<()> {
+    let config = Config::from_toml(SAMPLE_CONFIG)?;
+
+    assert_eq!(config.token.as_deref(), Some("file-token"));
+    assert_eq!(
+        config.tracked_channel(),
+        TrackedChannel::Name("wordle".to_string())
+    );
+    assert_eq!(config.timezone, London);
+    assert_eq!(config.min_active_secs, 5.0);
+    assert_eq!(config.max_downloads, 2);
+    assert_eq!(config.database_url(), "sqlite:///var/lib/wordle/wordle.db");
+    assert_eq!(config.embed.title, "Done!");
+    assert_eq!(config.embed.colour, (255, 136, 0));
+    // Anything the file leaves out keeps its default
+    assert_eq!(config.embed.footer, EmbedStyle::default().footer);
+    assert!(!config.dry_run);
+
+    assert!(Config::from_toml("timezone = \"Mars/Olympus\"").is_err());
+    for contents in [
+        "min_active_secs = -5.0",
+        "catch_up_hours = nan",
+        "max_backoff_secs = inf",
+    ] {
+        let error = Config::from_toml(contents).unwrap_err();
+        assert!(
+            matches!(&error, WordleError::InvalidSetting { .. }),
+            "{contents}: {error:?}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_env_overrides_config_file() -> Result<()> {
+    let env = HashMap::from([
+        ("DISCORD_TOKEN", "env-token"),
+        ("DAILY_PUZZLES_CHANNEL_ID", "1234"),
+        ("DEBUG_CHANNEL_NAME", "wordle-debug"),
+        ("WORDLE_MAX_DOWNLOADS", "8"),
+        ("WORDLE_MARKER_BAND_REACH", "4"),
+        ("WORDLE_DRY_RUN", "yes"),
+    ]);
+    let mut config = Config::from_toml(SAMPLE_CONFIG)?;
+    config.apply_env(|key| env.get(key).map(|value| value.to_string()))?;
+
+    assert_eq!(config.token.as_deref(), Some("env-token"));
+    assert_eq!(config.tracked_channel(), TrackedChannel::Id(1234));
+    assert_eq!(config.debug_channel_name.as_deref(), Some("wordle-debug"));
+    assert_eq!(config.max_downloads, 8);
+    assert_eq!(
+        config.search_region(),
+        SearchRegion::AroundMarkers { reach: 4.0 }
+    );
+    assert!(config.dry_run);
+    // Settings the environment doesn't mention come from the file
+    assert_eq!(config.timezone, London);
+    assert_eq!(config.embed.title, "Done!");
+
+    let mut config = Config::default();
+    let error = config
+        .apply_env(|key| (key == "WORDLE_HTTP_PORT").then(|| "eighty".to_string()))
+        .unwrap_err();
+    assert!(
+        matches!(&error, WordleError::InvalidSetting { key, .. } if key == "WORDLE_HTTP_PORT"),
+        "{error:?}"
+    );
+    // Durations that can't be converted are refused rather than panicking later
+    for (key, value) in [
+        ("WORDLE_MIN_ACTIVE_SECS", "-1"),
+        ("WORDLE_MARKER_BAND_REACH", "NaN"),
+        ("WORDLE_DOWNLOAD_TIMEOUT_SECS", "inf"),
+    ] {
+        let error = Config::default()
+            .apply_env(|var| (var == key).then(|| value.to_string()))
+            .unwrap_err();
+        assert!(
+            matches!(&error, WordleError::InvalidSetting { key: invalid, .. } if invalid == key),
+            "{key}={value}: {error:?}"
+        );
+    }
+
+    // The usual proxy variable is used unless the bot is given its own
+    let proxies = HashMap::from([
+        ("HTTPS_PROXY", "http://corporate:3128"),
+        ("WORDLE_PROXY_URL", "http://wordle:3128"),
+    ]);
+    let mut config = Config::default();
+    config.apply_env(|key| (key == "HTTPS_PROXY").then(|| proxies[key].to_string()))?;
+    assert_eq!(config.proxy_url.as_deref(), Some("http://corporate:3128"));
+    config.apply_env(|key| proxies.get(key).map(|value| value.to_string()))?;
+    assert_eq!(config.proxy_url.as_deref(), Some("http://wordle:3128"));
+
+    // A template per screenshot theme
+    let mut config = Config::default();
+    config.apply_env(|key| {
+        (key == "WORDLE_MARKER_TEMPLATES")
+            .then(|| "./data/solved.png, ./data/solved_dark.png,".to_string())
+    })?;
+    assert_eq!(
+        config.marker_templates,
+        vec!["./data/solved.png", "./data/solved_dark.png"]
+    );
+
+    // Dark theme square colours
+    let grid = HashMap::from([
+        ("WORDLE_GRID_GREEN", "#538D4E"),
+        ("WORDLE_GRID_TOLERANCE", "16"),
+    ]);
+    let mut config = Config::default();
+    config.apply_env(|key| grid.get(key).map(|value| value.to_string()))?;
+    assert_eq!(config.grid_colours.green, (83, 141, 78));
+    assert_eq!(config.grid_colours.tolerance, 16);
+    assert_eq!(config.grid_colours.grey, GridColours::default().grey);
+    Ok(())
+}
+
+#[test]
+fn test_marker_thresholds_are_per_template() -> Result<()> {
+    let config = Config::from_toml(
+        r#"
+        marker_templates = ["./data/solved.png", "./data/solved-dark.png"]
+
+        [marker_thresholds]
+        "./data/solved-dark.png" = 0.8
+        "#,
+    )?;
+    assert_eq!(config.marker_threshold("./data/solved-dark.png"), 0.8);
+    assert_eq!(
+        config.marker_threshold("./data/solved.png"),
+        DEFAULT_MARKER_THRESHOLD
+    );
+
+    let mut config = Config::default();
+    config.apply_env(|key| {
+        (key == "WORDLE_MARKER_THRESHOLDS")
+            .then(|| "./data/solved.png=0.9, ./data/solved-dark.png=0.75".to_string())
+    })?;
+    assert_eq!(config.marker_threshold("./data/solved.png"), 0.9);
+    assert_eq!(config.marker_threshold("./data/solved-dark.png"), 0.75);
+
+    let error = config
+        .apply_env(|key| {
+            (key == "WORDLE_MARKER_THRESHOLDS").then(|| "./data/solved.png".to_string())
+        })
+        .unwrap_err();
+    assert!(
+        matches!(&error, WordleError::InvalidSetting { key, .. } if key == "WORDLE_MARKER_THRESHOLDS"),
+        "{error:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_timezone() {
+    assert_eq!(parse_timezone("Europe/London"), Some(London));
+    assert_eq!(parse_timezone(" Australia/Sydney "), Some(Sydney));
+    assert_eq!(parse_timezone("Mars/Olympus_Mons"), None);
+    assert_eq!(parse_timezone("AEST+10"), None);
+    assert_eq!(parse_timezone(""), None);
+}
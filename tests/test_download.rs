@@ -0,0 +1,179 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use wordle_timer_bot::{DownloadLimiter, clean_downloads, http_client, is_expired_download};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_downloads_never_exceed_limit() {
+    let limiter = DownloadLimiter::new(3);
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let limiter = limiter.clone();
+            let running = running.clone();
+            let peak = peak.clone();
+            tokio::spawn(async move {
+                limiter
+                    .run(async {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert_eq!(running.load(Ordering::SeqCst), 0);
+    assert!(peak.load(Ordering::SeqCst) <= 3);
+    assert!(peak.load(Ordering::SeqCst) >= 1);
+}
+
+#[tokio::test]
+async fn test_zero_limit_still_allows_downloads() {
+    let limiter = DownloadLimiter::new(0);
+    assert_eq!(limiter.run(async { 42 }).await, 42);
+}
+
+#[tokio::test]
+async fn test_client_sends_requests_through_configured_proxy() {
+    // A stand-in proxy that answers every request itself
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+    let received = tokio::spawn(async move {
+        let (mut stream, _) = proxy.accept().await.unwrap();
+        let mut request = vec![0; 1024];
+        let read = stream.read(&mut request).await.unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&request[..read]).into_owned()
+    });
+
+    let client = http_client(Some(&proxy_url), Duration::from_secs(5)).unwrap();
+    let body = client
+        .get("http://cdn.wordle.invalid/avatar.png")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert_eq!(body, "ok");
+    // Proxied requests name the whole URL rather than just the path
+    assert!(
+        received
+            .await
+            .unwrap()
+            .starts_with("GET http://cdn.wordle.invalid/avatar.png ")
+    );
+}
+
+#[test]
+fn test_invalid_proxy_url_is_rejected() {
+    assert!(http_client(Some("not a proxy"), Duration::from_secs(5)).is_err());
+    assert!(http_client(None, Duration::from_secs(5)).is_ok());
+}
+
+#[tokio::test]
+async fn test_downloads_reuse_one_connection() {
+    // Counts the connections opened, answering any number of requests on each
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/image.png", server.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = server.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut request = vec![0; 1024];
+                while stream.read(&mut request).await.is_ok_and(|read| read > 0) {
+                    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                    if stream.write_all(response).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    // Clones handed to each event handler share the same client
+    let limiter = DownloadLimiter::new(1);
+    for downloads in [limiter.clone(), limiter.clone(), limiter] {
+        let body = downloads
+            .client()
+            .get(&url)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+    }
+
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_only_old_downloaded_images_expire() {
+    let now = SystemTime::now();
+    let old = now - Duration::from_secs(48 * 60 * 60);
+    let recent = now - Duration::from_secs(60 * 60);
+    let retention = Duration::from_secs(24 * 60 * 60);
+    let protected = ["./data/solved.png", "./themes/dark_solved.png"];
+    let expired = |name: &str, is_file: bool, modified: SystemTime| {
+        is_expired_download(
+            &Path::new("./data").join(name),
+            is_file,
+            modified,
+            now,
+            retention,
+            &protected,
+        )
+    };
+
+    assert!(expired("1234.webp", true, old));
+    assert!(expired("image.png?ex=66f1&is=66ef", true, old));
+    assert!(!expired("1234.webp", true, recent));
+
+    // Templates are kept wherever they were configured from, however old
+    assert!(!expired("solved.png", true, old));
+    assert!(!expired("dark_solved.png", true, old));
+    // As is anything that isn't a downloaded image
+    assert!(!expired("wordle.db", true, old));
+    assert!(!expired("archive", false, old));
+    assert!(!expired("avatar_overrides.png", false, old));
+}
+
+#[tokio::test]
+async fn test_downloads_are_cleaned_in_the_configured_dir() {
+    let data_dir = std::env::temp_dir().join("wordle_clean_downloads_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    std::fs::create_dir_all(&data_dir).unwrap();
+    std::fs::write(data_dir.join("1234.webp"), b"image").unwrap();
+    std::fs::write(data_dir.join("wordle.db"), b"database").unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let deleted = clean_downloads(&data_dir, Duration::ZERO, &[])
+        .await
+        .unwrap();
+
+    assert_eq!(deleted, 1);
+    assert!(!data_dir.join("1234.webp").exists());
+    assert!(data_dir.join("wordle.db").exists());
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::{format_duration, parse_duration};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn test_unit_durations() -> anyhow::Result<()> {
+    assert_eq!(parse_duration("4m32s")?, ms(272_000));
+    assert_eq!(parse_duration("4M 32S")?, ms(272_000));
+    assert_eq!(parse_duration("95.5s")?, ms(95_500));
+    assert_eq!(parse_duration("1h2m3.019s")?, ms(3_723_019));
+    assert_eq!(parse_duration(" 1h 5s ")?, ms(3_605_000));
+    assert_eq!(parse_duration("3m")?, ms(180_000));
+    Ok(())
+}
+
+#[test]
+fn test_clock_durations() -> anyhow::Result<()> {
+    assert_eq!(parse_duration("4:32")?, ms(272_000));
+    assert_eq!(parse_duration("0:07.25")?, ms(7_250));
+    assert_eq!(parse_duration("1:04:32.019")?, ms(3_872_019));
+    assert_eq!(parse_duration("75:00")?, ms(4_500_000));
+    Ok(())
+}
+
+#[test]
+fn test_verbose_durations() -> anyhow::Result<()> {
+    assert_eq!(parse_duration("4 minutes and 32.019 seconds")?, ms(272_019));
+    assert_eq!(
+        parse_duration("1 hour, 1 minute and 1.000 second")?,
+        ms(3_661_000)
+    );
+    assert_eq!(parse_duration("2 hours and 5 seconds")?, ms(7_205_000));
+    assert_eq!(parse_duration("4 mins, 32 secs")?, ms(272_000));
+    Ok(())
+}
+
+#[test]
+fn test_formatted_durations_round_trip() -> anyhow::Result<()> {
+    for duration in [
+        ms(0),
+        ms(999),
+        ms(1_000),
+        ms(59_999),
+        ms(60_000),
+        ms(61_001),
+        ms(272_019),
+        ms(3_600_000),
+        ms(3_661_000),
+        ms(7_384_567),
+        ms(90_061_500),
+    ] {
+        let formatted = format_duration(duration);
+        assert_eq!(parse_duration(&formatted)?, duration, "{formatted:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_nonsense_durations_are_rejected() {
+    for value in [
+        "",
+        "soon",
+        "4",
+        "4x",
+        "m",
+        "32s4m",
+        "4m4m",
+        "4.5m",
+        "1.2345s",
+        "4:5",
+        "4:60",
+        "1:60:00",
+        "1:2:03",
+        "1:2:3:4",
+        "-4m",
+        "4m-3s",
+        "4 minutes and",
+        "and 4 minutes",
+        ", 4m",
+        "4 minutes and and 3 seconds",
+        "4 fortnights",
+        "4 minutes 2",
+    ] {
+        let error = parse_duration(value).unwrap_err();
+        assert!(
+            matches!(&error, WordleError::InvalidDuration { value: given, .. } if given == value),
+            "{value:?}: {error:?}"
+        );
+    }
+}
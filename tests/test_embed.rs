@@ -0,0 +1,242 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Australia::Sydney;
+use serenity::all::CreateEmbed;
+use std::time::Duration;
+use wordle_timer_bot::config::{EmbedStyle, GuildConfig, WebhookConfig};
+use wordle_timer_bot::game::GameState;
+use wordle_timer_bot::locale::{Locale, fill};
+use wordle_timer_bot::{
+    TrackedChannel, combined_completion_description, completion_description,
+    completion_description_in, completion_embed, completion_webhook, render_footer,
+    start_description,
+};
+
+#[test]
+fn test_completion_below_min_active_time_omits_time() {
+    let description = completion_description(
+        "alice",
+        Duration::from_millis(312),
+        Duration::from_secs(5),
+        false,
+        false,
+    );
+    assert_eq!(description, "alice finished their Wordle!");
+}
+
+#[test]
+fn test_completion_without_active_time_omits_time() {
+    let description = completion_description("alice", Duration::ZERO, Duration::ZERO, false, false);
+    assert_eq!(description, "alice finished their Wordle!");
+}
+
+#[test]
+fn test_completion_above_min_active_time_shows_time() {
+    let description = completion_description(
+        "alice",
+        Duration::from_secs(95),
+        Duration::from_secs(5),
+        false,
+        true,
+    );
+    assert_eq!(
+        description,
+        "alice finished their Wordle in **1 minute and 35.000 seconds**! (Updated)"
+    );
+}
+
+#[test]
+fn test_completion_notes_hard_mode() {
+    let description = completion_description(
+        "alice",
+        Duration::from_secs(95),
+        Duration::from_secs(5),
+        true,
+        true,
+    );
+    assert_eq!(
+        description,
+        "alice finished their Wordle in **1 minute and 35.000 seconds**! (hard mode) (Updated)"
+    );
+}
+
+#[test]
+fn test_combined_completion_embed_lists_fastest_first() -> anyhow::Result<()> {
+    let config = GuildConfig {
+        channel: TrackedChannel::Name("daily-puzzles".to_string()),
+        timezone: Sydney,
+        embed: EmbedStyle::default(),
+        locale: Locale::English,
+    };
+    let completed_at = Utc.with_ymd_and_hms(2025, 1, 14, 22, 5, 0).unwrap();
+    let finished = |secs| {
+        let mut game_state = GameState::in_timezone(Sydney);
+        game_state.mark_completed(Duration::from_secs(secs), completed_at);
+        game_state
+    };
+    let games = [
+        ("carol", finished(200)),
+        ("alice", finished(95)),
+        ("dave", finished(0)),
+        ("bob", finished(120)),
+    ];
+
+    let finishers: Vec<_> = games
+        .iter()
+        .map(|(user_name, game_state)| (*user_name, game_state.total_active_time))
+        .collect();
+    let description = combined_completion_description(&finishers, Duration::ZERO, false);
+    let embed = serde_json::to_value(completion_embed(&config, description, completed_at))?;
+
+    assert_eq!(
+        embed["description"],
+        "alice finished in **1 minute and 35.000 seconds**\n\
+         bob finished in **2 minutes and 0.000 seconds**\n\
+         carol finished in **3 minutes and 20.000 seconds**\n\
+         dave finished"
+    );
+    assert_eq!(embed["title"], config.embed.title);
+
+    Ok(())
+}
+
+#[test]
+fn test_completion_embed_in_second_locale() -> anyhow::Result<()> {
+    let config = GuildConfig {
+        channel: TrackedChannel::Name("daily-puzzles".to_string()),
+        timezone: Sydney,
+        embed: EmbedStyle::default().localized(Locale::Spanish),
+        locale: Locale::Spanish,
+    };
+    let completed_at = Utc.with_ymd_and_hms(2025, 1, 14, 22, 5, 0).unwrap();
+
+    let description = completion_description_in(
+        config.locale.strings(),
+        "alice",
+        Duration::from_secs(3_725),
+        Duration::ZERO,
+        true,
+        true,
+    );
+    let embed = serde_json::to_value(completion_embed(&config, description, completed_at))?;
+
+    assert_eq!(embed["title"], "🧩 ¡Wordle resuelto!");
+    assert_eq!(
+        embed["description"],
+        "¡alice terminó su Wordle en **1 hora, 2 minutos y 5.000 segundos**! (modo difícil) (Actualizado)"
+    );
+    assert_eq!(
+        embed["footer"]["text"],
+        "Tiempo medido por el tercer cerebro de Matt. Terminado a las 9:05 AM AEDT."
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_embed_style_is_not_localized() {
+    let style = EmbedStyle {
+        title: "Solved!".to_string(),
+        ..EmbedStyle::default()
+    };
+
+    let localized = style.localized(Locale::French);
+    assert_eq!(localized.title, "Solved!");
+    assert_eq!(localized.footer, Locale::French.strings().embed_footer);
+}
+
+#[test]
+fn test_locale_codes_ignore_region() {
+    assert_eq!("es-MX".parse(), Ok(Locale::Spanish));
+    assert_eq!("FR".parse(), Ok(Locale::French));
+    assert!("de".parse::<Locale>().is_err());
+}
+
+#[test]
+fn test_template_values_are_not_filled_in_again() {
+    assert_eq!(
+        fill("{name} in {time}", &[("name", "{time}"), ("time", "1s")]),
+        "{time} in 1s"
+    );
+}
+
+#[test]
+fn test_start_description_in_progress_and_completed() {
+    let started_at = Utc.with_ymd_and_hms(2025, 1, 14, 22, 5, 0).unwrap();
+
+    assert_eq!(
+        start_description("alice", started_at, Duration::from_secs(95), false, Sydney),
+        "alice started today's Wordle at 9:05 AM AEDT and has been playing for **1 minute and 35.000 seconds** so far."
+    );
+    assert_eq!(
+        start_description("alice", started_at, Duration::from_secs(95), true, Sydney),
+        "alice started today's Wordle at 9:05 AM AEDT and finished in **1 minute and 35.000 seconds**."
+    );
+}
+
+#[test]
+fn test_completion_webhook_payload() -> anyhow::Result<()> {
+    let config = GuildConfig {
+        channel: TrackedChannel::Name("daily-puzzles".to_string()),
+        timezone: Sydney,
+        embed: EmbedStyle::default(),
+        locale: Locale::English,
+    };
+    let webhook = WebhookConfig {
+        url: Some("https://discord.com/api/webhooks/1/token".to_string()),
+        name: Some("Wordle Timer".to_string()),
+        avatar_url: Some("https://cdn.example/wordle.png".to_string()),
+    };
+
+    let mut game_state = GameState::in_timezone(Sydney);
+    let completed_at = Utc.with_ymd_and_hms(2025, 1, 14, 22, 5, 0).unwrap();
+    game_state.mark_completed(Duration::from_secs(95), completed_at);
+    let description = completion_description(
+        "alice",
+        game_state.total_active_time,
+        Duration::ZERO,
+        false,
+        false,
+    );
+    let embed = completion_embed(&config, description, game_state.completed_at.unwrap());
+
+    let payload = serde_json::to_value(completion_webhook(&webhook, embed))?;
+    assert_eq!(payload["username"], "Wordle Timer");
+    assert_eq!(payload["avatar_url"], "https://cdn.example/wordle.png");
+    let embed = &payload["embeds"][0];
+    assert_eq!(embed["title"], config.embed.title);
+    assert_eq!(
+        embed["description"],
+        "alice finished their Wordle in **1 minute and 35.000 seconds**!"
+    );
+    assert_eq!(
+        embed["footer"]["text"],
+        format!("{} Finished at 9:05 AM AEDT.", config.embed.footer)
+    );
+
+    // Without a name or avatar the webhook's own are used
+    let payload = serde_json::to_value(completion_webhook(
+        &WebhookConfig::default(),
+        CreateEmbed::new(),
+    ))?;
+    assert!(payload.get("username").is_none());
+    assert!(payload.get("avatar_url").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_footer_template_fills_in_puzzle_and_date() {
+    // Already the 17th in Sydney, so the next day's puzzle
+    let at = Utc.with_ymd_and_hms(2024, 7, 16, 22, 0, 0).unwrap();
+    assert_eq!(
+        render_footer("Wordle {puzzle} • {date}", at, Sydney),
+        "Wordle 1,124 • 17 Jul 2024"
+    );
+    assert_eq!(
+        render_footer("Wordle {puzzle} • {date}", at, chrono_tz::UTC),
+        "Wordle 1,123 • 16 Jul 2024"
+    );
+
+    // Footers without placeholders are left alone
+    assert_eq!(render_footer("Tracked.", at, Sydney), "Tracked.");
+}
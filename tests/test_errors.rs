@@ -0,0 +1,112 @@
+// Exercises the OpenCV detection, which the stub built without it doesn't have
+#![cfg(feature = "cv")]
+
+mod common;
+
+use opencv::{core::Scalar, imgproc};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::{
+    DownloadLimiter, Player, RetryPolicy, SearchRegion, find_players_in_image, http_client,
+    is_player_in_image, load_first_frame, read_image, validate_templates,
+};
+
+#[test]
+fn test_missing_template() {
+    let error = validate_templates(&["./does/not/exist.png"]).unwrap_err();
+    assert!(
+        matches!(&error, WordleError::TemplateMissing { path } if path == "./does/not/exist.png"),
+        "{error:?}"
+    );
+    assert!(!error.is_transient());
+}
+
+#[test]
+fn test_undecodable_image() {
+    let error = read_image("./Cargo.toml").unwrap_err();
+    assert!(matches!(error, WordleError::Decode { .. }), "{error:?}");
+
+    let error = load_first_frame("./Cargo.toml").unwrap_err();
+    assert!(matches!(error, WordleError::Decode { .. }), "{error:?}");
+}
+
+#[test]
+fn test_opencv_failure() -> anyhow::Result<()> {
+    let avatar = common::pattern(32)?;
+    let mut grey_avatar = opencv::core::Mat::default();
+    imgproc::cvt_color_def(&avatar, &mut grey_avatar, imgproc::COLOR_BGR2GRAY)?;
+    let haystack = common::blank(200, 100, Scalar::all(0.0))?;
+
+    let error = is_player_in_image(&grey_avatar, &haystack).unwrap_err();
+    assert!(matches!(error, WordleError::OpenCv(_)), "{error:?}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_failure_is_transient() {
+    let players = vec![Player::new(1, "https://cdn.example/avatar.png".to_string())];
+    let error = find_players_in_image(
+        players,
+        "not a url".to_string(),
+        &DownloadLimiter::default().with_retry(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }),
+        1,
+        &[],
+        SearchRegion::Whole,
+        None,
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+        matches!(&error, WordleError::Download { url, .. } if url == "not a url"),
+        "{error:?}"
+    );
+    assert!(error.is_transient());
+}
+
+#[tokio::test]
+async fn test_hanging_download_times_out() {
+    // Accepts connections but never answers
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/screenshot.png", server.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        loop {
+            let (stream, _) = server.accept().await.unwrap();
+            open.push(stream);
+        }
+    });
+
+    let downloads = DownloadLimiter::default()
+        .with_client(http_client(None, Duration::from_millis(200)).unwrap())
+        .with_retry(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        });
+    let error = tokio::time::timeout(
+        Duration::from_secs(5),
+        find_players_in_image(
+            Vec::new(),
+            url.clone(),
+            &downloads,
+            1,
+            &[],
+            SearchRegion::Whole,
+            None,
+        ),
+    )
+    .await
+    .expect("The download should time out by itself")
+    .unwrap_err();
+
+    assert!(
+        matches!(&error, WordleError::Timeout { url: timed_out } if *timed_out == url),
+        "{error:?}"
+    );
+    assert!(error.is_transient());
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use std::time::Duration;
+use wordle_timer_bot::events::{CompletionEvent, CompletionEvents};
+use wordle_timer_bot::history::{Completion, Outcome};
+
+#[tokio::test]
+async fn test_completion_is_published_to_every_subscriber() -> Result<()> {
+    let events = CompletionEvents::new();
+    let completion = Completion {
+        user_id: 10,
+        guild_id: 1,
+        date: NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(),
+        active_time: Duration::from_secs(95),
+        guess_count: Some(4),
+        outcome: Outcome::Solved,
+        hard_mode: false,
+        display_name: None,
+    };
+    let completed_at = Utc::now();
+    // Nobody is listening yet, which isn't an error
+    assert_eq!(
+        events.publish(CompletionEvent::new(&completion, "alice", completed_at)),
+        0
+    );
+
+    let mut poster = events.subscribe();
+    let mut forwarder = events.subscribe();
+    assert_eq!(
+        events.publish(CompletionEvent::new(&completion, "alice", completed_at)),
+        2
+    );
+
+    let expected = CompletionEvent {
+        user_id: 10,
+        username: "alice".to_string(),
+        guild_id: 1,
+        active_time: Duration::from_secs(95),
+        completed_at,
+        guess_count: Some(4),
+        outcome: Outcome::Solved,
+    };
+    assert_eq!(poster.recv().await?, expected);
+    assert_eq!(forwarder.recv().await?, expected);
+    // Only events published after subscribing arrive
+    assert!(poster.try_recv().is_err());
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDate;
+use std::time::Duration;
+use wordle_timer_bot::history::{Completion, Outcome, completions_to_csv, resolve_display_name};
+
+const HEADER: &str = "username,date,active_time_ms,guess_count,outcome,display_name\n";
+
+fn completion(day: u32, ms: u64, guess_count: Option<u32>, outcome: Outcome) -> Completion {
+    Completion {
+        user_id: 10,
+        guild_id: 1,
+        date: NaiveDate::from_ymd_opt(2024, 6, day).unwrap(),
+        active_time: Duration::from_millis(ms),
+        guess_count,
+        outcome,
+        hard_mode: false,
+        display_name: None,
+    }
+}
+
+#[test]
+fn test_empty_history_is_header_only() {
+    assert_eq!(completions_to_csv("matt", &[]), HEADER);
+}
+
+#[test]
+fn test_rows_in_order() {
+    let csv = completions_to_csv(
+        "matt",
+        &[
+            completion(1, 61_500, Some(4), Outcome::Solved),
+            completion(2, 300_000, None, Outcome::Failed),
+        ],
+    );
+
+    assert_eq!(
+        csv,
+        format!("{HEADER}matt,2024-06-01,61500,4,solved,\nmatt,2024-06-02,300000,,failed,\n")
+    );
+}
+
+#[test]
+fn test_username_is_escaped() {
+    let games = [completion(1, 1000, Some(3), Outcome::Solved)];
+
+    assert_eq!(
+        completions_to_csv("smith, \"bob\"", &games),
+        format!("{HEADER}\"smith, \"\"bob\"\"\",2024-06-01,1000,3,solved,\n")
+    );
+    assert!(completions_to_csv("line\nbreak", &games).contains("\"line\nbreak\""));
+}
+
+#[test]
+fn test_rows_keep_the_name_at_the_time() {
+    let games = [
+        Completion {
+            display_name: Some("Matt, before".to_string()),
+            ..completion(1, 1000, Some(3), Outcome::Solved)
+        },
+        Completion {
+            display_name: Some("Matthew".to_string()),
+            ..completion(2, 2000, Some(4), Outcome::Solved)
+        },
+    ];
+
+    assert_eq!(
+        completions_to_csv("matt", &games),
+        format!(
+            "{HEADER}matt,2024-06-01,1000,3,solved,\"Matt, before\"\nmatt,2024-06-02,2000,4,solved,Matthew\n"
+        )
+    );
+}
+
+#[test]
+fn test_current_name_wins_over_stored() {
+    // A member who has since renamed shows by their current name
+    assert_eq!(
+        resolve_display_name(Some("Matthew"), Some("Matt"), "matt_123"),
+        "Matthew"
+    );
+    // One who left falls back to the name stored with their games
+    assert_eq!(resolve_display_name(None, Some("Matt"), "matt_123"), "Matt");
+    assert_eq!(resolve_display_name(None, None, "matt_123"), "matt_123");
+}
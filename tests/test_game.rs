@@ -0,0 +1,406 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::{Australia::Sydney, Europe::London};
+use serenity::all::ReactionType;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wordle_timer_bot::game::{
+    GameKey, GameState, GameSummary, completed_by_reaction, is_players_game, roll_over_day,
+    set_guild_timezone, summarize_games,
+};
+use wordle_timer_bot::{
+    format_completion_time, is_completion_reaction, is_unknown_target, is_wordle_activity,
+};
+
+#[test]
+fn test_completion_time_survives_update() {
+    let first = Utc.with_ymd_and_hms(2024, 6, 12, 1, 30, 0).unwrap();
+    let redetected = Utc.with_ymd_and_hms(2024, 6, 12, 1, 45, 0).unwrap();
+
+    let mut game_state = GameState::new();
+    assert_eq!(game_state.completed_at, None);
+
+    assert_eq!(
+        game_state.mark_completed(Duration::from_secs(90), first),
+        first
+    );
+    // The update path re-detects the completion later with a new total
+    assert_eq!(
+        game_state.mark_completed(Duration::from_secs(120), redetected),
+        first
+    );
+
+    assert_eq!(game_state.completed_at, Some(first));
+    assert_eq!(game_state.total_active_time, Duration::from_secs(120));
+    assert!(game_state.completed);
+}
+
+#[test]
+fn test_completion_time_is_shown_in_local_time() {
+    // 01:30 UTC in June is 11:30 AEST
+    let completed_at = Utc.with_ymd_and_hms(2024, 6, 12, 1, 30, 0).unwrap();
+    assert_eq!(
+        format_completion_time(completed_at, Sydney),
+        "11:30 AM AEST"
+    );
+}
+
+#[test]
+fn test_puzzle_date_follows_timezone() {
+    // 20:00 UTC on the 11th is already the 12th in Sydney
+    let created_at = Utc.with_ymd_and_hms(2024, 6, 11, 20, 0, 0).unwrap();
+
+    let sydney = GameState::in_timezone_at(Sydney, created_at);
+    let utc = GameState::in_timezone_at(chrono_tz::UTC, created_at);
+
+    assert_eq!(sydney.puzzle_date.to_string(), "2024-06-12");
+    assert_eq!(utc.puzzle_date.to_string(), "2024-06-11");
+}
+
+#[test]
+fn test_games_either_side_of_midnight_belong_to_their_own_puzzle() {
+    // 23:59 on the 11th and 00:01 on the 12th in Sydney
+    let before = GameState::in_timezone_at(
+        Sydney,
+        Utc.with_ymd_and_hms(2024, 6, 11, 13, 59, 0).unwrap(),
+    );
+    let after =
+        GameState::in_timezone_at(Sydney, Utc.with_ymd_and_hms(2024, 6, 11, 14, 1, 0).unwrap());
+    assert_eq!(before.puzzle_date.to_string(), "2024-06-11");
+    assert_eq!(after.puzzle_date.to_string(), "2024-06-12");
+
+    let at_00_02 = Utc.with_ymd_and_hms(2024, 6, 11, 14, 2, 0).unwrap();
+    assert!(!before.is_current_at(at_00_02, Duration::ZERO));
+    assert!(after.is_current_at(at_00_02, Duration::ZERO));
+
+    // The next night the 00:01 game is the one left behind
+    let next_midnight = Utc.with_ymd_and_hms(2024, 6, 12, 14, 0, 30).unwrap();
+    assert!(!after.is_current_at(next_midnight, Duration::ZERO));
+}
+
+#[test]
+fn test_game_started_before_midnight_carries_over() {
+    let grace = Duration::from_secs(15 * 60);
+    // 23:58 on the 11th in Sydney
+    let mut game_state = GameState::in_timezone_at(
+        Sydney,
+        Utc.with_ymd_and_hms(2024, 6, 11, 13, 58, 0).unwrap(),
+    );
+    let just_after_midnight = Utc.with_ymd_and_hms(2024, 6, 11, 14, 3, 0).unwrap();
+
+    assert!(!game_state.is_current_at(just_after_midnight, Duration::ZERO));
+    assert!(game_state.is_current_at(just_after_midnight, grace));
+    // It still counts as the 11th's puzzle when finished after midnight
+    game_state.mark_completed(Duration::from_secs(300), just_after_midnight);
+    assert_eq!(game_state.puzzle_date.to_string(), "2024-06-11");
+    // Once finished it is reset like any other game from a previous day
+    assert!(!game_state.is_current_at(just_after_midnight, grace));
+}
+
+#[test]
+fn test_grace_only_covers_games_started_close_to_midnight() {
+    let grace = Duration::from_secs(15 * 60);
+
+    // Started at 23:30, well before the grace window
+    let early = GameState::in_timezone_at(
+        Sydney,
+        Utc.with_ymd_and_hms(2024, 6, 11, 13, 30, 0).unwrap(),
+    );
+    assert!(!early.is_current_at(Utc.with_ymd_and_hms(2024, 6, 11, 14, 3, 0).unwrap(), grace));
+
+    // Started at 23:58 but still unfinished at 00:20, after the grace window
+    let late = GameState::in_timezone_at(
+        Sydney,
+        Utc.with_ymd_and_hms(2024, 6, 11, 13, 58, 0).unwrap(),
+    );
+    assert!(!late.is_current_at(Utc.with_ymd_and_hms(2024, 6, 11, 14, 20, 0).unwrap(), grace));
+}
+
+#[test]
+fn test_detections_within_cooldown_post_once() {
+    let cooldown = Duration::from_secs(30);
+    let start = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.mark_completed(Duration::from_secs(90), Utc::now());
+
+    // Posting as the completion handler does, skipping detections during the cooldown
+    let mut posts = 0;
+    for detected_at in [start, start + Duration::from_secs(5)] {
+        if !game_state.in_post_cooldown(detected_at, cooldown) {
+            posts += 1;
+            game_state.last_posted_at = Some(detected_at);
+        }
+    }
+    assert_eq!(posts, 1);
+
+    // Once it has passed, a re-detection may update the message again
+    assert!(!game_state.in_post_cooldown(start + Duration::from_secs(31), cooldown));
+    // With no cooldown every detection posts
+    assert!(!game_state.in_post_cooldown(start + Duration::from_secs(5), Duration::ZERO));
+}
+
+#[test]
+fn test_flapping_activity_is_counted_once() {
+    let start = Instant::now();
+    let at = |secs| start + Duration::from_secs(secs);
+
+    let mut game_state = GameState::new();
+    game_state.active_since = None;
+
+    assert!(game_state.resume(at(0)));
+    // A duplicate start doesn't restart the attempt
+    assert!(!game_state.resume(at(10)));
+    game_state.pause(at(30));
+    // Nor does a duplicate stop count the time again
+    game_state.pause(at(40));
+    assert_eq!(game_state.total_active_time, Duration::from_secs(30));
+    assert_eq!(game_state.elapsed_at(at(50)), Duration::from_secs(30));
+
+    assert!(game_state.resume(at(60)));
+    assert_eq!(game_state.elapsed_at(at(70)), Duration::from_secs(40));
+    // A stop from before the attempt started arrived late, adding nothing
+    game_state.pause(at(55));
+    assert_eq!(game_state.total_active_time, Duration::from_secs(30));
+    assert_eq!(game_state.active_since, None);
+}
+
+#[test]
+fn test_completion_ends_the_attempt() {
+    let start = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.active_since = Some(start);
+
+    game_state.pause(start + Duration::from_secs(45));
+    game_state.mark_completed(game_state.total_active_time, Utc::now());
+
+    // Neither a late start event nor re-detecting the completion can add to the time
+    assert!(!game_state.resume(start + Duration::from_secs(100)));
+    game_state.pause(start + Duration::from_secs(300));
+    assert_eq!(
+        game_state.elapsed_at(start + Duration::from_secs(300)),
+        Duration::from_secs(45)
+    );
+}
+
+#[test]
+fn test_deleted_completion_message_is_forgotten() {
+    let mut game_state = GameState::new();
+    game_state.completion_msg_id = Some(MessageId::new(42));
+    game_state.completion_channel_id = Some(ChannelId::new(7));
+
+    // The edit failed because the message is gone, so a new one gets sent
+    game_state.forget_completion_message();
+    assert_eq!(game_state.completion_msg_id, None);
+    assert_eq!(game_state.completion_channel_id, None);
+}
+
+#[test]
+fn test_unknown_target_errors() {
+    assert!(is_unknown_target(404, 0));
+    assert!(is_unknown_target(400, 10003)); // Unknown channel
+    assert!(is_unknown_target(400, 10008)); // Unknown message
+    assert!(!is_unknown_target(403, 50013)); // Missing permissions
+    assert!(!is_unknown_target(500, 0));
+}
+
+#[test]
+fn test_same_player_in_two_guilds_tracks_independently() {
+    let (first, second) = (GuildId::new(1), GuildId::new(2));
+    let (alice, bob) = (UserId::new(100), UserId::new(101));
+    let mut games: HashMap<GameKey, GameState> = HashMap::new();
+    games.insert((first, MessageId::new(10), alice), GameState::new());
+    games.insert((second, MessageId::new(20), alice), GameState::new());
+
+    // Finishing in one guild leaves the other's game running
+    for (key, game) in games.iter_mut() {
+        if is_players_game(key, Some(first), alice) {
+            game.mark_completed(Duration::from_secs(60), Utc::now());
+        }
+    }
+
+    let completed: Vec<_> = games
+        .iter()
+        .map(|((guild, _, _), game)| (*guild, game.completed))
+        .collect();
+    assert!(completed.contains(&(first, true)));
+    assert!(completed.contains(&(second, false)));
+
+    // Outside a guild no game is anyone's
+    assert!(games.keys().all(|key| !is_players_game(key, None, alice)));
+    assert!(
+        games
+            .keys()
+            .all(|key| !is_players_game(key, Some(first), bob))
+    );
+}
+
+#[test]
+fn test_members_sharing_a_nickname_keep_their_own_games() {
+    let guild = GuildId::new(1);
+    let (alice, namesake) = (UserId::new(100), UserId::new(101));
+    let mut games: HashMap<GameKey, GameState> = HashMap::new();
+    for user_id in [alice, namesake] {
+        let mut game_state = GameState::new();
+        game_state.player_name = "ally".to_string();
+        games.insert((guild, MessageId::new(10), user_id), game_state);
+    }
+
+    // Members sharing a nickname each keep their own game
+    let allys: Vec<_> = games
+        .keys()
+        .filter(|key| is_players_game(key, Some(guild), alice))
+        .collect();
+    assert_eq!(allys, vec![&(guild, MessageId::new(10), alice)]);
+}
+
+#[test]
+fn test_reaction_finishes_the_reactors_own_game() {
+    let guild = GuildId::new(1);
+    let (message, other_message) = (MessageId::new(10), MessageId::new(11));
+    let start = Instant::now();
+    let mut games: HashMap<GameKey, GameState> = HashMap::new();
+    let (alice, bob) = (UserId::new(100), UserId::new(101));
+    for (msg, user_id) in [(message, alice), (message, bob), (other_message, alice)] {
+        let mut game_state = GameState::new();
+        game_state.active_since = Some(start);
+        games.insert((guild, msg, user_id), game_state);
+    }
+
+    // Alice reacts to the first message, as the handler does: pause, then finish
+    let finished_at = start + Duration::from_secs(75);
+    for (key, game) in games.iter_mut() {
+        if completed_by_reaction(key, guild, message, alice, false) {
+            game.pause(finished_at);
+            let total = game.total_active_time;
+            game.mark_completed(total, Utc::now());
+        }
+    }
+
+    let alice_game = &games[&(guild, message, alice)];
+    assert!(alice_game.completed);
+    assert_eq!(alice_game.total_active_time, Duration::from_secs(75));
+    assert_eq!(alice_game.active_since, None);
+    // Bob's game, and Alice's game on another message, are left running
+    assert!(!games[&(guild, message, bob)].completed);
+    assert!(!games[&(guild, other_message, alice)].completed);
+}
+
+#[test]
+fn test_only_players_and_moderators_can_react_games_finished() {
+    let guild = GuildId::new(1);
+    let message = MessageId::new(10);
+    let key = (guild, message, UserId::new(100));
+    let carol = UserId::new(102);
+
+    assert!(!completed_by_reaction(&key, guild, message, carol, false));
+    assert!(completed_by_reaction(&key, guild, message, carol, true));
+    // Not even moderators finish games on other messages or in other guilds
+    assert!(!completed_by_reaction(
+        &key,
+        guild,
+        MessageId::new(11),
+        carol,
+        true
+    ));
+    assert!(!completed_by_reaction(
+        &key,
+        GuildId::new(2),
+        message,
+        carol,
+        true
+    ));
+}
+
+#[test]
+fn test_completion_reaction_matches_configured_emoji() {
+    let unicode = |emoji: &str| ReactionType::Unicode(emoji.to_string());
+
+    assert!(is_completion_reaction("✅", &unicode("✅")));
+    assert!(is_completion_reaction("✔", &unicode("✔\u{FE0F}")));
+    assert!(!is_completion_reaction("✅", &unicode("❌")));
+}
+
+#[test]
+fn test_summarize_games() {
+    // Noon in Sydney
+    let now = Utc.with_ymd_and_hms(2024, 6, 12, 2, 0, 0).unwrap();
+    let today = Utc.with_ymd_and_hms(2024, 6, 11, 23, 0, 0).unwrap();
+    let yesterday = Utc.with_ymd_and_hms(2024, 6, 10, 23, 0, 0).unwrap();
+    let game = |created_at, completed_at: Option<_>| {
+        let mut game_state = GameState::in_timezone_at(Sydney, created_at);
+        if let Some(completed_at) = completed_at {
+            game_state.mark_completed(Duration::from_secs(60), completed_at);
+        }
+        game_state
+    };
+    let last_completion = Utc.with_ymd_and_hms(2024, 6, 12, 1, 0, 0).unwrap();
+    let games = [
+        game(today, None),
+        game(today, Some(last_completion)),
+        game(today, Some(today)),
+        game(yesterday, None),
+        game(yesterday, Some(yesterday)),
+    ];
+
+    assert_eq!(
+        summarize_games(&games, now),
+        GameSummary {
+            active: 1,
+            completed: 2,
+            stale: 2,
+            last_completed_at: Some(last_completion),
+        }
+    );
+    assert_eq!(summarize_games([], now), GameSummary::default());
+}
+
+#[test]
+fn test_guild_timezone_change_applies_to_tracked_games() {
+    let (guild, other_guild) = (GuildId::new(1), GuildId::new(2));
+    // 23:30 in Sydney, so the puzzle day is nearly over there but not in London
+    let started = Utc.with_ymd_and_hms(2024, 6, 3, 13, 30, 0).unwrap();
+    let mut games: HashMap<GameKey, GameState> = HashMap::from([
+        (
+            (guild, MessageId::new(10), UserId::new(100)),
+            GameState::in_timezone_at(Sydney, started),
+        ),
+        (
+            (other_guild, MessageId::new(11), UserId::new(101)),
+            GameState::in_timezone_at(Sydney, started),
+        ),
+    ]);
+
+    assert_eq!(set_guild_timezone(&mut games, guild, London), 1);
+
+    let moved = &games[&(guild, MessageId::new(10), UserId::new(100))];
+    assert_eq!(moved.timezone, London);
+    // Still the puzzle it was started on, now lasting until midnight in London
+    assert_eq!(moved.puzzle_date, started.date_naive());
+    let after_sydney_midnight = started + chrono::Duration::hours(1);
+    assert!(moved.is_current_at(after_sydney_midnight, Duration::ZERO));
+    let untouched = &games[&(other_guild, MessageId::new(11), UserId::new(101))];
+    assert_eq!(untouched.timezone, Sydney);
+    assert!(!untouched.is_current_at(after_sydney_midnight, Duration::ZERO));
+}
+
+#[test]
+fn test_unfinished_game_is_not_kept_on_new_day() {
+    let yesterday = Utc.with_ymd_and_hms(2024, 6, 2, 22, 0, 0).unwrap();
+    let mut game_state = GameState::in_timezone_at(Sydney, yesterday);
+    game_state.total_active_time = Duration::from_secs(40);
+
+    let fresh = GameState::in_timezone_at(Sydney, yesterday + chrono::Duration::days(1));
+    assert!(roll_over_day(&mut game_state, fresh).is_none());
+    assert!(game_state.total_active_time.is_zero());
+}
+
+#[test]
+fn test_wordle_activity_matches_configured_names_in_any_case() {
+    let names = vec!["Wordle".to_string(), "NYT Games".to_string()];
+    for activity in ["Wordle", "wordle", "WORDLE", " Wordle ", "nyt games"] {
+        assert!(is_wordle_activity(&names, activity), "{activity}");
+    }
+    for activity in ["Wordle Unlimited", "Spelling Bee", ""] {
+        assert!(!is_wordle_activity(&names, activity), "{activity}");
+    }
+}
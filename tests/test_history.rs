@@ -0,0 +1,364 @@
+use anyhow::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::Australia::Sydney;
+use std::time::Duration;
+use wordle_timer_bot::game::{GameState, roll_over_day};
+use wordle_timer_bot::history::{
+    Completion, History, Outcome, leaderboard_page, percentile_report, personal_best,
+};
+
+const GUILD: u64 = 1;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+}
+
+fn completion(user_id: u64, day: u32, secs: u64, outcome: Outcome) -> Completion {
+    Completion {
+        user_id,
+        guild_id: GUILD,
+        date: date(day),
+        active_time: Duration::from_secs(secs),
+        guess_count: Some(4),
+        outcome,
+        hard_mode: false,
+        display_name: None,
+    }
+}
+
+#[tokio::test]
+async fn test_user_average_ignores_failures() -> Result<()> {
+    let history = History::in_memory().await?;
+    history
+        .record_completion(&completion(10, 1, 60, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&completion(10, 2, 120, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&completion(10, 3, 900, Outcome::Failed))
+        .await?;
+
+    assert_eq!(
+        history.user_average(GUILD, 10).await?,
+        Some(Duration::from_secs(90))
+    );
+    assert_eq!(history.user_average(GUILD, 11).await?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_percentile_report_interpolates_between_ranks() {
+    let times: Vec<Duration> = [40, 10, 30, 20, 50, 60, 70, 80, 90, 100]
+        .into_iter()
+        .map(Duration::from_secs)
+        .collect();
+
+    let report = percentile_report(&times, &[0.0, 50.0, 90.0, 100.0]);
+
+    assert_eq!(report.samples, 10);
+    assert_eq!(report.fastest, Some(Duration::from_secs(10)));
+    assert_eq!(report.mean, Some(Duration::from_secs(55)));
+    assert_eq!(
+        report.percentiles,
+        vec![
+            (0.0, Duration::from_secs(10)),
+            (50.0, Duration::from_secs(55)),
+            (90.0, Duration::from_secs(91)),
+            (100.0, Duration::from_secs(100)),
+        ]
+    );
+}
+
+#[test]
+fn test_percentile_report_small_samples() {
+    let empty = percentile_report(&[], &[50.0]);
+    assert_eq!(empty.samples, 0);
+    assert_eq!(empty.fastest, None);
+    assert!(empty.percentiles.is_empty());
+
+    let single = percentile_report(&[Duration::from_secs(42)], &[50.0, 90.0, 150.0]);
+    assert_eq!(
+        single.percentiles,
+        vec![
+            (50.0, Duration::from_secs(42)),
+            (90.0, Duration::from_secs(42)),
+            (150.0, Duration::from_secs(42)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_user_time_percentiles_ignores_failures() -> Result<()> {
+    let history = History::in_memory().await?;
+    for (day, secs) in [(1, 60), (2, 120), (3, 90)] {
+        history
+            .record_completion(&completion(10, day, secs, Outcome::Solved))
+            .await?;
+    }
+    history
+        .record_completion(&completion(10, 4, 900, Outcome::Failed))
+        .await?;
+
+    let report = history.user_time_percentiles(GUILD, 10, &[50.0]).await?;
+    assert_eq!(report.samples, 3);
+    assert_eq!(report.percentiles, vec![(50.0, Duration::from_secs(90))]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaderboard_for_date_is_fastest_first() -> Result<()> {
+    let history = History::in_memory().await?;
+    history
+        .record_completion(&completion(10, 1, 200, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&completion(11, 1, 100, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&completion(12, 2, 50, Outcome::Solved))
+        .await?;
+
+    // Re-recording the same day replaces the earlier time
+    history
+        .record_completion(&completion(10, 1, 80, Outcome::Solved))
+        .await?;
+
+    assert_eq!(
+        history.leaderboard_for_date(GUILD, date(1)).await?,
+        vec![
+            (10, Duration::from_secs(80)),
+            (11, Duration::from_secs(100))
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streak_for_user() -> Result<()> {
+    let history = History::in_memory().await?;
+    for day in [1, 3, 4, 5] {
+        history
+            .record_completion(&completion(10, day, 60, Outcome::Solved))
+            .await?;
+    }
+
+    assert_eq!(history.streak_for_user(GUILD, 10, date(5)).await?, 3);
+    // Not having played yet today doesn't break the streak
+    assert_eq!(history.streak_for_user(GUILD, 10, date(6)).await?, 3);
+    assert_eq!(history.streak_for_user(GUILD, 10, date(7)).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_daily_thread_is_replaced() -> Result<()> {
+    let history = History::in_memory().await?;
+    assert_eq!(history.daily_thread(5, date(1)).await?, None);
+
+    history.set_daily_thread(5, date(1), 100).await?;
+    // A recreated thread replaces the deleted one
+    history.set_daily_thread(5, date(1), 200).await?;
+
+    assert_eq!(history.daily_thread(5, date(1)).await?, Some(200));
+    assert_eq!(history.daily_thread(5, date(2)).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_all_time_leaderboard_ranks_best_solves() -> Result<()> {
+    let history = History::in_memory().await?;
+    for (user_id, day, secs, outcome) in [
+        (10, 1, 200, Outcome::Solved),
+        (10, 2, 90, Outcome::Solved),
+        (11, 1, 120, Outcome::Solved),
+        (12, 1, 30, Outcome::Failed), // Failures never rank
+        (13, 3, 90, Outcome::Solved),
+    ] {
+        history
+            .record_completion(&completion(user_id, day, secs, outcome))
+            .await?;
+    }
+
+    assert_eq!(
+        history.all_time_leaderboard(GUILD).await?,
+        vec![
+            (10, Duration::from_secs(90)),
+            (13, Duration::from_secs(90)),
+            (11, Duration::from_secs(120)),
+        ]
+    );
+    assert!(history.all_time_leaderboard(GUILD + 1).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_recent_players_are_latest_first() -> Result<()> {
+    let history = History::in_memory().await?;
+    for (user_id, day, outcome) in [
+        (10, 1, Outcome::Solved),
+        (11, 3, Outcome::Failed), // Failures still count as playing
+        (12, 2, Outcome::Solved),
+        (10, 4, Outcome::Solved),
+    ] {
+        history
+            .record_completion(&completion(user_id, day, 60, outcome))
+            .await?;
+    }
+
+    assert_eq!(history.recent_players(GUILD, 10).await?, vec![10, 11, 12]);
+    assert_eq!(history.recent_players(GUILD, 2).await?, vec![10, 11]);
+    assert!(history.recent_players(GUILD + 1, 10).await?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_leaderboard_pages() {
+    let entries: Vec<_> = (1..=23)
+        .map(|user_id| (user_id, Duration::from_secs(user_id * 10)))
+        .collect();
+
+    let first = leaderboard_page(&entries, 0);
+    assert_eq!((first.page, first.pages), (0, 3));
+    assert_eq!(first.text.lines().count(), 10);
+    assert_eq!(
+        first.text.lines().next(),
+        Some("**1.** <@1> 10.000 seconds")
+    );
+
+    // Past the end shows the last page, which holds the remainder
+    let last = leaderboard_page(&entries, 7);
+    assert_eq!(last.page, 2);
+    assert_eq!(last.text.lines().count(), 3);
+    assert!(last.text.starts_with("**21.** <@21>"));
+
+    let empty = leaderboard_page(&[], 0);
+    assert_eq!((empty.page, empty.pages), (0, 1));
+    assert_eq!(empty.text, "No solves recorded yet.");
+}
+
+#[tokio::test]
+async fn test_completions_keep_hard_mode() -> Result<()> {
+    let history = History::in_memory().await?;
+    history
+        .record_completion(&completion(10, 1, 60, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&Completion {
+            hard_mode: true,
+            ..completion(10, 2, 60, Outcome::Solved)
+        })
+        .await?;
+
+    let hard_mode: Vec<bool> = history
+        .completions_for_user(GUILD, 10)
+        .await?
+        .iter()
+        .map(|completion| completion.hard_mode)
+        .collect();
+    assert_eq!(hard_mode, vec![false, true]);
+
+    Ok(())
+}
+
+#[test]
+fn test_personal_best_is_fastest_solve() {
+    let completions = [
+        completion(10, 1, 95, Outcome::Solved),
+        completion(10, 2, 40, Outcome::Failed), // Quick, but not a solve
+        completion(10, 3, 61, Outcome::Solved),
+        completion(10, 4, 61, Outcome::Solved), // Only matched it
+    ];
+
+    let best = personal_best(&completions).unwrap();
+    assert_eq!(best.date, date(3));
+    assert_eq!(best.active_time, Duration::from_secs(61));
+    assert_eq!(best.guess_count, Some(4));
+
+    assert_eq!(personal_best(&completions[1..2]), None);
+    assert_eq!(personal_best(&[]), None);
+}
+
+#[tokio::test]
+async fn test_personal_best_is_per_user() -> Result<()> {
+    let history = History::in_memory().await?;
+    history
+        .record_completion(&completion(10, 1, 95, Outcome::Solved))
+        .await?;
+    history
+        .record_completion(&completion(11, 1, 30, Outcome::Solved))
+        .await?;
+
+    let best = history.personal_best(GUILD, 10).await?.unwrap();
+    assert_eq!(best.active_time, Duration::from_secs(95));
+    assert_eq!(history.personal_best(GUILD, 12).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_latest_display_name_survives_updates_without_one() -> Result<()> {
+    let history = History::in_memory().await?;
+    assert_eq!(history.latest_display_name(GUILD, 10).await?, None);
+
+    history
+        .record_completion(&Completion {
+            display_name: Some("Matt".to_string()),
+            ..completion(10, 1, 60, Outcome::Solved)
+        })
+        .await?;
+    history
+        .record_completion(&Completion {
+            display_name: Some("Matthew".to_string()),
+            ..completion(10, 2, 60, Outcome::Solved)
+        })
+        .await?;
+    // Re-recording a day without a name, e.g. from an import, keeps the one it had
+    history
+        .record_completion(&completion(10, 2, 50, Outcome::Solved))
+        .await?;
+
+    assert_eq!(
+        history.latest_display_name(GUILD, 10).await?,
+        Some("Matthew".to_string())
+    );
+    let names: Vec<Option<String>> = history
+        .completions_for_user(GUILD, 10)
+        .await?
+        .into_iter()
+        .map(|completion| completion.display_name)
+        .collect();
+    assert_eq!(
+        names,
+        vec![Some("Matt".to_string()), Some("Matthew".to_string())]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_finished_game_is_archived_before_new_day() -> Result<()> {
+    let history = History::in_memory().await?;
+    let yesterday = Utc.with_ymd_and_hms(2024, 6, 2, 22, 0, 0).unwrap(); // 3 June in Sydney
+    let mut game_state = GameState::in_timezone_at(Sydney, yesterday);
+    game_state.mark_completed(Duration::from_secs(95), yesterday);
+
+    let fresh = GameState::in_timezone_at(Sydney, yesterday + chrono::Duration::days(1));
+    let previous = roll_over_day(&mut game_state, fresh).expect("the game was finished");
+    history
+        .record_completion(&Completion::from_game(10, GUILD, &previous, None))
+        .await?;
+
+    assert!(!game_state.completed);
+    assert!(game_state.total_active_time.is_zero());
+    let recorded = history.completions_for_user(GUILD, 10).await?;
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].date, date(3));
+    assert_eq!(recorded[0].active_time, Duration::from_secs(95));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::time::Duration;
+use wordle_timer_bot::history::{Completion, History, Outcome, RejectedRow, parse_history_csv};
+
+const GUILD: u64 = 1;
+
+fn completion(
+    user_id: u64,
+    day: u32,
+    ms: u64,
+    guess_count: Option<u32>,
+    outcome: Outcome,
+) -> Completion {
+    Completion {
+        user_id,
+        guild_id: GUILD,
+        date: NaiveDate::from_ymd_opt(2024, 6, day).unwrap(),
+        active_time: Duration::from_millis(ms),
+        guess_count,
+        outcome,
+        hard_mode: false,
+        display_name: None,
+    }
+}
+
+#[test]
+fn test_rows_with_and_without_header() {
+    let rows = "10,2024-06-01,61500,4,solved\n20,2024-06-02,300000,,failed\n";
+    let expected = vec![
+        completion(10, 1, 61_500, Some(4), Outcome::Solved),
+        completion(20, 2, 300_000, None, Outcome::Failed),
+    ];
+
+    assert_eq!(parse_history_csv(rows, GUILD), (expected.clone(), vec![]));
+    let with_header = format!("user_id,date,active_time_ms,guess_count,outcome\r\n{rows}\n");
+    assert_eq!(parse_history_csv(&with_header, GUILD), (expected, vec![]));
+}
+
+#[test]
+fn test_malformed_rows_are_reported() {
+    let csv = "user_id,date,active_time_ms,guess_count,outcome
+10,2024-06-01,61500,4,Solved
+matt,2024-06-01,61500,4,solved
+10,01/06/2024,61500,4,solved
+10,2024-06-02,-5,4,solved
+10,2024-06-03,61500,7,solved
+10,2024-06-04,61500,4,won
+10,2024-06-05,61500
+";
+    let (completions, rejected) = parse_history_csv(csv, GUILD);
+
+    assert_eq!(
+        completions,
+        vec![completion(10, 1, 61_500, Some(4), Outcome::Solved)]
+    );
+    let lines: Vec<usize> = rejected.iter().map(|row| row.line).collect();
+    assert_eq!(lines, vec![3, 4, 5, 6, 7, 8]);
+    assert_eq!(
+        rejected[0],
+        RejectedRow {
+            line: 3,
+            reason: "'matt' isn't a user ID".to_string(),
+        }
+    );
+    assert_eq!(rejected[5].reason, "expected 5 columns, found 3");
+}
+
+#[tokio::test]
+async fn test_import_skips_bad_rows_and_records_the_rest() -> Result<()> {
+    let history = History::in_memory().await?;
+    let report = history
+        .import_csv(
+            GUILD,
+            "10,2024-06-01,60000,3,solved\n10,2024-06-02,oops,3,solved\n10,2024-06-03,120000,5,solved\n",
+        )
+        .await?;
+
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.rejected.len(), 1);
+    assert_eq!(report.rejected[0].line, 2);
+    assert_eq!(
+        history.user_average(GUILD, 10).await?,
+        Some(Duration::from_secs(90))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_history_from_file() -> Result<()> {
+    let path = std::env::temp_dir().join("wordle_import_test.csv");
+    std::fs::write(
+        &path,
+        "user_id,date,active_time_ms,guess_count,outcome\n10,2024-06-01,60000,3,solved\n",
+    )?;
+
+    let history = History::in_memory().await?;
+    assert_eq!(history.import_history(GUILD, &path).await?, 1);
+    assert_eq!(history.completions_for_user(GUILD, 10).await?.len(), 1);
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+// Exercises the OpenCV detection, which the stub built without it doesn't have
+#![cfg(feature = "cv")]
+
+mod common;
+
+use anyhow::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use opencv::core::{Point, Scalar};
+use std::sync::Mutex;
+use wordle_timer_bot::detection::{DetectionParams, detect_needle_in_haystack};
+
+/// Keeps every log record so tests can inspect them
+struct CaptureLogger {
+    records: Mutex<Vec<(Level, String, String)>>, // (level, target, message)
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_detection_details_are_logged_at_trace() -> Result<()> {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let needle = common::pattern(32)?;
+    let mut haystack = common::blank(160, 100, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(50, 30))?;
+    let params = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.9,
+        ..Default::default()
+    };
+    detect_needle_in_haystack(&needle, &haystack, &params)?;
+
+    let records = LOGGER.records.lock().unwrap();
+    let detection: Vec<_> = records
+        .iter()
+        .filter(|(_, target, _)| target == "wordle_timer_bot::detection")
+        .collect();
+    assert!(
+        detection
+            .iter()
+            .any(|(level, _, message)| *level == Level::Trace
+                && message.starts_with("Searched scale 1.000 (32x32)")),
+        "{records:?}"
+    );
+    assert!(
+        detection
+            .iter()
+            .any(|(level, _, message)| *level == Level::Debug
+                && message.starts_with("Best matches after")
+                && message.contains("x: 50, y: 30")),
+        "{records:?}"
+    );
+
+    Ok(())
+}
@@ -1,21 +1,46 @@
+// Exercises the OpenCV detection, which the stub built without it doesn't have
+#![cfg(feature = "cv")]
+
+mod common;
+
 use anyhow::Result;
 use opencv::{
-    core::{MatTraitConst, Rect, Scalar, Vector},
+    core::{Mat, MatTraitConst, Point, Rect, Scalar, Size, Vector},
     imgcodecs::{self, imwrite},
     imgproc::{self, LINE_8},
 };
-use wordle_timer_bot::detection::detect_needle_in_haystack;
+use wordle_timer_bot::config::GridColours;
+use wordle_timer_bot::detection::{
+    DetectionParams, Preprocess, RectFraction, ScaleSpacing, Scoring, calibrate_threshold,
+    clamp_to_frame, colour_coverage, colour_histogram, count_filled_rows, credit_completions,
+    detect_all_above_threshold, detect_needle_in_haystack, detect_needle_in_haystack_with_stats,
+    needle_fingerprint, rotate_image, scale_normalized_confidence, scale_range_for_target,
+    scale_values, ssim, vote_on_detection,
+};
+use wordle_timer_bot::metrics::metrics;
+use wordle_timer_bot::{
+    DEFAULT_MARKER_THRESHOLD, MIN_COLOUR_COVERAGE, MarkerTemplate, PlayerVerification,
+    SearchRegion, VerificationTrace, annotate_completion, annotate_trace, check_avatar_variants,
+    check_player_in_image, find_finished_players, is_hard_mode, is_player_in_image,
+    may_show_player, verify_finished_players, verify_finished_players_in,
+    verify_finished_players_parallel, verify_player_completion,
+};
 
 #[test]
 fn test_end_game_detection() -> Result<()> {
     let haystack = imgcodecs::imread("./data/preview.png", imgcodecs::IMREAD_COLOR_RGB)?;
     let needle = imgcodecs::imread("./data/solved.png", imgcodecs::IMREAD_COLOR_RGB)?;
 
-    let boxes = detect_needle_in_haystack(&needle, &haystack, 2, 0.6, 1.4, 100, 0.9)?;
+    let params = DetectionParams {
+        num_matches: 2,
+        threshold: 0.9,
+        ..Default::default()
+    };
+    let boxes = detect_needle_in_haystack(&needle, &haystack, &params)?;
     let mut display_image = haystack.clone();
 
     for (b, confidence) in boxes.iter() {
-        println!("Confidence: {confidence}");
+        log::debug!("Confidence: {confidence}");
         let top_left = b.0;
         imgproc::rectangle(
             &mut display_image,
@@ -32,6 +57,1227 @@ fn test_end_game_detection() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_rotated_detection() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let rotated = rotate_image(&needle, 4.0)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste(&rotated, &mut haystack, Point::new(150, 60))?;
+
+    let upright = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.0,
+        ..Default::default()
+    };
+    let tilted = DetectionParams {
+        rotation_steps: 2,
+        max_rotation_deg: 4.0,
+        ..upright
+    };
+
+    let upright_matches = detect_needle_in_haystack(&needle, &haystack, &upright)?;
+    let tilted_matches = detect_needle_in_haystack(&needle, &haystack, &tilted)?;
+    assert_eq!(tilted_matches.len(), 1);
+
+    let ((top_left, bottom_right), confidence) = tilted_matches[0];
+    assert!(confidence > upright_matches[0].1);
+    assert!(confidence > 0.99);
+
+    // The box should be the axis-aligned box of the rotated needle
+    assert_eq!(top_left, Point::new(150, 60));
+    assert_eq!(
+        bottom_right,
+        Point::new(150 + rotated.cols(), 60 + rotated.rows())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_circular_avatar_detection() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    // A light background, which the avatar's corners agree with no better than black
+    let mut haystack = common::blank(320, 200, Scalar::all(220.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(100, 50))?;
+
+    let square = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.0,
+        ..Default::default()
+    };
+    let circular = DetectionParams {
+        circular: true,
+        ..square
+    };
+    let square_matches = detect_needle_in_haystack(&avatar, &haystack, &square)?;
+    let circle_matches = detect_needle_in_haystack(&avatar, &haystack, &circular)?;
+
+    let ((top_left, bottom_right), circle_confidence) = circle_matches[0];
+    assert_eq!(top_left, Point::new(100, 50));
+    // Boxes still cover the whole avatar
+    assert_eq!(bottom_right, Point::new(164, 114));
+    assert!(circle_confidence > 0.99, "{circle_confidence}");
+    assert!(circle_confidence > square_matches[0].1);
+
+    Ok(())
+}
+
+#[test]
+fn test_short_circuit_skips_remaining_scales() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(120, 40))?;
+
+    // The exact-size needle is tried halfway through the scales
+    let exhaustive = DetectionParams {
+        min_scale: 0.5,
+        max_scale: 1.5,
+        scale_steps: 10,
+        threshold: 0.9,
+        short_circuit_threshold: None,
+        ..Default::default()
+    };
+    let short_circuit = DetectionParams {
+        short_circuit_threshold: Some(0.99),
+        ..exhaustive
+    };
+
+    let (all_matches, all_stats) =
+        detect_needle_in_haystack_with_stats(&needle, &haystack, &exhaustive)?;
+    let (early_matches, early_stats) =
+        detect_needle_in_haystack_with_stats(&needle, &haystack, &short_circuit)?;
+
+    assert_eq!(early_matches.len(), 1);
+    assert_eq!(early_matches[0].0, all_matches[0].0);
+    assert_eq!(early_matches[0].0.0, Point::new(120, 40));
+    assert_eq!(all_stats.scales_searched, 11);
+    assert_eq!(early_stats.scales_searched, 6);
+
+    Ok(())
+}
+
+#[test]
+fn test_repeat_search_reuses_resized_needles() -> Result<()> {
+    let needle = common::pattern(48)?;
+    let mut haystack = common::blank(331, 217, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(100, 60))?;
+    // Screenshots come in different sizes, which the needle's resizes don't depend on
+    let mut larger = common::blank(480, 360, Scalar::all(0.0))?;
+    common::paste(&needle, &mut larger, Point::new(100, 60))?;
+
+    let params = DetectionParams {
+        min_scale: 0.5,
+        max_scale: 1.5,
+        scale_steps: 10,
+        threshold: 0.9,
+        short_circuit_threshold: None,
+        needle_fingerprint: Some(needle_fingerprint(&needle)?),
+        ..Default::default()
+    };
+
+    let (first, stats) = detect_needle_in_haystack_with_stats(&needle, &haystack, &params)?;
+    // Other tests share the counters, so they can only have grown by more
+    let hits_before = metrics().resize_cache_hits.get();
+    let (second, _) = detect_needle_in_haystack_with_stats(&needle, &larger, &params)?;
+    let hits_after = metrics().resize_cache_hits.get();
+
+    assert_eq!(first, second);
+    assert!(
+        hits_after - hits_before >= stats.scales_searched as u64,
+        "{} hits for {} scales",
+        hits_after - hits_before,
+        stats.scales_searched
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_mismatched_channels_are_reported() -> Result<()> {
+    let needle = common::pattern(32)?;
+    let mut grey_needle = opencv::core::Mat::default();
+    imgproc::cvt_color_def(&needle, &mut grey_needle, imgproc::COLOR_BGR2GRAY)?;
+    let haystack = common::blank(200, 100, Scalar::all(0.0))?;
+
+    let error = detect_needle_in_haystack(&grey_needle, &haystack, &DetectionParams::default())
+        .expect_err("a greyscale needle can't be matched against a colour haystack");
+    assert_eq!(error.code, opencv::core::StsUnmatchedFormats);
+    assert!(error.message.contains("1 channel(s)"), "{}", error.message);
+    assert!(error.message.contains("3 channel(s)"), "{}", error.message);
+
+    Ok(())
+}
+
+#[test]
+fn test_needle_larger_than_haystack_is_skipped() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let haystack = common::blank(32, 32, Scalar::all(0.0))?;
+
+    let matches = detect_needle_in_haystack(&needle, &haystack, &DetectionParams::default())?;
+    assert!(matches.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_native_scale_outranks_downscaled_match() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut small = Mat::default();
+    imgproc::resize(
+        &needle,
+        &mut small,
+        Size::new(48, 48),
+        0.0,
+        0.0,
+        imgproc::INTER_LINEAR,
+    )?;
+
+    // The down-scaled copy is resized exactly as the search resizes the needle, so both
+    // match perfectly on raw confidence
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(20, 40))?;
+    common::paste(&small, &mut haystack, Point::new(250, 60))?;
+
+    let params = DetectionParams {
+        min_scale: 0.75,
+        max_scale: 1.0,
+        scale_steps: 1,
+        short_circuit_threshold: None,
+        ..Default::default()
+    };
+    let matches = detect_needle_in_haystack(&needle, &haystack, &params)?;
+
+    assert_eq!(
+        matches[0].0,
+        (Point::new(20, 40), Point::new(84, 104)),
+        "{matches:?}"
+    );
+    assert!(
+        scale_normalized_confidence(&((Point::new(0, 0), Point::new(64, 64)), 0.99))
+            > scale_normalized_confidence(&((Point::new(0, 0), Point::new(48, 48)), 0.99))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_close_avatars_share_one_marker() {
+    // Both avatar centres fall within the single marker's x-range
+    let marker = (Point::new(100, 200), Point::new(160, 220));
+    let avatars = [
+        Some((Point::new(96, 120), Point::new(136, 160))), // centre x 116
+        Some((Point::new(110, 120), Point::new(150, 160))), // centre x 130, nearest
+        None,                                              // Not found in the image
+    ];
+
+    assert_eq!(credit_completions(&[marker], &avatars), vec![1]);
+}
+
+#[test]
+fn test_boxes_off_the_frame_are_clamped() {
+    let frame = Size::new(100, 100);
+
+    // Partly off the left edge, the centre moves back into the frame
+    let clamped = clamp_to_frame(&(Point::new(-20, 10), Point::new(10, 40)), frame);
+    assert_eq!(clamped, Some((Point::new(0, 10), Point::new(10, 40))));
+    let marker = (Point::new(0, 60), Point::new(8, 70));
+    assert_eq!(credit_completions(&[marker], &[clamped]), vec![0]);
+
+    // Past the bottom right corner
+    let clamped = clamp_to_frame(&(Point::new(90, 90), Point::new(120, 130)), frame);
+    assert_eq!(clamped, Some((Point::new(90, 90), Point::new(100, 100))));
+
+    // Entirely off the frame, nothing is left to associate
+    assert_eq!(
+        clamp_to_frame(&(Point::new(-40, 10), Point::new(-5, 40)), frame),
+        None
+    );
+    assert_eq!(
+        clamp_to_frame(&(Point::new(30, 10), Point::new(30, 40)), frame),
+        None
+    );
+}
+
+#[test]
+fn test_padded_boxes_grow_within_the_frame() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste(&avatar, &mut haystack, Point::new(100, 120))?;
+    common::paste(&avatar, &mut haystack, Point::new(0, 10))?;
+    let params = DetectionParams {
+        num_matches: 2,
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        box_padding: 0.25,
+        ..Default::default()
+    };
+
+    let mut found: Vec<_> = detect_needle_in_haystack(&avatar, &haystack, &params)?
+        .into_iter()
+        .map(|(bounding_box, _)| bounding_box)
+        .collect();
+    found.sort_by_key(|(top_left, _)| top_left.y);
+
+    // Away from the edges, a quarter of the 64px avatar is added on every side
+    assert_eq!(found[1], (Point::new(84, 104), Point::new(180, 200)));
+    // In the corner, the padding stops at the frame
+    assert_eq!(found[0], (Point::new(0, 0), Point::new(80, 90)));
+    let frame = haystack.size()?;
+    assert!(found.iter().all(|(top_left, bottom_right)| top_left.x >= 0
+        && top_left.y >= 0
+        && bottom_right.x <= frame.width
+        && bottom_right.y <= frame.height));
+
+    // Without padding the tight boxes are returned
+    let tight = detect_needle_in_haystack(
+        &avatar,
+        &haystack,
+        &DetectionParams {
+            box_padding: 0.0,
+            ..params
+        },
+    )?;
+    assert!(
+        tight
+            .iter()
+            .any(|(bounding_box, _)| *bounding_box == (Point::new(100, 120), Point::new(164, 184)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_each_marker_credits_its_own_avatar() {
+    let markers = [
+        (Point::new(100, 200), Point::new(160, 220)),
+        (Point::new(300, 200), Point::new(360, 220)),
+    ];
+    let avatars = [
+        Some((Point::new(310, 120), Point::new(350, 160))),
+        Some((Point::new(110, 120), Point::new(150, 160))),
+        Some((Point::new(500, 120), Point::new(540, 160))), // No marker beneath
+    ];
+
+    assert_eq!(credit_completions(&markers, &avatars), vec![0, 1]);
+}
+
+#[test]
+fn test_overlapping_markers_credit_one_avatar() {
+    // Two detections of the same marker, a few pixels apart
+    let markers = [
+        (Point::new(100, 200), Point::new(160, 220)),
+        (Point::new(104, 202), Point::new(164, 222)),
+    ];
+    let avatars = [
+        Some((Point::new(110, 120), Point::new(150, 160))),
+        Some((Point::new(140, 120), Point::new(180, 160))), // centre x 160, also under it
+    ];
+
+    assert_eq!(credit_completions(&markers, &avatars), vec![0]);
+}
+
+#[test]
+fn test_avatar_under_several_markers_is_not_credited() {
+    // Markers in two rows whose x-ranges both take in the first avatar's centre
+    let markers = [
+        (Point::new(100, 200), Point::new(160, 220)),
+        (Point::new(120, 400), Point::new(180, 420)),
+    ];
+    let avatars = [
+        Some((Point::new(120, 120), Point::new(160, 160))), // centre x 140, under both
+        Some((Point::new(150, 320), Point::new(190, 360))), // centre x 170, second only
+    ];
+
+    assert_eq!(credit_completions(&markers, &avatars), vec![1]);
+}
+
+/// Draws a light-theme result grid with the first `filled` of its six rows guessed
+fn result_grid(filled: i32) -> Result<Mat> {
+    const TILE: i32 = 62;
+    const GAP: i32 = 5;
+    let mut grid = common::blank(5 * TILE + 4 * GAP, 6 * TILE + 5 * GAP, Scalar::all(255.0))?;
+    // Slightly off NYT's colours, as a compressed screenshot would be
+    let squares = [
+        Scalar::new(110.0, 166.0, 104.0, 0.0),
+        Scalar::new(197.0, 184.0, 84.0, 0.0),
+        Scalar::new(124.0, 120.0, 130.0, 0.0),
+    ];
+
+    for row in 0..6 {
+        for column in 0..5 {
+            let tile = Rect::new(column * (TILE + GAP), row * (TILE + GAP), TILE, TILE);
+            if row < filled {
+                let colour = squares[((row + column) % 3) as usize];
+                imgproc::rectangle(&mut grid, tile, colour, -1, LINE_8, 0)?;
+                // The guessed letter, in white
+                let letter = Rect::new(tile.x + 22, tile.y + 16, 18, 30);
+                imgproc::rectangle(&mut grid, letter, Scalar::all(255.0), -1, LINE_8, 0)?;
+            } else {
+                let outline = Scalar::new(211.0, 214.0, 218.0, 0.0);
+                imgproc::rectangle(&mut grid, tile, outline, 2, LINE_8, 0)?;
+            }
+        }
+    }
+    Ok(grid)
+}
+
+#[test]
+fn test_filled_grid_rows_are_counted() -> Result<()> {
+    for filled in [2, 4, 6] {
+        let grid = result_grid(filled)?;
+        assert_eq!(
+            count_filled_rows(&grid, &GridColours::default())?,
+            filled as u32,
+            "{filled} rows filled"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_grid_rows_outside_tolerance_are_empty() -> Result<()> {
+    let strict = GridColours {
+        tolerance: 1,
+        ..GridColours::default()
+    };
+    assert_eq!(count_filled_rows(&result_grid(4)?, &strict)?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_scale_range_for_target() -> Result<()> {
+    let needle = common::pattern(128)?;
+
+    let (min_scale, max_scale) = scale_range_for_target(&needle, 40, 0.1);
+    assert!((min_scale - 0.28125).abs() < 1e-9, "{min_scale}");
+    assert!((max_scale - 0.34375).abs() < 1e-9, "{max_scale}");
+
+    // No tolerance pins the exact scale
+    assert_eq!(scale_range_for_target(&needle, 40, 0.0), (0.3125, 0.3125));
+
+    Ok(())
+}
+
+#[test]
+fn test_calibrated_threshold_separates_examples() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut mirrored = opencv::core::Mat::default();
+    opencv::core::flip(&avatar, &mut mirrored, 1)?;
+    let mut upside_down = opencv::core::Mat::default();
+    opencv::core::flip(&avatar, &mut upside_down, 0)?;
+
+    let screenshot = |at: Point| -> Result<opencv::core::Mat> {
+        let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+        common::paste(&avatar, &mut haystack, at)?;
+        Ok(haystack)
+    };
+
+    let positives = vec![
+        (avatar.clone(), screenshot(Point::new(10, 10))?),
+        (avatar.clone(), screenshot(Point::new(200, 100))?),
+    ];
+    // Look-alike avatars that aren't in the screenshot
+    let negatives = vec![
+        (mirrored, screenshot(Point::new(10, 10))?),
+        (upside_down, screenshot(Point::new(200, 100))?),
+    ];
+
+    let threshold = calibrate_threshold(&positives, &negatives)?;
+    let params = DetectionParams {
+        threshold,
+        ..Default::default()
+    };
+    for (needle, haystack) in &positives {
+        assert_eq!(
+            detect_needle_in_haystack(needle, haystack, &params)?.len(),
+            1,
+            "{threshold}"
+        );
+    }
+    for (needle, haystack) in &negatives {
+        assert!(
+            detect_needle_in_haystack(needle, haystack, &params)?.is_empty(),
+            "{threshold}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_preprocessing_on_jpeg_degraded_screenshot() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::new(30.0, 60.0, 90.0, 0.0))?;
+    common::paste(&avatar, &mut haystack, Point::new(140, 70))?;
+
+    // Recompress heavily, as Discord does
+    let mut jpeg = Vector::<u8>::new();
+    imgcodecs::imencode(
+        ".jpg",
+        &haystack,
+        &mut jpeg,
+        &Vector::from_slice(&[imgcodecs::IMWRITE_JPEG_QUALITY, 15]),
+    )?;
+    let degraded = imgcodecs::imdecode(&jpeg, imgcodecs::IMREAD_COLOR)?;
+
+    for preprocess in [Preprocess::None, Preprocess::Grayscale, Preprocess::Edges] {
+        let params = DetectionParams {
+            min_scale: 1.0,
+            max_scale: 1.0,
+            scale_steps: 1,
+            threshold: 0.0,
+            preprocess,
+            ..Default::default()
+        };
+        let matches = detect_needle_in_haystack(&avatar, &degraded, &params)?;
+        let ((top_left, bottom_right), confidence) = matches[0];
+        log::debug!("{preprocess:?} confidence: {confidence}");
+
+        // Coordinates are unaffected by the conversion
+        assert_eq!(top_left, Point::new(140, 70), "{preprocess:?}");
+        assert_eq!(bottom_right, Point::new(204, 134), "{preprocess:?}");
+        assert!(confidence > 0.5, "{preprocess:?} confidence {confidence}");
+    }
+
+    Ok(())
+}
+
+#[test]
 fn test_avatar_detection() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(180, 90))?;
+
+    assert!(is_player_in_image(&avatar, &haystack)?);
+    assert!(!is_player_in_image(
+        &avatar,
+        &common::blank(320, 200, Scalar::all(0.0))?
+    )?);
+
+    // The details behind the decision say where the avatar was matched
+    let player_match = check_player_in_image(&avatar, &haystack, 1)?;
+    assert!(player_match.found);
+    let ((top_left, bottom_right), confidence) = player_match.best.unwrap();
+    assert_eq!(top_left, Point::new(180, 90));
+    assert_eq!(bottom_right, Point::new(244, 154));
+    assert!(confidence > 0.95);
+
+    let missing = check_player_in_image(&avatar, &common::blank(320, 200, Scalar::all(0.0))?, 3)?;
+    assert!(!missing.found);
+    assert_eq!(missing.best, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_player_found_under_any_avatar_variant() -> Result<()> {
+    let current = common::pattern(64)?;
+    let mut old = Mat::default();
+    opencv::core::bitwise_not(&current, &mut old, &opencv::core::no_array())?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&current, &mut haystack, Point::new(180, 90))?;
+
+    // The first avatar is out of date, the second is the one in the screenshot
+    let player_match = check_avatar_variants(&[old.clone(), current], &haystack, 1)?;
+    assert!(player_match.found);
+    assert_eq!(
+        player_match.best.map(|((top_left, _), _)| top_left),
+        Some(Point::new(180, 90))
+    );
+
+    assert!(!check_avatar_variants(&[old], &haystack, 1)?.found);
+    assert!(!check_avatar_variants(&[], &haystack, 1)?.found);
+
+    Ok(())
+}
+
+#[test]
+fn test_voting_settles_needle_between_scales() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(120, 60))?;
+
+    // The grid only has 0.8 and 1.2, either side of the needle's true scale
+    let params = DetectionParams {
+        min_scale: 0.8,
+        max_scale: 1.2,
+        scale_steps: 1,
+        ..Default::default()
+    };
+    assert!(detect_needle_in_haystack(&needle, &haystack, &params)?.is_empty());
+    assert!(!vote_on_detection(&needle, &haystack, &params, 1)?);
+
+    // Both shifted grids land on 1.0, outvoting the unshifted miss
+    assert!(vote_on_detection(&needle, &haystack, &params, 3)?);
+
+    // Voting doesn't conjure up a needle that isn't there
+    let empty = common::blank(320, 200, Scalar::all(0.0))?;
+    assert!(!vote_on_detection(&needle, &empty, &params, 3)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_voting_on_a_single_scale() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(120, 60))?;
+
+    // Every vote searches the one scale, unshifted
+    let params = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 0,
+        ..Default::default()
+    };
+    assert!(vote_on_detection(&needle, &haystack, &params, 3)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_verification_trace_for_finished_player() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let marker_with_tick = tick_marker()?;
+
+    // Avatar centred at x = 132 with the completion marker beneath it
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(100, 40))?;
+    common::paste(&marker_with_tick, &mut haystack, Point::new(120, 130))?;
+
+    let trace = verify_finished_players(
+        &[avatar],
+        &haystack,
+        &[MarkerTemplate::new(marker_with_tick.clone())],
+    )?;
+
+    assert_eq!(trace.markers.len(), 1);
+    assert_eq!(trace.markers[0].0.0, Point::new(120, 130));
+    assert_eq!(trace.avatars.len(), 1);
+    let avatar_trace = &trace.avatars[0];
+    assert!(
+        avatar_trace
+            .found
+            .is_some_and(|(_, confidence)| confidence > 0.95)
+    );
+    assert_eq!(avatar_trace.center, Some(Point::new(132, 72)));
+    assert_eq!(avatar_trace.candidate_markers, vec![0]);
+    assert!(avatar_trace.credited);
+    assert_eq!(trace.credited(), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_player_verification_outcomes() -> Result<()> {
+    let finished = common::pattern(64)?;
+    let mut unfinished = Mat::default();
+    opencv::core::bitwise_not(&finished, &mut unfinished, &opencv::core::no_array())?;
+    let markers = [MarkerTemplate::new(tick_marker()?)];
+
+    // Only the first avatar has a completion marker beneath it
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&finished, &mut haystack, Point::new(100, 40))?;
+    common::paste(&markers[0].image, &mut haystack, Point::new(120, 130))?;
+    common::paste_circle(&unfinished, &mut haystack, Point::new(220, 40))?;
+    let mut in_progress = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&finished, &mut in_progress, Point::new(100, 40))?;
+
+    assert_eq!(
+        verify_player_completion(&finished, &haystack, &markers)?,
+        PlayerVerification::PlayerCompleted
+    );
+    assert_eq!(
+        verify_player_completion(&unfinished, &haystack, &markers)?,
+        PlayerVerification::PlayerNotCompleted
+    );
+    assert_eq!(
+        verify_player_completion(&finished, &in_progress, &markers)?,
+        PlayerVerification::NoCompletionsInImage
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_absent_player_is_ruled_out_by_colour() -> Result<()> {
+    let pictured = common::pattern(64)?;
+    let absent = common::blank(64, 64, Scalar::new(230.0, 30.0, 200.0, 0.0))?;
+    let markers = [MarkerTemplate::new(tick_marker()?)];
+
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&pictured, &mut haystack, Point::new(100, 40))?;
+    common::paste(&markers[0].image, &mut haystack, Point::new(120, 130))?;
+    let histogram = colour_histogram(&haystack)?;
+
+    assert!(colour_coverage(&pictured, &histogram)? > 0.99);
+    assert!(colour_coverage(&absent, &histogram)? < MIN_COLOUR_COVERAGE);
+    assert!(may_show_player(&pictured, &histogram)?);
+
+    // Other tests share the counter, so it can only have grown by more
+    let rejections_before = metrics().presence_rejections.get();
+    assert!(!may_show_player(&absent, &histogram)?);
+    assert_eq!(
+        verify_player_completion(&absent, &haystack, &markers)?,
+        PlayerVerification::PlayerNotCompleted
+    );
+    assert!(metrics().presence_rejections.get() - rejections_before >= 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_avatar_outside_marker_band_is_not_credited() -> Result<()> {
+    let finished = common::pattern(64)?;
+    let mut elsewhere = Mat::default();
+    opencv::core::bitwise_not(&finished, &mut elsewhere, &opencv::core::no_array())?;
+    let marker_with_tick = tick_marker()?;
+
+    // The first avatar sits above its marker, the second only appears far below the
+    // other marker's row, as it might in a member list
+    let mut haystack = common::blank(320, 400, Scalar::all(0.0))?;
+    common::paste_circle(&finished, &mut haystack, Point::new(100, 40))?;
+    common::paste(&marker_with_tick, &mut haystack, Point::new(120, 130))?;
+    common::paste(&marker_with_tick, &mut haystack, Point::new(220, 130))?;
+    common::paste_circle(&elsewhere, &mut haystack, Point::new(200, 310))?;
+    let avatars = [finished, elsewhere];
+
+    // Searching the whole screenshot credits the copy below the second marker
+    let whole = verify_finished_players(
+        &avatars,
+        &haystack,
+        &[MarkerTemplate::new(marker_with_tick.clone())],
+    )?;
+    assert_eq!(whole.credited(), vec![0, 1]);
+
+    let banded = verify_finished_players_in(
+        &avatars,
+        &haystack,
+        &[MarkerTemplate::new(marker_with_tick.clone())],
+        SearchRegion::AroundMarkers { reach: 4.0 },
+    )?;
+    assert_eq!(banded.markers.len(), 2);
+    assert_eq!(banded.avatars[0].center, Some(Point::new(132, 72)));
+    assert_eq!(banded.avatars[1].found, None);
+    assert_eq!(banded.credited(), vec![0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_parallel_verification_matches_serial() -> Result<()> {
+    let avatar = common::pattern(48)?;
+    let mut inverted = Mat::default();
+    opencv::core::bitwise_not(&avatar, &mut inverted, &opencv::core::no_array())?;
+    let mut flipped = Mat::default();
+    opencv::core::flip(&avatar, &mut flipped, 0)?;
+    let mut mirrored = Mat::default();
+    opencv::core::flip(&avatar, &mut mirrored, 1)?;
+    let marker_with_tick = tick_marker()?;
+
+    // Three players above their markers, the fourth isn't in the screenshot
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    for (i, finished) in [&avatar, &inverted, &flipped].into_iter().enumerate() {
+        let x = 20 + 120 * i as i32;
+        common::paste_circle(finished, &mut haystack, Point::new(x, 30))?;
+        common::paste(&marker_with_tick, &mut haystack, Point::new(x + 12, 110))?;
+    }
+    let avatars = [avatar, inverted, flipped, mirrored];
+    let completed = |trace: &VerificationTrace| -> Vec<bool> {
+        trace.avatars.iter().map(|avatar| avatar.credited).collect()
+    };
+
+    for region in [
+        SearchRegion::Whole,
+        SearchRegion::AroundMarkers { reach: 4.0 },
+    ] {
+        let serial = verify_finished_players_in(
+            &avatars,
+            &haystack,
+            &[MarkerTemplate::new(marker_with_tick.clone())],
+            region,
+        )?;
+        assert_eq!(serial.credited(), vec![0, 1, 2], "{region:?}");
+
+        for workers in [2, 3, 8] {
+            let parallel = verify_finished_players_parallel(
+                &avatars,
+                &haystack,
+                &[MarkerTemplate::new(marker_with_tick.clone())],
+                region,
+                workers,
+            )?;
+            assert_eq!(completed(&parallel), completed(&serial), "{region:?}");
+            assert_eq!(parallel, serial, "{region:?} on {workers} workers");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_all_markers_found_regardless_of_num_matches() -> Result<()> {
+    let marker_with_tick = tick_marker()?;
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    let positions = [
+        Point::new(40, 130),
+        Point::new(160, 130),
+        Point::new(280, 130),
+    ];
+    for at in positions {
+        common::paste(&marker_with_tick, &mut haystack, at)?;
+    }
+    let params = DetectionParams {
+        num_matches: 1,
+        ..Default::default()
+    };
+
+    let mut found: Vec<_> = detect_all_above_threshold(&marker_with_tick, &haystack, &params)?
+        .into_iter()
+        .map(|((top_left, _), _)| top_left)
+        .collect();
+    found.sort_by_key(|top_left| top_left.x);
+    assert_eq!(found, positions);
+
+    // The capped search still stops at the count asked for, one per marker
+    let capped = detect_needle_in_haystack(&marker_with_tick, &haystack, &params)?;
+    assert_eq!(capped.len(), 1);
+    assert!(positions.contains(&capped[0].0.0));
+
+    Ok(())
+}
+
+#[test]
+fn test_annotated_trace_boxes_markers_and_avatars() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let marker_with_tick = tick_marker()?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(100, 40))?;
+    common::paste(&marker_with_tick, &mut haystack, Point::new(120, 130))?;
+
+    let trace = verify_finished_players(
+        &[avatar],
+        &haystack,
+        &[MarkerTemplate::new(marker_with_tick.clone())],
+    )?;
+    let png = annotate_trace(&haystack, &trace)?;
+    let annotated = imgcodecs::imdecode(&Vector::from_slice(&png), imgcodecs::IMREAD_COLOR)?;
+
+    assert_eq!(annotated.size()?, haystack.size()?);
+    // Decoded in BGR order: the marker is boxed in yellow and the credited avatar in green
+    let (top_left, _) = trace.markers[0].0;
+    let marker_edge = annotated.at_2d::<opencv::core::Vec3b>(top_left.y, top_left.x)?;
+    assert_eq!(marker_edge.0, [0, 210, 255]);
+    let ((top_left, _), _) = trace.avatars[0].found.unwrap();
+    let avatar_edge = annotated.at_2d::<opencv::core::Vec3b>(top_left.y, top_left.x)?;
+    assert_eq!(avatar_edge.0, [0, 220, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_annotated_completion_boxes_detected_positions() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let marker_with_tick = tick_marker()?;
+    let mut haystack = common::blank(320, 200, Scalar::all(0.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(30, 50))?;
+    common::paste(&marker_with_tick, &mut haystack, Point::new(50, 140))?;
+
+    let png = annotate_completion(
+        &haystack,
+        std::slice::from_ref(&avatar),
+        &[MarkerTemplate::new(marker_with_tick.clone())],
+    )?;
+    let annotated = imgcodecs::imdecode(&Vector::from_slice(&png), imgcodecs::IMREAD_COLOR)?;
+    assert_eq!(annotated.size()?, haystack.size()?);
+
+    // Match positions can be off by a pixel or so between scales, so look around them
+    let has_colour_near = |at: Point, bgr: [u8; 3]| -> Result<bool> {
+        for y in at.y - 2..=at.y + 2 {
+            for x in at.x - 2..=at.x + 2 {
+                if annotated.at_2d::<opencv::core::Vec3b>(y, x)?.0 == bgr {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    };
+    // Decoded in BGR order: the marker in yellow and the credited avatar in green
+    assert!(has_colour_near(Point::new(50, 140), [0, 210, 255])?);
+    assert!(has_colour_near(Point::new(30, 50), [0, 220, 0])?);
+    // Nothing is drawn where nothing was found
+    assert!(!has_colour_near(Point::new(250, 100), [0, 220, 0])?);
+
+    Ok(())
+}
+
+/// A completion marker: a green square with a white tick
+fn tick_marker() -> Result<Mat> {
+    let mut marker_with_tick = common::blank(24, 24, Scalar::new(80.0, 200.0, 80.0, 0.0))?;
+    imgproc::line(
+        &mut marker_with_tick,
+        Point::new(4, 12),
+        Point::new(10, 20),
+        Scalar::all(255.0),
+        3,
+        LINE_8,
+        0,
+    )?;
+    imgproc::line(
+        &mut marker_with_tick,
+        Point::new(10, 20),
+        Point::new(20, 4),
+        Scalar::all(255.0),
+        3,
+        LINE_8,
+        0,
+    )?;
+
+    Ok(marker_with_tick)
+}
+
+/// A dark-theme completion marker: a dark tick cut out of a green disc on near-black
+fn dark_tick_marker() -> Result<Mat> {
+    let background = Scalar::new(19.0, 18.0, 18.0, 0.0);
+    let mut marker = common::blank(24, 24, background)?;
+    imgproc::circle(
+        &mut marker,
+        Point::new(12, 12),
+        11,
+        Scalar::new(80.0, 200.0, 80.0, 0.0),
+        -1,
+        LINE_8,
+        0,
+    )?;
+    for (from, to) in [
+        (Point::new(5, 12), Point::new(10, 18)),
+        (Point::new(10, 18), Point::new(19, 6)),
+    ] {
+        imgproc::line(&mut marker, from, to, background, 3, LINE_8, 0)?;
+    }
+
+    Ok(marker)
+}
+
+#[test]
+fn test_finished_players_found_under_either_theme() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let light_marker = tick_marker()?;
+    let dark_marker = dark_tick_marker()?;
+
+    let mut light = common::blank(320, 200, Scalar::all(255.0))?;
+    common::paste_circle(&avatar, &mut light, Point::new(100, 40))?;
+    common::paste(&light_marker, &mut light, Point::new(120, 130))?;
+    let mut dark = common::blank(320, 200, Scalar::new(19.0, 18.0, 18.0, 0.0))?;
+    common::paste_circle(&avatar, &mut dark, Point::new(100, 40))?;
+    common::paste(&dark_marker, &mut dark, Point::new(120, 130))?;
+    let avatars = [avatar];
+
+    // Only the light-theme marker misses dark-theme completions
+    let light_only = &[MarkerTemplate::new(light_marker.clone())];
+    assert_eq!(
+        find_finished_players(&avatars, &light, light_only)?,
+        vec![0]
+    );
+    assert!(find_finished_players(&avatars, &dark, light_only)?.is_empty());
+
+    let both_themes = [
+        MarkerTemplate::new(light_marker),
+        MarkerTemplate::new(dark_marker),
+    ];
+    assert_eq!(
+        find_finished_players(&avatars, &light, &both_themes)?,
+        vec![0]
+    );
+    assert_eq!(
+        find_finished_players(&avatars, &dark, &both_themes)?,
+        vec![0]
+    );
+
+    Ok(())
+}
+
+/// A white asterisk on orange, standing in for the hard-mode marker
+fn hard_mode_marker() -> Result<Mat> {
+    let mut marker = common::blank(24, 24, Scalar::new(30.0, 140.0, 230.0, 0.0))?;
+    for (from, to) in [
+        (Point::new(12, 3), Point::new(12, 21)),
+        (Point::new(4, 7), Point::new(20, 17)),
+        (Point::new(4, 17), Point::new(20, 7)),
+    ] {
+        imgproc::line(&mut marker, from, to, Scalar::all(255.0), 3, LINE_8, 0)?;
+    }
+
+    Ok(marker)
+}
+
+#[test]
+fn test_is_hard_mode_only_with_marker() -> Result<()> {
+    let marker = hard_mode_marker()?;
+    let mut normal = common::blank(200, 120, Scalar::all(30.0))?;
+    common::paste(&tick_marker()?, &mut normal, Point::new(40, 40))?;
+    let mut hard = normal.clone();
+    common::paste(&marker, &mut hard, Point::new(120, 40))?;
+
+    assert!(is_hard_mode(&hard, &marker)?);
+    assert!(!is_hard_mode(&normal, &marker)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_log_scale_spacing_has_equal_ratios() {
+    let params = DetectionParams {
+        min_scale: 0.5,
+        max_scale: 2.0,
+        scale_steps: 4,
+        scale_spacing: ScaleSpacing::Log,
+        ..Default::default()
+    };
+    let scales = scale_values(&params);
+
+    let expected = [0.5, 0.5 * 2f64.sqrt(), 1.0, 2f64.sqrt(), 2.0];
+    assert_eq!(scales.len(), expected.len());
+    for (scale, expected) in scales.iter().zip(expected) {
+        assert!((scale - expected).abs() < 1e-9, "{scales:?}");
+    }
+
+    // Linear spacing keeps equal differences
+    let linear = scale_values(&DetectionParams {
+        scale_spacing: ScaleSpacing::Linear,
+        ..params
+    });
+    assert_eq!(linear, vec![0.5, 0.875, 1.25, 1.625, 2.0]);
+}
+
+#[test]
+fn test_ssim_rescoring_prefers_true_match_over_faded_copy() -> Result<()> {
+    // A dense texture, so only aligned windows correlate at all
+    opencv::core::set_rng_seed(7)?;
+    let mut needle = common::blank(64, 64, Scalar::all(0.0))?;
+    opencv::core::randu(&mut needle, &Scalar::all(0.0), &Scalar::all(256.0))?;
+
+    // The true match has some noise, lowering its correlation a little
+    let mut noise = common::blank(64, 64, Scalar::all(0.0))?;
+    opencv::core::randu(&mut noise, &Scalar::all(0.0), &Scalar::all(41.0))?;
+    let mut noisy = Mat::default();
+    opencv::core::add_def(&needle, &noise, &mut noisy)?;
+    let mut true_match = Mat::default();
+    opencv::core::subtract_def(&noisy, &Scalar::all(20.0), &mut true_match)?;
+    // Correlation ignores contrast, so a washed out copy correlates almost perfectly
+    let mut faded = Mat::default();
+    needle.convert_to(&mut faded, -1, 0.25, 96.0)?;
+
+    let mut haystack = common::blank(300, 120, Scalar::all(128.0))?;
+    common::paste(&faded, &mut haystack, Point::new(20, 20))?;
+    common::paste(&true_match, &mut haystack, Point::new(180, 20))?;
+
+    let correlation = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.8,
+        short_circuit_threshold: None,
+        ..Default::default()
+    };
+    let matches = detect_needle_in_haystack(&needle, &haystack, &correlation)?;
+    assert_eq!(matches[0].0.0, Point::new(20, 20));
+
+    let rescored = DetectionParams {
+        scoring: Scoring::Ssim {
+            coarse_threshold: 0.5,
+        },
+        ..correlation
+    };
+    let matches = detect_needle_in_haystack(&needle, &haystack, &rescored)?;
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].0.0, Point::new(180, 20));
+    assert!(matches[0].1 > 0.9, "SSIM was {}", matches[0].1);
+
+    assert!((ssim(&needle, &needle)? - 1.0).abs() < 1e-6);
+    assert!(ssim(&needle, &faded)? < 0.8);
+
+    Ok(())
+}
+
+#[test]
+fn test_inner_crop_matches_avatar_with_occluded_edge() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(20.0))?;
+    common::paste(&avatar, &mut haystack, Point::new(100, 50))?;
+    // A status ring around the avatar and a badge over its corner
+    imgproc::circle(
+        &mut haystack,
+        Point::new(132, 82),
+        30,
+        Scalar::all(255.0),
+        8,
+        LINE_8,
+        0,
+    )?;
+    imgproc::rectangle(
+        &mut haystack,
+        Rect::new(148, 98, 16, 16),
+        Scalar::new(230.0, 40.0, 40.0, 0.0),
+        -1,
+        LINE_8,
+        0,
+    )?;
+
+    let whole = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.9,
+        ..Default::default()
+    };
+    assert!(detect_needle_in_haystack(&avatar, &haystack, &whole)?.is_empty());
+
+    let centre = DetectionParams {
+        inner_crop_fraction: 0.5,
+        ..whole
+    };
+    let matches = detect_needle_in_haystack(&avatar, &haystack, &centre)?;
+    assert_eq!(matches.len(), 1);
+    // The box still covers the whole avatar
+    assert_eq!(matches[0].0, (Point::new(100, 50), Point::new(164, 114)));
+
+    Ok(())
+}
+
+#[test]
+fn test_marker_thresholds_are_set_per_template() -> Result<()> {
+    let avatar = common::pattern(64)?;
+    let mut haystack = common::blank(320, 200, Scalar::all(255.0))?;
+    common::paste_circle(&avatar, &mut haystack, Point::new(100, 40))?;
+    // Recompression softens the small marker's edges, so it scores about 0.9
+    let mut sharp = common::blank(40, 40, Scalar::all(255.0))?;
+    common::paste(&tick_marker()?, &mut sharp, Point::new(8, 8))?;
+    let mut softened = Mat::default();
+    imgproc::gaussian_blur_def(&sharp, &mut softened, Size::new(3, 3), 0.0)?;
+    common::paste(&softened, &mut haystack, Point::new(112, 122))?;
+    let avatars = [avatar];
+
+    // Markers are matched more leniently than avatars unless their template says otherwise
+    assert!(DEFAULT_MARKER_THRESHOLD < DetectionParams::default().threshold);
+    let marker = MarkerTemplate::new(tick_marker()?);
+    assert_eq!(
+        find_finished_players(&avatars, &haystack, std::slice::from_ref(&marker))?,
+        vec![0]
+    );
+    let strict = marker.with_threshold(0.98);
+    assert!(find_finished_players(&avatars, &haystack, &[strict])?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_repeated_matches_do_not_end_the_search_early() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut large = Mat::default();
+    imgproc::resize(
+        &needle,
+        &mut large,
+        Size::new(80, 80),
+        0.0,
+        0.0,
+        imgproc::INTER_LINEAR,
+    )?;
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(20, 40))?;
+    common::paste(&large, &mut haystack, Point::new(250, 60))?;
+
+    // Scales 1.00 and 1.01 both resize the needle to 64 pixels, finding the first
+    // avatar perfectly twice before the larger one is reached
+    let params = DetectionParams {
+        num_matches: 2,
+        min_scale: 1.0,
+        max_scale: 1.25,
+        scale_steps: 25,
+        threshold: 0.9,
+        ..Default::default()
+    };
+    let (matches, stats) = detect_needle_in_haystack_with_stats(&needle, &haystack, &params)?;
+
+    assert_eq!(matches.len(), 2, "{matches:?}");
+    let mut top_lefts: Vec<Point> = matches.iter().map(|(b, _)| b.0).collect();
+    top_lefts.sort_by_key(|point| point.x);
+    assert_eq!(top_lefts[0], Point::new(20, 40));
+    assert!((top_lefts[1].x - 250).abs() <= 1 && (top_lefts[1].y - 60).abs() <= 1);
+    assert!(stats.scales_searched > 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_matches_outside_roi_are_excluded() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    // One copy in the "header" on the left and one in the part of the screen that counts
+    common::paste(&needle, &mut haystack, Point::new(20, 40))?;
+    common::paste(&needle, &mut haystack, Point::new(250, 60))?;
+
+    let params = DetectionParams {
+        num_matches: 2,
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        threshold: 0.9,
+        roi: Some(RectFraction {
+            x: 0.5,
+            y: 0.1,
+            width: 0.5,
+            height: 0.9,
+        }),
+        ..Default::default()
+    };
+    let matches = detect_needle_in_haystack(&needle, &haystack, &params)?;
+
+    assert_eq!(matches.len(), 1, "{matches:?}");
+    assert_eq!(matches[0].0, (Point::new(250, 60), Point::new(314, 124)));
+
+    // Regions hanging off the frame are clamped to it
+    let frame = haystack.size()?;
+    let overhanging = RectFraction {
+        x: 0.75,
+        y: -0.5,
+        width: 0.5,
+        height: 1.0,
+    };
+    assert_eq!(overhanging.to_rect(frame), Rect::new(300, 0, 100, 100));
+
+    Ok(())
+}
+
+#[test]
+fn test_padding_grows_past_the_roi() -> Result<()> {
+    let needle = common::pattern(64)?;
+    let mut haystack = common::blank(400, 200, Scalar::all(0.0))?;
+    common::paste(&needle, &mut haystack, Point::new(250, 60))?;
+
+    // The region starts at x = 240, closer to the needle than its padding
+    let params = DetectionParams {
+        min_scale: 1.0,
+        max_scale: 1.0,
+        scale_steps: 1,
+        box_padding: 0.25,
+        roi: Some(RectFraction {
+            x: 0.6,
+            y: 0.0,
+            width: 0.4,
+            height: 1.0,
+        }),
+        ..Default::default()
+    };
+    let matches = detect_needle_in_haystack(&needle, &haystack, &params)?;
+
+    // Padded the same as without a region, only the haystack's frame stops it
+    assert_eq!(matches[0].0, (Point::new(234, 44), Point::new(330, 140)));
+
     Ok(())
 }
@@ -0,0 +1,43 @@
+use std::time::Duration;
+use wordle_timer_bot::metrics::Metrics;
+
+#[test]
+fn test_metrics_render_in_text_format() {
+    let metrics = Metrics::new();
+    metrics.completions_detected.inc();
+    metrics.completions_detected.inc();
+    metrics.download_failures.inc();
+    metrics
+        .detection_latency
+        .observe(Duration::from_millis(300).as_secs_f64());
+
+    let text = metrics.render();
+
+    assert!(
+        text.contains("# TYPE wordle_completions_detected_total counter"),
+        "{text}"
+    );
+    assert!(
+        text.contains("wordle_completions_detected_total 2"),
+        "{text}"
+    );
+    assert!(text.contains("wordle_download_failures_total 1"), "{text}");
+    assert!(text.contains("wordle_verifications_total 0"), "{text}");
+    assert!(text.contains("wordle_detection_failures_total 0"), "{text}");
+    assert!(
+        text.contains("# TYPE wordle_detection_latency_seconds histogram"),
+        "{text}"
+    );
+    assert!(
+        text.contains("wordle_detection_latency_seconds_bucket{le=\"0.25\"} 0"),
+        "{text}"
+    );
+    assert!(
+        text.contains("wordle_detection_latency_seconds_bucket{le=\"0.5\"} 1"),
+        "{text}"
+    );
+    assert!(
+        text.contains("wordle_detection_latency_seconds_count 1"),
+        "{text}"
+    );
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use wordle_timer_bot::history::History;
+use wordle_timer_bot::is_tracked;
+
+#[test]
+fn test_opted_out_users_are_not_tracked() {
+    let opted_out = HashSet::from([10]);
+
+    assert!(!is_tracked(Some(10), &opted_out));
+    assert!(is_tracked(Some(11), &opted_out));
+    // Players that can't be matched to a member can't have opted out
+    assert!(is_tracked(None, &opted_out));
+}
+
+#[tokio::test]
+async fn test_opt_out_is_per_guild_and_reversible() -> Result<()> {
+    let history = History::in_memory().await?;
+    history.opt_out(1, 10).await?;
+    history.opt_out(1, 10).await?; // Opting out twice is harmless
+    history.opt_out(1, 11).await?;
+
+    assert_eq!(history.opted_out_users(1).await?, HashSet::from([10, 11]));
+    assert!(history.opted_out_users(2).await?.is_empty());
+
+    history.opt_in(1, 10).await?;
+    let opted_out = history.opted_out_users(1).await?;
+    assert!(is_tracked(Some(10), &opted_out));
+    assert!(!is_tracked(Some(11), &opted_out));
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Australia::Sydney;
+use serenity::model::id::{GuildId, MessageId, UserId};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use wordle_timer_bot::config::Config;
+use wordle_timer_bot::game::GameState;
+use wordle_timer_bot::history::History;
+use wordle_timer_bot::reminders::{is_reminder_due, players_to_remind};
+
+#[test]
+fn test_players_without_a_finished_game_today_are_reminded() {
+    let (guild, other_guild) = (GuildId::new(1), GuildId::new(2));
+    let now = Utc::now();
+    let finished = || {
+        let mut game = GameState::in_timezone(Sydney);
+        game.mark_completed(Duration::from_secs(60), now);
+        game
+    };
+    let mut yesterdays = GameState::in_timezone_at(Sydney, now - chrono::Duration::days(1));
+    yesterdays.mark_completed(Duration::from_secs(60), now - chrono::Duration::days(1));
+
+    let games = HashMap::from([
+        // Finished
+        ((guild, MessageId::new(10), UserId::new(100)), finished()),
+        // Still playing
+        (
+            (guild, MessageId::new(11), UserId::new(101)),
+            GameState::in_timezone(Sydney),
+        ),
+        // Finished, but in another server
+        (
+            (other_guild, MessageId::new(12), UserId::new(102)),
+            finished(),
+        ),
+        // Finished yesterday's
+        ((guild, MessageId::new(13), UserId::new(103)), yesterdays),
+    ]);
+    let opted_in = vec![100, 101, 102, 103, 104]; // 104 hasn't started
+
+    assert_eq!(
+        players_to_remind(&games, guild, &opted_in, now, Duration::ZERO),
+        vec![101, 102, 103, 104]
+    );
+}
+
+#[test]
+fn test_reminders_are_due_once_a_day_from_the_reminder_time() {
+    let at = NaiveTime::from_hms_opt(20, 0, 0).unwrap();
+    let sydney = |hour, minute| {
+        Sydney
+            .with_ymd_and_hms(2024, 6, 3, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    let today = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+    let yesterday = NaiveDate::from_ymd_opt(2024, 6, 2).unwrap();
+
+    assert!(!is_reminder_due(
+        sydney(19, 59),
+        Sydney,
+        at,
+        Some(yesterday)
+    ));
+    assert!(is_reminder_due(sydney(20, 0), Sydney, at, Some(yesterday)));
+    assert!(is_reminder_due(sydney(20, 30), Sydney, at, None));
+    assert!(!is_reminder_due(sydney(20, 30), Sydney, at, Some(today)));
+    // Too long after to still be useful, say after a restart
+    assert!(!is_reminder_due(sydney(22, 0), Sydney, at, None));
+}
+
+#[test]
+fn test_reminder_time_is_read_from_the_environment() -> Result<()> {
+    let mut config = Config::default();
+    assert_eq!(config.reminder_time, None);
+
+    config.apply_env(|key| (key == "WORDLE_REMINDER_TIME").then(|| "20:30".to_string()))?;
+    assert_eq!(config.reminder_time, NaiveTime::from_hms_opt(20, 30, 0));
+    assert!(
+        config
+            .apply_env(|key| (key == "WORDLE_REMINDER_TIME").then(|| "8pm".to_string()))
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reminders_are_per_guild_and_reversible() -> Result<()> {
+    let history = History::in_memory().await?;
+    history.remind(1, 10).await?;
+    history.remind(1, 10).await?; // Asking twice is harmless
+    history.remind(1, 11).await?;
+    history.remind(2, 10).await?;
+
+    history.stop_reminding(1, 10).await?;
+    assert_eq!(
+        history.reminded_users().await?,
+        HashMap::from([(1, HashSet::from([11])), (2, HashSet::from([10]))])
+    );
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use std::cell::Cell;
+use std::time::Duration;
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::{RetryPolicy, backoff_delay, retry_transient};
+
+#[test]
+fn test_backoff_delay_is_jittered_within_bounds() {
+    for attempt in 0..6 {
+        let base = Duration::from_secs(1 << attempt);
+
+        for _ in 0..100 {
+            let delay = backoff_delay(attempt);
+            assert!(
+                delay >= base.mul_f64(0.5),
+                "{delay:?} too short for {attempt}"
+            );
+            assert!(
+                delay <= base.mul_f64(1.5),
+                "{delay:?} too long for {attempt}"
+            );
+        }
+    }
+}
+
+fn no_wait(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        max_backoff: Duration::ZERO,
+    }
+}
+
+fn download_error() -> WordleError {
+    WordleError::Download {
+        url: "https://cdn.example/avatar.png".to_string(),
+        source: "connection reset".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_transient_errors_are_retried() {
+    let attempts = Cell::new(0);
+    let result = retry_transient(&no_wait(3), || async {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 3 {
+            Err(download_error())
+        } else {
+            Ok("downloaded")
+        }
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), "downloaded");
+    assert_eq!(attempts.get(), 3);
+
+    // Giving up once the retries run out
+    attempts.set(0);
+    let result: Result<(), _> = retry_transient(&no_wait(2), || async {
+        attempts.set(attempts.get() + 1);
+        Err(download_error())
+    })
+    .await;
+    assert!(matches!(result, Err(WordleError::Download { .. })));
+    assert_eq!(attempts.get(), 3);
+}
+
+#[tokio::test]
+async fn test_permanent_errors_are_not_retried() {
+    let attempts = Cell::new(0);
+    let result: Result<(), _> = retry_transient(&no_wait(5), || async {
+        attempts.set(attempts.get() + 1);
+        Err(WordleError::TemplateMissing {
+            path: "./data/solved.png".to_string(),
+        })
+    })
+    .await;
+
+    assert!(matches!(result, Err(WordleError::TemplateMissing { .. })));
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn test_retry_delay_is_capped() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        max_backoff: Duration::from_secs(3),
+    };
+    assert!(policy.delay(0) <= Duration::from_millis(1500));
+    assert_eq!(policy.delay(8), Duration::from_secs(3));
+}
+
+#[test]
+fn test_large_attempts_wait_the_cap() {
+    let policy = RetryPolicy {
+        max_retries: 20,
+        max_backoff: Duration::from_secs(30),
+    };
+    // 2^10 seconds without the cap
+    assert_eq!(policy.delay(10), Duration::from_secs(30));
+    // The shift saturates rather than overflowing
+    assert_eq!(policy.delay(u32::MAX), Duration::from_secs(30));
+    assert!(backoff_delay(u32::MAX) >= Duration::from_secs(1 << 29));
+}
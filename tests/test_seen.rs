@@ -0,0 +1,40 @@
+use std::time::{Duration, Instant};
+use wordle_timer_bot::seen::RecentScreenshots;
+
+const URL: &str = "https://cdn.discordapp.com/attachments/1/2/wordle.png";
+
+#[test]
+fn test_second_processing_is_short_circuited() {
+    let recent = RecentScreenshots::new(8, Duration::from_secs(600));
+    let now = Instant::now();
+
+    assert!(!recent.is_repeat(URL, now));
+    assert!(recent.is_repeat(URL, now + Duration::from_secs(1)));
+    // A different screenshot is still processed
+    assert!(!recent.is_repeat("https://cdn.discordapp.com/attachments/1/3/wordle.png", now));
+}
+
+#[test]
+fn test_screenshot_is_processed_again_after_the_window() {
+    let recent = RecentScreenshots::new(8, Duration::from_secs(600));
+    let now = Instant::now();
+
+    assert!(!recent.is_repeat(URL, now));
+    assert!(recent.is_repeat(URL, now + Duration::from_secs(599)));
+    assert!(!recent.is_repeat(URL, now + Duration::from_secs(600)));
+    assert!(recent.is_repeat(URL, now + Duration::from_secs(601)));
+}
+
+#[test]
+fn test_least_recent_screenshot_is_forgotten() {
+    let recent = RecentScreenshots::new(2, Duration::from_secs(600));
+    let now = Instant::now();
+
+    assert!(!recent.is_repeat("a", now));
+    assert!(!recent.is_repeat("b", now));
+    assert!(recent.is_repeat("a", now)); // Now the most recent
+    assert!(!recent.is_repeat("c", now)); // Forgets "b"
+
+    assert!(recent.is_repeat("a", now));
+    assert!(!recent.is_repeat("b", now));
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use serenity::prelude::{RwLock, TypeMap};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use tokio::net::TcpListener;
+use wordle_timer_bot::server::{self, ServerState};
+
+#[tokio::test]
+async fn test_health_reflects_connection() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/health", listener.local_addr()?);
+    let state = ServerState {
+        connected: Arc::new(AtomicBool::new(false)),
+        data: Arc::new(RwLock::new(TypeMap::new())),
+    };
+    tokio::spawn(server::serve(listener, state.clone()));
+
+    assert_eq!(reqwest::get(&url).await?.status(), 503);
+
+    state.connected.store(true, Ordering::Relaxed);
+    assert_eq!(reqwest::get(&url).await?.status(), 200);
+
+    Ok(())
+}
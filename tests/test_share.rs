@@ -0,0 +1,79 @@
+use chrono::NaiveDate;
+use wordle_timer_bot::share::{parse_share, wordle_number_for_date};
+
+#[test]
+fn test_parse_solved_share() {
+    let share = parse_share("Wordle 1,123 4/6\n\n⬛🟨⬛⬛⬛\n⬛⬛🟩🟨⬛\n🟩🟩🟩⬛⬛\n🟩🟩🟩🟩🟩")
+        .expect("Expected a share");
+
+    assert_eq!(share.puzzle_number, 1123);
+    assert_eq!(share.guesses, Some(4));
+    assert!(share.solved());
+    assert!(!share.hard_mode);
+    assert_eq!(share.grid.len(), 4);
+}
+
+#[test]
+fn test_parse_failed_share() {
+    let share = parse_share(
+        "Wordle 1.124 X/6\n\n⬜⬜🟨⬜⬜\n⬜🟩⬜⬜⬜\n⬜🟩🟩⬜⬜\n🟩🟩🟩⬜⬜\n🟩🟩🟩⬜🟩\n🟩🟩🟩⬜🟩",
+    )
+    .expect("Expected a share");
+
+    assert_eq!(share.puzzle_number, 1124);
+    assert_eq!(share.guesses, None);
+    assert!(!share.solved());
+}
+
+#[test]
+fn test_parse_hard_mode_share() {
+    let share =
+        parse_share("Wordle 1123 2/6*\n\n🟧🟦⬛⬛🟦\n🟧🟧🟧🟧🟧").expect("Expected a share");
+
+    assert_eq!(share.guesses, Some(2));
+    assert!(share.hard_mode);
+}
+
+#[test]
+fn test_parse_share_without_grid_or_with_text_around_it() {
+    let share = parse_share("got it!\nWordle 1123 3/6").expect("Expected a share");
+    assert_eq!(share.guesses, Some(3));
+    assert!(share.grid.is_empty());
+
+    // Squares with variation selectors, as some clients send them
+    let share = parse_share("Wordle 1123 1/6\n🟩\u{FE0F}🟩🟩🟩🟩").expect("Expected a share");
+    assert_eq!(share.grid.len(), 1);
+}
+
+#[test]
+fn test_rejects_grid_not_matching_guesses() {
+    assert_eq!(
+        parse_share("Wordle 1123 3/6\n\n⬛🟨⬛⬛⬛\n🟩🟩🟩🟩🟩"),
+        None
+    );
+}
+
+#[test]
+fn test_rejects_non_shares() {
+    for content in [
+        "I'm playing Wordle",
+        "Wordle is hard today",
+        "Wordle 1123 7/6",
+        "Wordle 1123 0/6",
+        "Wordle 1123 4/5",
+        "Wordle abc 4/6",
+    ] {
+        assert_eq!(parse_share(content), None, "{content}");
+    }
+}
+
+#[test]
+fn test_wordle_number_for_date() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+    assert_eq!(wordle_number_for_date(date(2021, 6, 19)), 0);
+    assert_eq!(wordle_number_for_date(date(2022, 1, 1)), 196);
+    assert_eq!(wordle_number_for_date(date(2024, 3, 15)), 1000);
+    assert_eq!(wordle_number_for_date(date(2024, 7, 16)), 1123);
+    assert_eq!(wordle_number_for_date(date(2021, 1, 1)), 0);
+}
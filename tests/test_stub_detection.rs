@@ -0,0 +1,67 @@
+// Runs the bot's game logic against the detection stub built without OpenCV
+#![cfg(not(feature = "cv"))]
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use wordle_timer_bot::game::{GameState, summarize_games};
+use wordle_timer_bot::{
+    DownloadLimiter, Player, SearchRegion, find_players_in_image, set_stubbed_detection,
+};
+
+/// Starts a game for every player, then finishes the games of those detection finds
+async fn play(players: usize) -> Result<HashMap<usize, GameState>> {
+    let start = Instant::now();
+    let mut games: HashMap<usize, GameState> = (0..players)
+        .map(|uid| {
+            let game = GameState {
+                active_since: Some(start),
+                ..GameState::new()
+            };
+            (uid, game)
+        })
+        .collect();
+
+    let players = (0..players)
+        .map(|uid| Player::new(uid, format!("https://cdn.discordapp.com/avatars/{uid}.png")))
+        .collect();
+    let found = find_players_in_image(
+        players,
+        "https://cdn.discordapp.com/attachments/1/2/wordle.png".to_string(),
+        &DownloadLimiter::default(),
+        1,
+        &[],
+        SearchRegion::Whole,
+        None,
+    )
+    .await?;
+
+    for player in found.players {
+        let game = games.get_mut(&player.uid()).unwrap();
+        game.pause(start + Duration::from_secs(90));
+        let total_time = game.total_active_time;
+        game.mark_completed(total_time, Utc::now());
+    }
+    Ok(games)
+}
+
+// Both outcomes in one test, as the stubbed result is shared by the whole binary
+#[tokio::test]
+async fn test_games_follow_stubbed_detection() -> Result<()> {
+    set_stubbed_detection(true);
+    let games = play(3).await?;
+    let summary = summarize_games(games.values(), Utc::now());
+    assert_eq!((summary.completed, summary.active), (3, 0));
+    assert!(
+        games
+            .values()
+            .all(|game| game.total_active_time == Duration::from_secs(90))
+    );
+
+    set_stubbed_detection(false);
+    let games = play(3).await?;
+    let summary = summarize_games(games.values(), Utc::now());
+    assert_eq!((summary.completed, summary.active), (0, 3));
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use wordle_timer_bot::error::WordleError;
+use wordle_timer_bot::validate_templates;
+
+#[test]
+fn test_validate_templates_rejects_missing_template() {
+    let error = validate_templates(&["./data/does-not-exist.png"]).unwrap_err();
+    assert!(
+        matches!(&error, WordleError::TemplateMissing { path } if path.contains("does-not-exist.png")),
+        "{error:?}"
+    );
+}
+
+#[test]
+#[cfg(feature = "cv")] // Without it templates aren't decoded
+fn test_validate_templates_rejects_corrupt_template() -> std::io::Result<()> {
+    let path = std::env::temp_dir().join("wordle_corrupt_template.png");
+    std::fs::write(&path, b"not an image")?;
+
+    let error = validate_templates(&[path.to_str().unwrap()]).unwrap_err();
+    assert!(matches!(error, WordleError::Decode { .. }), "{error:?}");
+
+    std::fs::remove_file(path)
+}
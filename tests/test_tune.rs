@@ -0,0 +1,44 @@
+// Exercises the OpenCV detection, which the stub built without it doesn't have
+#![cfg(feature = "cv")]
+
+use std::process::Command;
+
+#[test]
+fn test_tune_example_runs_on_fixtures() {
+    // Examples are built alongside the tests, into target/<profile>/examples
+    let exe = std::env::current_exe().unwrap();
+    let tune = exe
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .join("examples")
+        .join(format!("tune{}", std::env::consts::EXE_SUFFIX));
+    assert!(
+        tune.exists(),
+        "{} is missing, run the whole test suite so examples are built",
+        tune.display()
+    );
+
+    let output = Command::new(&tune)
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/tuning"
+        ))
+        .args(["--scales", "0.8:1.3:20", "--thresholds", "0.8:0.95:0.05"])
+        .env_remove("DISCORD_TOKEN")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{stdout}\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert!(
+        stdout.contains("4 positive and 4 negative pair(s)"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("precision"), "{stdout}");
+    assert!(stdout.contains("Best: scales 0.80-1.30/20"), "{stdout}");
+}